@@ -1,12 +1,40 @@
-use crate::{words, history::{self, TestResult}};
+use crate::{words::{self, DrillPreset}, clock::{Clock, SystemClock}, export, history::{self, TestResult}, keymap::{Action, KeyCode, KeyEvent, KeyModifiers, Keymap, Scope}, paths, settings::{self, PasteHandling, Settings, SnapshotInterval, TextSource, TimerStart}, snapshot::{self, Snapshot}, stats, svg, text, theme::{self, Theme}};
 use anyhow::Result;
 use chrono::Local;
-use ratatui::widgets::TableState;
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub type AppResult<T> = Result<T>;
 
+/// How long the results screen takes to count up to the final numbers.
+const RESULT_ANIMATION: Duration = Duration::from_millis(500);
+/// How long an option stays highlighted after it changes.
+const TOGGLE_FLASH: Duration = Duration::from_millis(400);
+/// How long the "fix the errors" cue flashes after a key that couldn't finish the test, and
+/// how fast it blinks; after that it stays lit until the errors are fixed.
+const BLOCKED_FLASH: Duration = Duration::from_millis(900);
+const BLOCKED_BLINK: Duration = Duration::from_millis(150);
+/// How long the screen flashes when a test ends, with the completion cue's flash on.
+const COMPLETION_FLASH: Duration = Duration::from_millis(300);
+/// Input poll timeouts while something is moving on screen and while it isn't.
+const ACTIVE_POLL: Duration = Duration::from_millis(16);
+const IDLE_POLL: Duration = Duration::from_millis(100);
+/// Choices for how often a running test is saved, on the settings screen.
+const SNAPSHOT_INTERVALS: [SnapshotInterval; 8] = [
+    SnapshotInterval::Seconds(0),
+    SnapshotInterval::Seconds(1),
+    SnapshotInterval::Seconds(2),
+    SnapshotInterval::Seconds(5),
+    SnapshotInterval::Seconds(10),
+    SnapshotInterval::Keystrokes(25),
+    SnapshotInterval::Keystrokes(50),
+    SnapshotInterval::Keystrokes(100),
+];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
     Welcome,
@@ -14,14 +42,157 @@ pub enum AppMode {
     Results,
     History,
     HistoryDetails,
+    HistoryCompare,
+    Settings,
+    Review,
+    Tutorial,
+    Drills,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TestMode {
     Words(usize),
     Time(u64), // Duration in seconds
 }
 
+/// Which per-run value the history trend chart plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryTrend {
+    #[default]
+    Wpm,
+    Accuracy,
+}
+
+/// What the main chart on the results screen shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartView {
+    #[default]
+    Wpm,
+    Accuracy,
+    Latency,
+    Keys, // Keyboard heatmap of where the errors landed
+}
+
+impl ChartView {
+    pub fn next(self) -> Self {
+        match self {
+            ChartView::Wpm => ChartView::Accuracy,
+            ChartView::Accuracy => ChartView::Latency,
+            ChartView::Latency => ChartView::Keys,
+            ChartView::Keys => ChartView::Wpm,
+        }
+    }
+}
+
+/// Words generated at the start of a test, and added each time the text runs low.
+const WORD_BATCH: usize = 50;
+/// More words are added once fewer than this many are left untyped.
+const REFILL_AHEAD: usize = 25;
+
+/// Word counts offered in Words mode.
+const WORD_COUNTS: [usize; 4] = [10, 25, 50, 100];
+/// Time limits in seconds offered in Time mode.
+const TIME_LIMITS: [u64; 3] = [15, 30, 60];
+/// WPM goals offered on the settings screen; 0 means no goal.
+const WPM_GOALS: [u32; 17] = [0, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120, 130, 140, 150, 175, 200];
+/// Warmup lengths in words offered on the settings screen.
+const WARMUP_WORDS: [usize; 5] = [0, 1, 2, 3, 5];
+/// Look-ahead window sizes offered on the settings screen.
+const LOOKAHEAD_CHARS: [usize; 4] = [0, 3, 5, 10];
+/// How far behind the cursor typed text starts to fade, offered on the settings screen.
+const FADE_TYPED_CHARS: [usize; 4] = [0, 3, 5, 10];
+/// Metronome tempos in beats per minute offered on the settings screen; 0 turns it off. A beat
+/// is a keystroke, so 300 is 60 WPM.
+const METRONOME_BPMS: [u32; 8] = [0, 60, 120, 180, 240, 300, 360, 420];
+/// Longest the metronome stays lit on each beat; faster tempos light it for a quarter of a beat.
+const METRONOME_PULSE: Duration = Duration::from_millis(100);
+
+/// Options on the settings screen, in display order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingOption {
+    Mode,
+    Length,
+    Source,
+    Goal,
+    Punctuation,
+    PunctuationLevel,
+    Numbers,
+    Cursor,
+    Warmup,
+    TimerStart,
+    FixedCursor,
+    Lookahead,
+    FadeTyped,
+    Metronome,
+    RollingAverage,
+    Smoothing,
+    FocusMode,
+    FinishWord,
+    CompleteWords,
+    FinishOnSpace,
+    FinishCue,
+    HideResults,
+    FixErrors,
+    SuddenDeath,
+    EnterAsSpace,
+    Paste,
+    AutoSave,
+    PracticeLoop,
+    Beginner,
+    Combos,
+    KeyboardLayout,
+    Theme,
+}
+
+impl SettingOption {
+    pub const ALL: [SettingOption; 32] = [
+        SettingOption::Mode,
+        SettingOption::Length,
+        SettingOption::Source,
+        SettingOption::Goal,
+        SettingOption::Punctuation,
+        SettingOption::PunctuationLevel,
+        SettingOption::Numbers,
+        SettingOption::Cursor,
+        SettingOption::Warmup,
+        SettingOption::TimerStart,
+        SettingOption::FixedCursor,
+        SettingOption::Lookahead,
+        SettingOption::FadeTyped,
+        SettingOption::Metronome,
+        SettingOption::RollingAverage,
+        SettingOption::Smoothing,
+        SettingOption::FocusMode,
+        SettingOption::FinishWord,
+        SettingOption::CompleteWords,
+        SettingOption::FinishOnSpace,
+        SettingOption::FinishCue,
+        SettingOption::HideResults,
+        SettingOption::FixErrors,
+        SettingOption::SuddenDeath,
+        SettingOption::EnterAsSpace,
+        SettingOption::Paste,
+        SettingOption::AutoSave,
+        SettingOption::PracticeLoop,
+        SettingOption::Beginner,
+        SettingOption::Combos,
+        SettingOption::KeyboardLayout,
+        SettingOption::Theme,
+    ];
+
+    /// The toggles that also have a hotkey on the welcome screen.
+    pub const WELCOME: [SettingOption; 8] = [
+        SettingOption::Beginner,
+        SettingOption::SuddenDeath,
+        SettingOption::Punctuation,
+        SettingOption::Numbers,
+        SettingOption::Cursor,
+        SettingOption::FinishWord,
+        SettingOption::EnterAsSpace,
+        SettingOption::PracticeLoop,
+    ];
+}
+
 impl std::fmt::Display for TestMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -31,11 +202,27 @@ impl std::fmt::Display for TestMode {
     }
 }
 
-pub struct App {
+impl TestMode {
+    /// Reads back a mode as it's recorded in history, e.g. "Words: 25". Drill labels aren't modes.
+    pub fn parse(mode: &str) -> Option<TestMode> {
+        if let Some(words) = mode.strip_prefix("Words: ") {
+            return words.parse().ok().map(TestMode::Words);
+        }
+        let secs = mode.strip_prefix("Time: ")?.strip_suffix('s')?;
+        secs.parse().ok().map(TestMode::Time)
+    }
+}
+
+/// Pages in the first-launch tutorial.
+pub const TUTORIAL_PAGES: usize = 4;
+
+pub struct App<C: Clock = SystemClock> {
     pub running: bool,
+    pub dirty: bool, // Something on screen changed since the last draw
     pub mode: AppMode,
     pub input: String,
     pub target_text: String,
+    pub pending_words: usize, // Words of a Words test not generated yet; added as the text runs low
     pub start_time: Option<Instant>,
     pub end_time: Option<Instant>,
     pub cursor_position: usize,
@@ -43,410 +230,3449 @@ pub struct App {
     pub test_mode: TestMode,
     pub include_punctuation: bool,
     pub include_numbers: bool,
+    pub settings: Settings,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    pub settings_selection: usize, // Index into `SettingOption::ALL` on the settings screen
+    pub toggle_flash: Option<(SettingOption, Instant)>, // Option that just changed, for highlighting
     // Stats
     pub total_correct_strokes: usize,
     pub total_incorrect_strokes: usize,
     // Analytics
     pub wpm_history: Vec<(f64, f64)>, // (time, wpm)
     pub error_points: Vec<(f64, f64)>, // (time, wpm_at_error)
+    pub error_positions: Vec<usize>, // Character of the target text each of `error_points` was typed at
     pub last_wpm_sample: Option<Instant>,
+    pub keystroke_times: Vec<f64>, // Seconds since start of every key press
+    pub char_times: Vec<f64>, // Seconds since start when each character of the input was typed, from `measured_from`
+    pub time_up: bool, // Time mode expired, waiting for the current word to be finished
+    pub missed_words: BTreeSet<usize>, // Indices of target words that had an error
+    pub warming_up: bool, // Typing the unscored warmup words at the start of the text
+    pub measured_from: usize, // Characters of input typed during the warmup, left out of the score
+    pub pasted_ranges: Vec<Range<usize>>, // Characters of the input inserted by pasting, left out of WPM
+    pub correct_chars: usize, // Characters of the input from `measured_from` on that match the text, kept up to date per key
+    pub blocked_since: Option<Instant>, // Last key typed while `require_correct_words` held the finished text open
+    pub drill_label: Option<String>, // Recorded as the mode when the text is a drill rather than a normal test
+    pub status_message: Option<String>, // One-off notice shown until the next key press
+    pub last_result: Option<TestResult>, // The most recently finished run
+    pub result_recorded: bool, // Whether the last run was long enough to be saved to history
+    pub result_animation_start: Option<Instant>, // Set while the results are counting up
+    pub completion_flash: Option<Instant>, // When the finished test's screen flash started
+    pub results_revealed: bool, // Whether the results screen shows its numbers yet, with `hide_results` on
+    pub ring_bell: bool, // The terminal bell is due, rung by the main loop after the next draw
+    pub metronome_start: Option<Instant>, // When the metronome's first beat fell, while it's ticking
+    pub metronome_beats: u64, // Metronome beats so far, to ring the bell once on each
+    pub review_scroll: usize, // First line shown when reviewing the finished text
+    pub tutorial_page: usize, // Page of the first-launch tutorial on screen
+    pub drill_selection: usize, // Index into `DrillPreset::ALL` in the drills menu
+    pub error_review: Option<usize>, // Error of the history entry being stepped through in its details
+    pub auto_restart_at: Option<Instant>, // When the practice loop starts the next test
+    pub word_preview: Option<String>, // Text shuffled up on the welcome screen for the next test
+    pub pending_snapshot: Option<Snapshot>, // Interrupted test found at launch, offered for resuming
+    pub last_snapshot_at: Option<Instant>, // When the running test was last saved; `None` if it hasn't been
+    pub keys_since_snapshot: usize, // Keystrokes since the running test was last saved
     // History
     pub history: Vec<TestResult>,
-    pub history_state: TableState,
+    pub history_disabled: bool, // Set by `--no-history` or the setting; history is never loaded or saved
+    pub no_history_flag: bool, // `--no-history` was given, so it holds for every profile
+    pub theme_override: bool, // Theme came from `--theme` or `TYPESTORM_THEME`, not the profile's settings
     pub selected_history_index: usize,
+    pub compare_index: Option<usize>, // History entry marked for comparison
+    pub history_trend: HistoryTrend,
+    pub chart_view: ChartView, // Chart shown on the results screen
+    pub session_results: Vec<TestResult>, // Runs recorded since launch, for the quit summary
+    pub session_count: usize, // Tests finished since launch, recorded or not
+    pub exported_files: Vec<PathBuf>, // Charts and run data written this session, listed on exit
+    pub clock: C,
 }
 
 impl Default for App {
     fn default() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self::load(false)
+    }
+
+    /// Loads saved settings and history. With `no_history` (or the `no_history` setting) the
+    /// history file is left untouched for the whole run.
+    pub fn load(no_history: bool) -> Self {
+        // A true first run has neither settings nor history yet
+        let first_run = !settings::get_settings_file_path().is_ok_and(|path| path.exists());
+        let mut app = Self { no_history_flag: no_history, ..Self::default() };
+        app.load_profile();
+        if first_run && app.history.is_empty() && !app.settings.seen_tutorial {
+            app.mode = AppMode::Tutorial;
+        }
+        app
+    }
+}
+
+impl<C: Clock> App<C> {
+    /// Reads the settings, history and any interrupted test of the active profile.
+    fn load_profile(&mut self) {
+        let settings = settings::load_settings().unwrap_or_default();
+        self.test_mode = settings.test_mode;
+        self.include_punctuation = settings.include_punctuation;
+        self.include_numbers = settings.include_numbers;
+        self.settings = settings;
+        self.history_disabled = self.no_history_flag || self.settings.no_history;
+        self.history = Vec::new();
+        self.selected_history_index = 0;
+        // A mark is an index into the old history
+        self.compare_index = None;
+        self.pending_snapshot = None;
+        // Load history, and any test that was cut short last time
+        if !self.history_disabled {
+            if let Ok((history, unreadable)) = history::load_history() {
+                self.history = history;
+                if unreadable > 0 {
+                    self.status_message = Some(format!(
+                        "Skipped {} unreadable line{} in the history file; it's left as it is until fixed",
+                        unreadable,
+                        if unreadable == 1 { "" } else { "s" }
+                    ));
+                }
+            }
+            self.pending_snapshot = snapshot::load_snapshot();
+        }
+    }
+
+    /// Moves on to the next saved profile (the default one comes first) and loads its
+    /// settings and history. Profiles are created with `--profile <name>`.
+    fn next_profile(&mut self) {
+        let mut profiles: Vec<Option<String>> = std::iter::once(None).chain(paths::profiles().into_iter().map(Some)).collect();
+        let current = paths::profile();
+        if !profiles.contains(&current) {
+            // Started with `--profile` but nothing saved yet
+            profiles.push(current.clone());
+            profiles.sort();
+        }
+        let i = profiles.iter().position(|p| *p == current).unwrap_or(0);
+        self.switch_profile(profiles[(i + 1) % profiles.len()].clone());
+    }
+
+    /// Makes `profile` the active one and reloads everything from its files.
+    pub fn switch_profile(&mut self, profile: Option<String>) {
+        self.save_settings();
+        paths::set_profile(profile);
+        self.load_profile();
+        if !self.theme_override {
+            // A profile without a saved theme gets the default, not the last profile's
+            self.theme = self.settings.theme.as_deref().and_then(Theme::by_name).unwrap_or_default();
+        }
+        let (keymap, warnings) = Keymap::from_config(&self.settings.keybindings);
+        self.keymap = keymap;
+        self.word_preview = None;
+        self.session_results.clear();
+        self.status_message = Some(match warnings.first() {
+            Some(warning) => format!("Switched to profile {} ({})", self.profile_name(), warning),
+            None => format!("Switched to profile {}", self.profile_name()),
+        });
+    }
+
+    /// Name of the active profile for display.
+    pub fn profile_name(&self) -> String {
+        paths::profile().unwrap_or_else(|| "default".to_string())
+    }
+}
+
+impl<C: Clock> App<C> {
+    /// A fresh app driven by `clock`, without loading any saved history or settings.
+    pub fn with_clock(clock: C) -> Self {
         Self {
             running: true,
+            dirty: true,
             mode: AppMode::Welcome,
             input: String::new(),
             target_text: String::new(),
+            pending_words: 0,
             start_time: None,
             end_time: None,
             cursor_position: 0,
             test_mode: TestMode::Words(10),
             include_punctuation: false,
             include_numbers: false,
+            settings: Settings::default(),
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            settings_selection: 0,
+            toggle_flash: None,
             total_correct_strokes: 0,
             total_incorrect_strokes: 0,
             wpm_history: Vec::new(),
             error_points: Vec::new(),
+            error_positions: Vec::new(),
             last_wpm_sample: None,
+            keystroke_times: Vec::new(),
+            char_times: Vec::new(),
+            time_up: false,
+            missed_words: BTreeSet::new(),
+            warming_up: false,
+            measured_from: 0,
+            pasted_ranges: Vec::new(),
+            correct_chars: 0,
+            blocked_since: None,
+            drill_label: None,
+            status_message: None,
+            last_result: None,
+            result_recorded: false,
+            result_animation_start: None,
+            completion_flash: None,
+            results_revealed: true,
+            ring_bell: false,
+            metronome_start: None,
+            metronome_beats: 0,
+            review_scroll: 0,
+            tutorial_page: 0,
+            drill_selection: 0,
+            error_review: None,
+            auto_restart_at: None,
+            word_preview: None,
+            pending_snapshot: None,
+            last_snapshot_at: None,
+            keys_since_snapshot: 0,
             history: Vec::new(),
-            history_state: TableState::default(),
+            history_disabled: false,
+            no_history_flag: false,
+            theme_override: false,
             selected_history_index: 0,
+            compare_index: None,
+            history_trend: HistoryTrend::default(),
+            chart_view: ChartView::default(),
+            session_results: Vec::new(),
+            session_count: 0,
+            exported_files: Vec::new(),
+            clock,
         }
     }
-}
 
-impl App {
-    pub fn new() -> Self {
-        let mut app = Self::default();
-        // Load history
-        if let Ok(history) = history::load_history() {
-            app.history = history;
+    pub fn tick(&mut self) {
+        // The clock, the count-up, the flash and the practice loop countdown change the screen
+        // on their own; their last frame is drawn by the tick that ends them below
+        if self.mode == AppMode::Typing || self.animating() || self.auto_restart_at.is_some() {
+            self.dirty = true;
+        }
+        if self.toggle_flash.is_some() && self.flashing_option().is_none() {
+            self.toggle_flash = None;
+        }
+        if self.completion_flash.is_some() && !self.completion_flash_active() {
+            self.completion_flash = None;
+        }
+        self.tick_metronome();
+        if self.mode == AppMode::Results && self.result_animation_progress() >= 1.0 {
+            self.result_animation_start = None;
+        }
+        if self.mode == AppMode::Results && self.auto_restart_remaining() == Some(Duration::ZERO) {
+            self.start_typing();
         }
-        app
-    }
 
-    pub fn tick(&mut self) {
-        if self.mode == AppMode::Typing {
-            // Sample WPM every 1 second
-            if let Some(start) = self.start_time {
-                let now = Instant::now();
-                let elapsed = now.duration_since(start).as_secs_f64();
-                
-                // Only start sampling after at least 1 second has passed to avoid inflated initial WPM
-                if elapsed >= 1.0 {
-                    let should_sample = match self.last_wpm_sample {
-                        None => true,
-                        Some(last) => now.duration_since(last).as_secs_f64() >= 1.0,
-                    };
+        // Nothing else moves on its own outside a test, and the warmup isn't timed
+        if self.mode != AppMode::Typing || self.warming_up {
+            return;
+        }
 
-                    if should_sample {
-                        let current_wpm = self.calculate_wpm();
-                        self.wpm_history.push((elapsed, current_wpm));
-                        self.last_wpm_sample = Some(now);
-                    }
+        // Sample WPM every 1 second
+        if let Some(start) = self.start_time {
+            let now = self.clock.now();
+            let elapsed = now.duration_since(start).as_secs_f64();
+            
+            // Only start sampling after at least 1 second has passed to avoid inflated initial WPM
+            if elapsed >= 1.0 {
+                let should_sample = match self.last_wpm_sample {
+                    None => true,
+                    Some(last) => now.duration_since(last).as_secs_f64() >= 1.0,
+                };
+
+                if should_sample {
+                    let current_wpm = self.calculate_wpm();
+                    self.wpm_history.push((elapsed, current_wpm));
+                    self.last_wpm_sample = Some(now);
                 }
             }
+        }
 
-            if let TestMode::Time(duration) = self.test_mode {
-                if let Some(start) = self.start_time {
-                    if start.elapsed().as_secs() >= duration {
-                        self.end_time = Some(Instant::now());
-                        // Capture final sample (only if at least 1 second has passed)
-                        let elapsed = start.elapsed().as_secs_f64();
-                        if elapsed >= 1.0 {
-                            let current_wpm = self.calculate_wpm();
-                            self.wpm_history.push((elapsed, current_wpm));
-                        }
-                        
-                        self.save_result();
-                        self.mode = AppMode::Results;
+        if let TestMode::Time(duration) = self.test_mode {
+            if let Some(start) = self.start_time {
+                if !self.time_up && self.clock.now().duration_since(start).as_secs() >= duration {
+                    let mid_word = !self.input.is_empty() && !self.input.ends_with(' ');
+                    if self.settings.finish_word_on_timeout && mid_word {
+                        // Let the current word be finished; the next space ends the test
+                        self.time_up = true;
+                    } else {
+                        self.finish_test();
                     }
                 }
             }
         }
+
+        self.save_snapshot_if_due();
+    }
+
+    /// Stops the clock, captures the final WPM sample, records the result and shows it.
+    fn finish_test(&mut self) {
+        self.clear_snapshot();
+        self.end_time = Some(self.clock.now());
+        // Capture final sample (only if at least 1 second has passed)
+        if let Some(start) = self.start_time {
+            let elapsed = self.clock.now().duration_since(start).as_secs_f64();
+            if elapsed >= 1.0 {
+                let current_wpm = self.calculate_wpm();
+                // A test that ends on a sampling tick already has a sample for this moment
+                if self.wpm_history.last().is_some_and(|&(t, _)| elapsed - t < stats::SAME_SAMPLE_SECS) {
+                    self.wpm_history.pop();
+                }
+                self.wpm_history.push((elapsed, current_wpm));
+            }
+        }
+        self.session_count += 1;
+        self.save_result();
+        self.mode = AppMode::Results;
+        let cue = self.settings.completion_cue;
+        self.ring_bell = cue.bell();
+        self.completion_flash = cue.flash().then(|| self.clock.now());
+        self.results_revealed = !self.settings.hide_results;
+        if self.results_revealed {
+            self.show_results();
+        }
     }
 
-    pub fn start_typing(&mut self) {
-        let count = match self.test_mode {
+    /// Starts the results count-up, and the practice loop's countdown to the next test. Hidden
+    /// results wait for this until they're revealed.
+    fn show_results(&mut self) {
+        self.results_revealed = true;
+        self.result_animation_start = Some(self.clock.now());
+        if self.settings.practice_loop {
+            self.auto_restart_at = Some(self.clock.now() + Duration::from_secs(self.settings.practice_loop_delay_secs));
+        }
+    }
+
+    /// Number of words a new test generates for the current mode.
+    pub fn generated_word_count(&self) -> usize {
+        match self.test_mode {
             TestMode::Words(n) => n,
             TestMode::Time(_) => 100, // Generate enough words for time mode, can refill if needed
+        }
+    }
+
+    /// Expected characters per word (excluding spaces) with the current toggles.
+    pub fn expected_word_length(&self) -> f64 {
+        if self.settings.text_source == TextSource::Sentences {
+            return words::sentence_word_length();
+        }
+        let numbers = self.include_numbers.then_some(self.settings.numbers);
+        let punctuation = self.include_punctuation.then_some(self.settings.punctuation_level);
+        words::average_word_length(words::word_pool(self.settings.beginner), punctuation, numbers)
+    }
+
+    /// Sets everything up for a first-time typist: common short words only, no punctuation or
+    /// numbers, and the shortest Words test. Each part can still be changed afterwards.
+    pub fn apply_beginner_preset(&mut self) {
+        self.settings.beginner = true;
+        self.include_punctuation = false;
+        self.include_numbers = false;
+        self.test_mode = TestMode::Words(WORD_COUNTS[0]);
+    }
+
+    /// Fresh text for the start of a test with the current mode and options. Long tests get
+    /// their first [`WORD_BATCH`] words; the rest are added while typing.
+    fn generate_text(&self) -> String {
+        self.generate_words(self.generated_word_count().min(WORD_BATCH))
+    }
+
+    /// `count` random words with the current options, separated by spaces. Sentences are kept
+    /// whole, so they can run a few words over.
+    fn generate_words(&self, count: usize) -> String {
+        if self.settings.text_source == TextSource::Sentences {
+            return words::get_sentences(count).join(" ");
+        }
+        let numbers = self.include_numbers.then_some(self.settings.numbers);
+        let punctuation = self.include_punctuation.then_some(self.settings.punctuation_level);
+        words::get_random_words(words::word_pool(self.settings.beginner), count, punctuation, numbers).join(" ")
+    }
+
+    /// Appends the next batch of words once the untyped part of the text runs low: in Words mode
+    /// until the test has all its words, in Time mode for as long as the clock runs. Drills keep
+    /// their own text.
+    fn refill_text(&mut self) {
+        if self.drill_label.is_some() {
+            return;
+        }
+        let batch = match self.test_mode {
+            TestMode::Words(_) => self.pending_words.min(WORD_BATCH),
+            TestMode::Time(_) => WORD_BATCH,
         };
-        
-        let words = words::get_random_words(count, self.include_punctuation, self.include_numbers);
-        self.target_text = words.join(" ");
-        self.input = String::new();
+        let ahead = self.target_text.split(' ').count().saturating_sub(self.completed_words());
+        if batch == 0 || ahead >= REFILL_AHEAD {
+            return;
+        }
+        let more = self.generate_words(batch);
+        let typed_past_end = self.cursor_position >= text::grapheme_count(&self.target_text);
+        self.target_text.push(' ');
+        self.target_text.push_str(&more);
+        if typed_past_end {
+            // Extra characters typed past the old end now have text to match against
+            self.correct_chars = self.count_correct_chars();
+        }
+        if let TestMode::Words(_) = self.test_mode {
+            self.pending_words = self.pending_words.saturating_sub(more.split(' ').count());
+        }
+    }
+
+    /// Words in the whole test, including any not generated yet.
+    pub fn total_words(&self) -> usize {
+        self.target_text.split(' ').count() + self.pending_words
+    }
+
+    /// Generates the next test's text ahead of time so it can be looked at, and shuffled again,
+    /// on the welcome screen.
+    pub fn shuffle_preview(&mut self) {
+        self.word_preview = Some(self.generate_text());
+    }
+
+    /// Starts a test on the previewed text if there is one, otherwise on fresh text.
+    pub fn start_typing(&mut self) {
+        self.target_text = match self.word_preview.take() {
+            Some(text) => text,
+            None => self.generate_text(),
+        };
+        self.pending_words = match self.test_mode {
+            TestMode::Words(n) => n.saturating_sub(self.target_text.split(' ').count()),
+            TestMode::Time(_) => 0,
+        };
+        self.drill_label = None;
+        self.mode = AppMode::Typing;
+        self.reset_run();
+    }
+
+    /// Starts a new test one step harder than the current settings and says what changed.
+    fn start_harder_test(&mut self) {
+        let change = self.escalate();
+        self.save_settings();
+        self.start_typing();
+        self.status_message = Some(change);
+    }
+
+    /// Makes the test one step harder: longer until it's at the longest length offered, then
+    /// with punctuation, then with numbers. Returns what changed, for the footer.
+    fn escalate(&mut self) -> String {
+        let longer = match self.test_mode {
+            TestMode::Words(n) => WORD_COUNTS.iter().find(|&&count| count > n).map(|&count| TestMode::Words(count)),
+            TestMode::Time(s) => TIME_LIMITS.iter().find(|&&limit| limit > s).map(|&limit| TestMode::Time(limit)),
+        };
+        let change = if let Some(mode) = longer {
+            self.test_mode = mode;
+            match mode {
+                TestMode::Words(n) => format!("Harder: now {} words", n),
+                TestMode::Time(s) => format!("Harder: now {}s", s),
+            }
+        } else if !self.include_punctuation {
+            self.include_punctuation = true;
+            "Harder: now with punctuation".to_string()
+        } else if !self.include_numbers {
+            self.include_numbers = true;
+            "Harder: now with numbers".to_string()
+        } else {
+            return "Already at the hardest settings".to_string();
+        };
+        // A shuffled preview was made for the old settings
+        self.word_preview = None;
+        change
+    }
+
+    /// Starts a drill of made-up and real words built around the letter combinations picked in
+    /// the settings, as many words as a normal test.
+    fn start_combo_drill(&mut self) {
+        let combos = &self.settings.drill_combos;
+        self.target_text = words::combo_words(combos, self.generated_word_count()).join(" ");
+        self.pending_words = 0;
+        self.drill_label = Some(if combos.is_empty() {
+            "Drill: combos (all)".to_string()
+        } else {
+            format!("Drill: combos {}", combos.join(", "))
+        });
+        self.word_preview = None;
+        self.mode = AppMode::Typing;
+        self.reset_run();
+    }
+
+    /// Starts one of the fixed touch-typing exercises, as long as a normal test and saved under
+    /// its own name, e.g. "Drill: home row".
+    fn start_drill_preset(&mut self, preset: DrillPreset) {
+        self.target_text = preset.text(self.settings.keyboard_layout, self.generated_word_count());
+        self.pending_words = 0;
+        self.drill_label = Some(format!("Drill: {}", preset.name().to_lowercase()));
+        self.word_preview = None;
+        self.mode = AppMode::Typing;
+        self.reset_run();
+    }
+
+    /// Starts the history entry in the details view over with exactly the same text, mode and
+    /// options, or explains why it can't be when the run was saved without its text.
+    fn repeat_history_entry(&mut self) {
+        let Some(result) = self.selected_history_entry().and_then(|i| self.history.get(i)) else {
+            return;
+        };
+        if result.target_text.is_empty() {
+            self.status_message = Some("This run was saved without its text, so it can't be repeated".to_string());
+            return;
+        }
+        self.target_text = result.target_text.clone();
+        match TestMode::parse(&result.mode) {
+            Some(mode) => {
+                self.test_mode = mode;
+                self.include_punctuation = result.punctuation;
+                self.include_numbers = result.numbers;
+                self.drill_label = None;
+            }
+            // Drills keep their label, so the repeat is filed with them
+            None => self.drill_label = Some(result.mode.clone()),
+        }
+        self.pending_words = 0;
+        self.word_preview = None;
+        self.mode = AppMode::Typing;
+        self.reset_run();
+    }
+
+    /// Starts a drill of the words that had errors in the last run, or explains why there is none.
+    fn start_missed_words_drill(&mut self) {
+        let target_words: Vec<&str> = self.target_text.split(' ').collect();
+        let missed: Vec<String> = self
+            .missed_words
+            .iter()
+            .filter_map(|&i| target_words.get(i))
+            .map(|w| w.to_string())
+            .collect();
+
+        if missed.is_empty() {
+            self.status_message = Some("Perfect run — nothing to drill.".to_string());
+            return;
+        }
+
+        self.target_text = words::drill_words(&missed, 3).join(" ");
+        self.pending_words = 0;
+        self.drill_label = Some("Drill: missed words".to_string());
         self.mode = AppMode::Typing;
-        self.start_time = None; // Don't start timer yet - wait for first keystroke
+        self.reset_run();
+    }
+
+    /// Clears all progress on the current text so it can be typed again from the start.
+    fn reset_run(&mut self) {
+        self.input = String::new();
         self.end_time = None;
         self.cursor_position = 0;
         self.total_correct_strokes = 0;
         self.total_incorrect_strokes = 0;
         self.wpm_history = Vec::new();
         self.error_points = Vec::new();
+        self.error_positions.clear();
+        self.last_wpm_sample = None;
+        self.keystroke_times.clear();
+        self.char_times.clear();
+        self.time_up = false;
+        self.missed_words.clear();
+        self.auto_restart_at = None;
+        // The warmup only runs when there's text left to score after it
+        let warmup = self.settings.warmup_words;
+        self.warming_up = warmup > 0 && self.target_text.split(' ').count() > warmup;
+        self.measured_from = 0;
+        self.pasted_ranges.clear();
+        self.correct_chars = 0;
+        self.blocked_since = None;
+        self.start_clock_if_on_start();
+        self.clear_snapshot();
+    }
+
+    /// Starts the clock right away with the `timer_start` setting on `OnStart`; otherwise it
+    /// waits for the first keystroke. The warmup is never timed.
+    fn start_clock_if_on_start(&mut self) {
+        self.start_time = match self.settings.timer_start {
+            TimerStart::OnStart if !self.warming_up => Some(self.clock.now()),
+            _ => None,
+        };
+    }
+
+    /// Ends the warmup once its last word and the space after it are typed: the clock and all
+    /// counters start over, and only what's typed from here on is scored.
+    fn end_warmup_if_done(&mut self) {
+        if !self.warming_up || self.completed_words() < self.settings.warmup_words {
+            return;
+        }
+        self.warming_up = false;
+        self.measured_from = self.cursor_position;
+        // Anything pasted during the warmup is already left out with the rest of it
+        self.pasted_ranges.clear();
+        self.correct_chars = 0;
+        self.start_clock_if_on_start();
+        self.total_correct_strokes = 0;
+        self.total_incorrect_strokes = 0;
+        self.wpm_history.clear();
+        self.error_points.clear();
+        self.error_positions.clear();
         self.last_wpm_sample = None;
+        self.keystroke_times.clear();
+        self.char_times.clear();
+        self.missed_words.clear();
+    }
+
+    /// The running test as a [`Snapshot`], or `None` before its first keystroke.
+    pub fn snapshot(&self) -> Option<Snapshot> {
+        // The clock may already be running with `timer_start` on `OnStart`
+        if self.mode != AppMode::Typing || self.keystroke_times.is_empty() {
+            return None;
+        }
+        Some(Snapshot {
+            test_mode: self.test_mode,
+            punctuation: self.include_punctuation,
+            numbers: self.include_numbers,
+            drill_label: self.drill_label.clone(),
+            text_source: self.settings.text_source,
+            target_text: self.target_text.clone(),
+            pending_words: self.pending_words,
+            input: self.input.clone(),
+            elapsed_secs: self.elapsed()?.as_secs_f64(),
+            time_up: self.time_up,
+            total_correct_strokes: self.total_correct_strokes,
+            total_incorrect_strokes: self.total_incorrect_strokes,
+            wpm_history: self.wpm_history.clone(),
+            error_points: self.error_points.clone(),
+            error_positions: self.error_positions.clone(),
+            keystroke_times: self.keystroke_times.clone(),
+            char_times: self.char_times.clone(),
+            missed_words: self.missed_words.clone(),
+            warming_up: self.warming_up,
+            measured_from: self.measured_from,
+            pasted_ranges: self.pasted_ranges.clone(),
+        })
     }
 
-    pub fn handle_events(&mut self) -> AppResult<()> {
-        if event::poll(Duration::from_millis(16))? {
-            if let Event::Key(key) = event::read()? {
-                self.handle_key_event(key);
+    /// Saves the running test as often as the `snapshot_interval` setting asks, and right away
+    /// the first time. Nothing is written with history or the setting off.
+    fn save_snapshot_if_due(&mut self) {
+        if self.history_disabled || !self.settings.snapshot_interval.enabled() {
+            return;
+        }
+        let now = self.clock.now();
+        if let Some(last) = self.last_snapshot_at {
+            let due = match self.settings.snapshot_interval {
+                SnapshotInterval::Seconds(secs) => now.duration_since(last) >= Duration::from_secs(secs),
+                SnapshotInterval::Keystrokes(keys) => self.keys_since_snapshot >= keys,
+            };
+            if !due {
+                return;
+            }
+        }
+        if let Some(snapshot) = self.snapshot() {
+            if snapshot::save_snapshot(&snapshot).is_ok() {
+                self.last_snapshot_at = Some(now);
+                self.keys_since_snapshot = 0;
+                // The file on disk now holds this test, not the one found at launch
+                self.pending_snapshot = None;
             }
         }
-        Ok(())
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
+    /// Moves through the errors of the history entry in the details view. Stepping back from the
+    /// first one leaves the review; runs saved without their text have nothing to step through.
+    fn step_error_review(&mut self, forward: bool) {
+        let Some(result) = self.selected_history_entry().and_then(|i| self.history.get(i)) else {
+            return;
+        };
+        let errors = result.reviewable_errors();
+        if errors == 0 {
+            self.status_message = Some(if result.target_text.is_empty() {
+                "This run was saved without its text, so its errors can't be reviewed".to_string()
+            } else {
+                "No errors in this run".to_string()
+            });
+            return;
+        }
+        self.error_review = match (self.error_review, forward) {
+            (None, true) => Some(0),
+            (Some(i), true) => Some((i + 1).min(errors - 1)),
+            (Some(i), false) => i.checked_sub(1),
+            (None, false) => None,
+        };
+    }
+
+    /// Removes the saved snapshot of the running test, if one was written.
+    fn clear_snapshot(&mut self) {
+        if self.last_snapshot_at.take().is_some() {
+            let _ = snapshot::clear_snapshot();
+        }
+    }
+
+    /// Picks the interrupted test found at launch back up where its snapshot left off.
+    pub fn resume_snapshot(&mut self) {
+        let Some(saved) = self.pending_snapshot.take() else {
+            return;
+        };
+        self.reset_run();
+        self.test_mode = saved.test_mode;
+        self.include_punctuation = saved.punctuation;
+        self.include_numbers = saved.numbers;
+        self.drill_label = saved.drill_label;
+        // Refills and the recorded mode follow the text the test was started with
+        self.settings.text_source = saved.text_source;
+        self.target_text = saved.target_text;
+        self.pending_words = saved.pending_words;
+        self.cursor_position = text::grapheme_count(&saved.input);
+        self.input = saved.input;
+        // Backdate the start so the clock carries on from the saved elapsed time
+        let now = self.clock.now();
+        self.start_time = Some(now.checked_sub(Duration::from_secs_f64(saved.elapsed_secs)).unwrap_or(now));
+        self.time_up = saved.time_up;
+        self.total_correct_strokes = saved.total_correct_strokes;
+        self.total_incorrect_strokes = saved.total_incorrect_strokes;
+        self.wpm_history = saved.wpm_history;
+        self.error_points = saved.error_points;
+        self.error_positions = saved.error_positions;
+        self.keystroke_times = saved.keystroke_times;
+        self.char_times = saved.char_times;
+        self.missed_words = saved.missed_words;
+        self.warming_up = saved.warming_up;
+        self.measured_from = saved.measured_from;
+        self.pasted_ranges = saved.pasted_ranges;
+        self.correct_chars = self.count_correct_chars();
+        // The snapshot file still describes this test, so it's cleared when the test ends
+        self.last_snapshot_at = Some(now);
+        self.mode = AppMode::Typing;
+    }
+
+    /// Forgets the interrupted test found at launch.
+    fn discard_snapshot(&mut self) {
+        if self.pending_snapshot.take().is_some() {
+            let _ = snapshot::clear_snapshot();
+        }
+    }
+
+    /// How long to wait for input before redrawing. Static screens wait longer to save CPU,
+    /// since any key press still wakes the loop immediately.
+    pub fn poll_timeout(&self) -> Duration {
+        if self.mode == AppMode::Typing || self.animating() {
+            ACTIVE_POLL
+        } else {
+            IDLE_POLL
+        }
+    }
+
+    /// The results are counting up or a changed option is still highlighted.
+    fn animating(&self) -> bool {
+        self.result_animation_start.is_some() || self.toggle_flash.is_some() || self.completion_flash.is_some()
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        self.dirty = true;
+        // Notices only stay up until the next key press
+        self.status_message = None;
+
+        // Raw mode swallows the terminal's own Ctrl+C handling, so quit through the normal
+        // teardown from every screen instead
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.running = false;
+            return;
+        }
+
+        let scope = match self.mode {
+            AppMode::Welcome => Scope::Welcome,
+            AppMode::Typing => Scope::Typing,
+            AppMode::Results if !self.results_revealed => Scope::Hidden,
+            AppMode::Results => Scope::Results,
+            AppMode::History => Scope::History,
+            AppMode::HistoryDetails | AppMode::HistoryCompare => Scope::Details,
+            AppMode::Settings => Scope::Settings,
+            AppMode::Review => Scope::Review,
+            AppMode::Tutorial => Scope::Tutorial,
+            AppMode::Drills => Scope::Drills,
+        };
+        let action = self.keymap.action(scope, key);
+
         match self.mode {
-            AppMode::Welcome => match key.code {
-                KeyCode::Char('q') | KeyCode::Esc => self.running = false,
-                KeyCode::Enter => self.start_typing(),
-                KeyCode::Char('w') => self.cycle_word_mode(),
-                KeyCode::Char('t') => self.cycle_time_mode(),
-                KeyCode::Char('p') => self.include_punctuation = !self.include_punctuation,
-                KeyCode::Char('n') => self.include_numbers = !self.include_numbers,
-                KeyCode::Char('h') => {
+            AppMode::Welcome => match action {
+                Some(Action::Quit) => self.running = false,
+                Some(Action::Start) => self.start_typing(),
+                Some(Action::Resume) => self.resume_snapshot(),
+                Some(Action::Discard) => self.discard_snapshot(),
+                Some(Action::WordMode) => {
+                    self.cycle_word_mode();
+                    self.word_preview = None;
+                    self.save_settings();
+                }
+                Some(Action::TimeMode) => {
+                    self.cycle_time_mode();
+                    self.word_preview = None;
+                    self.save_settings();
+                }
+                Some(Action::Shuffle) => self.shuffle_preview(),
+                Some(Action::ComboDrill) => self.start_combo_drill(),
+                Some(Action::OpenDrills) => self.mode = AppMode::Drills,
+                Some(Action::Profile) => self.next_profile(),
+                Some(Action::Punctuation) => self.change_option(SettingOption::Punctuation, true),
+                Some(Action::Numbers) => self.change_option(SettingOption::Numbers, true),
+                Some(Action::Cursor) => self.change_option(SettingOption::Cursor, true),
+                Some(Action::FinishWord) => self.change_option(SettingOption::FinishWord, true),
+                Some(Action::EnterAsSpace) => self.change_option(SettingOption::EnterAsSpace, true),
+                Some(Action::PracticeLoop) => self.change_option(SettingOption::PracticeLoop, true),
+                Some(Action::Beginner) => self.change_option(SettingOption::Beginner, true),
+                Some(Action::SuddenDeath) => self.change_option(SettingOption::SuddenDeath, true),
+                Some(Action::OpenSettings) => {
+                    self.mode = AppMode::Settings;
+                    self.settings_selection = 0;
+                }
+                Some(Action::OpenHistory) => {
                     self.mode = AppMode::History;
-                    self.history_state.select(Some(0));
-                    self.selected_history_index = 0;
+                    // Pick up where the table was left, on the last row if runs were dropped since
+                    self.selected_history_index = self.selected_history_index.min(self.history.len().saturating_sub(1));
                 }
                 _ => {}
             },
-            AppMode::History => match key.code {
-                KeyCode::Esc => self.mode = AppMode::Welcome,
-                KeyCode::Char('q') => self.mode = AppMode::Welcome,
-                KeyCode::Up | KeyCode::Char('k') => {
-                     if !self.history.is_empty() {
-                        let i = match self.history_state.selected() {
-                            Some(i) => {
-                                if i == 0 {
-                                    self.history.len() - 1
-                                } else {
-                                    i - 1
-                                }
-                            }
-                            None => 0,
-                        };
-                        self.history_state.select(Some(i));
-                        self.selected_history_index = i;
-                    }
+            AppMode::History => match action {
+                Some(Action::Back) => self.mode = AppMode::Welcome,
+                Some(Action::Up) if !self.history.is_empty() => {
+                    let i = self.selected_history_index;
+                    self.selected_history_index = if i == 0 { self.history.len() - 1 } else { i - 1 };
                 }
-                KeyCode::Down | KeyCode::Char('j') => {
-                    if !self.history.is_empty() {
-                        let i = match self.history_state.selected() {
-                            Some(i) => {
-                                if i >= self.history.len() - 1 {
-                                    0
-                                } else {
-                                    i + 1
-                                }
-                            }
-                            None => 0,
-                        };
-                        self.history_state.select(Some(i));
-                        self.selected_history_index = i;
-                    }
+                Some(Action::Down) if !self.history.is_empty() => {
+                    let i = self.selected_history_index;
+                    self.selected_history_index = if i >= self.history.len() - 1 { 0 } else { i + 1 };
                 }
-                KeyCode::Enter => {
-                    if !self.history.is_empty() {
-                        self.mode = AppMode::HistoryDetails;
-                    }
+                Some(Action::Details) if !self.history.is_empty() => {
+                    self.mode = AppMode::HistoryDetails;
+                    self.error_review = None;
+                }
+                Some(Action::Mark) => {
+                    let selected = self.selected_history_entry();
+                    self.compare_index = if self.compare_index == selected { None } else { selected };
                 }
+                Some(Action::Trend) => {
+                    self.history_trend = match self.history_trend {
+                        HistoryTrend::Wpm => HistoryTrend::Accuracy,
+                        HistoryTrend::Accuracy => HistoryTrend::Wpm,
+                    };
+                }
+                Some(Action::Compare) => match (self.compare_index, self.selected_history_entry()) {
+                    (Some(marked), Some(selected)) if marked != selected => self.mode = AppMode::HistoryCompare,
+                    (Some(_), _) => self.status_message = Some("Select a different run to compare with".to_string()),
+                    (None, _) => {
+                        self.status_message = Some(format!("Mark a run with <{}> first", self.keymap.label(Action::Mark)))
+                    }
+                },
                 _ => {}
             },
-            AppMode::HistoryDetails => match key.code {
-                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Backspace => self.mode = AppMode::History,
+            AppMode::Settings => match action {
+                Some(Action::Back) => self.mode = AppMode::Welcome,
+                Some(Action::Down) => {
+                    self.settings_selection = (self.settings_selection + 1) % SettingOption::ALL.len();
+                }
+                Some(Action::Up) => {
+                    let len = SettingOption::ALL.len();
+                    self.settings_selection = (self.settings_selection + len - 1) % len;
+                }
+                Some(Action::Next) => {
+                    self.change_option(SettingOption::ALL[self.settings_selection], true);
+                }
+                Some(Action::Previous) => {
+                    self.change_option(SettingOption::ALL[self.settings_selection], false);
+                }
                 _ => {}
             },
-            AppMode::Typing => match key.code {
-                KeyCode::Esc => {
-                    self.mode = AppMode::Welcome;
-                    self.start_time = None;
+            AppMode::HistoryDetails | AppMode::HistoryCompare => match action {
+                Some(Action::Back) => self.mode = AppMode::History,
+                Some(Action::Average) if self.mode == AppMode::HistoryDetails => {
+                    self.change_option(SettingOption::RollingAverage, true);
                 }
-                KeyCode::Char(c) => {
-                    // Start timer on first keystroke
-                    if self.start_time.is_none() {
-                        self.start_time = Some(Instant::now());
+                Some(Action::Export) if self.mode == AppMode::HistoryDetails => self.export_chart(),
+                Some(Action::Repeat) if self.mode == AppMode::HistoryDetails => self.repeat_history_entry(),
+                Some(Action::NextError) if self.mode == AppMode::HistoryDetails => self.step_error_review(true),
+                Some(Action::PreviousError) if self.mode == AppMode::HistoryDetails => self.step_error_review(false),
+                _ => {}
+            },
+            AppMode::Typing => {
+                let typed = match (action, key.code) {
+                    (Some(Action::Cancel), _) => {
+                        self.clear_snapshot();
+                        self.mode = AppMode::Welcome;
+                        self.start_time = None;
+                        false
                     }
-                    
-                    // Check if correct BEFORE updating input
-                    let target_char = self.target_text.chars().nth(self.cursor_position);
-                    if let Some(tc) = target_char {
-                        if c == tc {
-                            self.total_correct_strokes += 1;
-                        } else {
-                            self.total_incorrect_strokes += 1;
-                            // Record error point
-                            if let Some(start) = self.start_time {
-                                let elapsed = start.elapsed().as_secs_f64();
-                                let current_wpm = self.calculate_wpm();
-                                self.error_points.push((elapsed, current_wpm));
-                            }
-                        }
-                    } else {
-                         // Typing beyond end of string counts as incorrect
-                         self.total_incorrect_strokes += 1;
-                         if let Some(start) = self.start_time {
-                            let elapsed = start.elapsed().as_secs_f64();
-                            let current_wpm = self.calculate_wpm();
-                            self.error_points.push((elapsed, current_wpm));
-                        }
+                    (Some(Action::Focus), _) => {
+                        self.change_option(SettingOption::FocusMode, true);
+                        false
                     }
-
-                    self.input.push(c);
-                    self.cursor_position += 1;
-                    self.check_completion();
-                }
-                KeyCode::Backspace => {
-                    if !self.input.is_empty() {
-                        // Allow backspacing across a space only if there's an error somewhere in the typed text
-                        if let Some(last_char) = self.input.chars().last() {
-                            if last_char == ' ' {
-                                if self.target_text.starts_with(&self.input) {
-                                    // Everything is correct so far, block backspace across space
-                                    return;
-                                }
-                            }
-                        }
-                        self.input.pop();
-                        self.cursor_position -= 1;
+                    // Line-kill: wipe the input and start the same text over
+                    (Some(Action::Clear), _) => {
+                        self.reset_run();
+                        false
+                    }
+                    (_, KeyCode::Char(c)) => {
+                        self.type_char(c);
+                        true
+                    }
+                    (_, KeyCode::Enter) if self.settings.enter_as_space => {
+                        // Only stand in for a space; where the text really has a line break, Enter types it
+                        let expected = self.target_text.graphemes(true).nth(self.cursor_position);
+                        self.type_char(if expected == Some("\n") { '\n' } else { ' ' });
+                        true
+                    }
+                    (_, KeyCode::Backspace) => {
+                        self.backspace();
+                        false
+                    }
+                    _ => false,
+                };
+                self.update_blocked_cue(typed);
+            }
+            AppMode::Results if !self.results_revealed => match action {
+                Some(Action::Quit) => self.running = false,
+                Some(Action::Reveal) => self.show_results(),
+                _ => {}
+            },
+            AppMode::Results => {
+                // Any key skips the count-up to the final values and stays on the results instead of
+                // the practice loop's next test, then does what it normally does
+                self.result_animation_start = None;
+                self.auto_restart_at = None;
+                match action {
+                    Some(Action::Quit) => self.running = false,
+                    Some(Action::Restart) => self.start_typing(),
+                    Some(Action::Harder) => self.start_harder_test(),
+                    Some(Action::Drill) => self.start_missed_words_drill(),
+                    Some(Action::ChartView) => self.chart_view = self.chart_view.next(),
+                    Some(Action::Average) => self.change_option(SettingOption::RollingAverage, true),
+                    Some(Action::Review) => {
+                        self.mode = AppMode::Review;
+                        self.review_scroll = 0;
                     }
+                    Some(Action::Export) => self.export_chart(),
+                    Some(Action::ExportData) => self.export_run_data(),
+                    _ => {}
                 }
+            }
+            // The UI clamps the scroll to the length of the text
+            AppMode::Review => match action {
+                Some(Action::Back) => self.mode = AppMode::Results,
+                Some(Action::Up) => self.review_scroll = self.review_scroll.saturating_sub(1),
+                Some(Action::Down) => self.review_scroll += 1,
                 _ => {}
             },
-            AppMode::Results => match key.code {
-                KeyCode::Char('q') | KeyCode::Esc => self.running = false,
-                KeyCode::Enter => self.start_typing(),
-                KeyCode::Char('r') => self.start_typing(),
+            AppMode::Tutorial => match action {
+                Some(Action::Next) if self.tutorial_page + 1 < TUTORIAL_PAGES => self.tutorial_page += 1,
+                Some(Action::Next) | Some(Action::Back) => self.finish_tutorial(),
+                Some(Action::Previous) => self.tutorial_page = self.tutorial_page.saturating_sub(1),
+                _ => {}
+            },
+            AppMode::Drills => match action {
+                Some(Action::Back) => self.mode = AppMode::Welcome,
+                Some(Action::Down) => self.drill_selection = (self.drill_selection + 1) % DrillPreset::ALL.len(),
+                Some(Action::Up) => {
+                    let len = DrillPreset::ALL.len();
+                    self.drill_selection = (self.drill_selection + len - 1) % len;
+                }
+                Some(Action::Start) => self.start_drill_preset(DrillPreset::ALL[self.drill_selection]),
                 _ => {}
             },
         }
     }
 
-    fn check_completion(&mut self) {
-        match self.test_mode {
-            TestMode::Words(_) => {
-                if self.input.len() >= self.target_text.len() {
-                    self.end_time = Some(Instant::now());
-                    // Capture final sample (only if at least 1 second has passed)
-                    if let Some(start) = self.start_time {
-                        let elapsed = start.elapsed().as_secs_f64();
-                        if elapsed >= 1.0 {
-                            let current_wpm = self.calculate_wpm();
-                            self.wpm_history.push((elapsed, current_wpm));
-                        }
-                    }
-                    self.save_result();
-                    self.mode = AppMode::Results;
+    /// Leaves the first-launch tutorial for the welcome screen, for good.
+    fn finish_tutorial(&mut self) {
+        self.settings.seen_tutorial = true;
+        self.save_settings();
+        self.mode = AppMode::Welcome;
+    }
+
+    /// Feeds one typed character into the running test.
+    pub fn type_char(&mut self, c: char) {
+        if self.mode != AppMode::Typing {
+            return;
+        }
+
+        if self.time_up && c == ' ' {
+            // Time ran out mid-word; the word is now finished
+            self.finish_test();
+            return;
+        }
+
+        if c == ' ' && self.waiting_for_trailing_space() {
+            // The space after the last word ends the test without counting as a keystroke
+            self.finish_test();
+            return;
+        }
+
+        // Start timer on first keystroke
+        if self.start_time.is_none() {
+            self.start_time = Some(self.clock.now());
+        }
+        self.record_keystroke();
+        
+        // A combining mark, joiner or modifier builds on the character just typed instead of
+        // moving the cursor on, so compare the whole grapheme it makes
+        let extends = text::extends_last(&self.input, c);
+        let (position, typed) = match text::last_grapheme(&self.input) {
+            Some(last) if extends => (self.cursor_position - 1, format!("{}{}", last, c)),
+            _ => (self.cursor_position, c.to_string()),
+        };
+
+        // Check if correct BEFORE updating input
+        let target = self.target_text.graphemes(true).nth(position);
+        // Keep the count of matching characters in step: a mark can make or break a match
+        if position >= self.measured_from {
+            let was_correct = extends && target == text::last_grapheme(&self.input);
+            let is_correct = target == Some(typed.as_str());
+            self.correct_chars = self.correct_chars + usize::from(is_correct) - usize::from(was_correct);
+        }
+        if let Some(target) = target {
+            // Typing the start of a multi-code-point character is on the right track
+            if target.starts_with(&typed) {
+                self.total_correct_strokes += 1;
+            } else {
+                self.total_incorrect_strokes += 1;
+                // Remember which word the error landed in (a mistyped space belongs to the word before it)
+                let word_index = self.target_text.graphemes(true).take(position).filter(|&g| g == " ").count();
+                self.missed_words.insert(word_index);
+                // Record error point
+                if let Some(start) = self.start_time {
+                    let elapsed = self.clock.now().duration_since(start).as_secs_f64();
+                    let current_wpm = self.calculate_wpm();
+                    self.error_points.push((elapsed, current_wpm));
+                    self.error_positions.push(position);
                 }
             }
-            TestMode::Time(_) => {
-                // In time mode, we don't end on completion, we might need to append more words if they type fast
-                // For now, let's just assume 100 words is enough or end if they finish (unlikely for 100 words in short time)
-                if self.input.len() >= self.target_text.len() {
-                     self.end_time = Some(Instant::now());
-                     // Capture final sample (only if at least 1 second has passed)
-                     if let Some(start) = self.start_time {
-                        let elapsed = start.elapsed().as_secs_f64();
-                        if elapsed >= 1.0 {
-                            let current_wpm = self.calculate_wpm();
-                            self.wpm_history.push((elapsed, current_wpm));
-                        }
-                     }
-                     self.save_result();
-                     self.mode = AppMode::Results;
-                }
+        } else {
+             // Typing beyond end of string counts as incorrect
+             self.total_incorrect_strokes += 1;
+             if let Some(start) = self.start_time {
+                let elapsed = self.clock.now().duration_since(start).as_secs_f64();
+                let current_wpm = self.calculate_wpm();
+                self.error_points.push((elapsed, current_wpm));
+                self.error_positions.push(position);
             }
         }
-    }
 
-    fn save_result(&mut self) {
-        let result = TestResult {
-            timestamp: Local::now(),
-            mode: format!("{}", self.test_mode),
-            wpm: self.calculate_wpm(),
-            accuracy: self.calculate_accuracy(),
-            wpm_history: self.wpm_history.clone(),
-            error_points: self.error_points.clone(),
-        };
-        self.history.push(result);
-        let _ = history::save_history(&self.history);
+        self.input.push(c);
+        let now = self.elapsed().map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        if extends {
+            // The character is only done once its last mark is in
+            if let Some(last) = self.char_times.last_mut() {
+                *last = now;
+            }
+        } else {
+            self.cursor_position += 1;
+            self.char_times.push(now);
+        }
+        self.end_warmup_if_done();
+        // In sudden death the first mistake ends the run, with the wrong key left in to review
+        if self.settings.sudden_death && !self.warming_up && self.total_incorrect_strokes > 0 {
+            self.finish_test();
+            return;
+        }
+        self.check_completion();
     }
 
-    fn cycle_word_mode(&mut self) {
-        self.test_mode = match self.test_mode {
-            TestMode::Words(10) => TestMode::Words(25),
-            TestMode::Words(25) => TestMode::Words(50),
-            TestMode::Words(50) => TestMode::Words(100),
-            _ => TestMode::Words(10),
-        };
+    /// Handles text pasted into the terminal. It never counts as typing: depending on the
+    /// `paste` setting it's dropped, or inserted in one go and left out of WPM.
+    pub fn handle_paste(&mut self, pasted: &str) {
+        if self.mode != AppMode::Typing {
+            return;
+        }
+        self.dirty = true;
+        match self.settings.paste {
+            PasteHandling::Ignore => self.status_message = Some("Paste ignored".to_string()),
+            PasteHandling::Insert => {
+                // Line breaks and tabs in the clipboard stand in for the spaces between words
+                let pasted: String = pasted
+                    .chars()
+                    .filter(|&c| c != '\r')
+                    .map(|c| if c.is_whitespace() { ' ' } else { c })
+                    .collect();
+                let count = text::grapheme_count(&pasted);
+                if count == 0 {
+                    return;
+                }
+                if self.start_time.is_none() {
+                    self.start_time = Some(self.clock.now());
+                }
+                let from = text::grapheme_count(&self.input);
+                self.pasted_ranges.push(from..from + count);
+                self.input.push_str(&pasted);
+                let now = self.elapsed().map_or(0.0, |elapsed| elapsed.as_secs_f64());
+                self.char_times.extend(std::iter::repeat_n(now, count));
+                self.cursor_position = text::grapheme_count(&self.input);
+                // Pasted text can run on from the character before it, so count again
+                self.correct_chars = self.count_correct_chars();
+                self.status_message = Some(format!("Pasted {} characters, not counted toward WPM", count));
+                self.end_warmup_if_done();
+                self.check_completion();
+            }
+        }
     }
 
-    fn cycle_time_mode(&mut self) {
+    /// Deletes the last typed character, unless that would step back over a correctly typed word.
+    pub fn backspace(&mut self) {
+        if self.mode != AppMode::Typing || self.input.is_empty() {
+            return;
+        }
+        // Allow backspacing across a space only if there's an error somewhere in the typed text
+        let last_len = text::last_grapheme(&self.input).map_or(0, str::len);
+        if self.input.ends_with(' ') && self.target_text.starts_with(&self.input) {
+            // Everything is correct so far, block backspace across space
+            return;
+        }
+        if self.cursor_position <= self.measured_from {
+            // The warmup is over and can't be retyped
+            return;
+        }
+        self.record_keystroke();
+        if text::last_grapheme(&self.input) == self.target_text.graphemes(true).nth(self.cursor_position - 1) {
+            self.correct_chars -= 1;
+        }
+        // Remove the whole character, combining marks and all
+        self.input.truncate(self.input.len() - last_len);
+        self.cursor_position -= 1;
+        self.char_times.pop();
+        // A pasted character that's deleted and typed again by hand counts like any other
+        if let Some(range) = self.pasted_ranges.last_mut().filter(|range| range.contains(&self.cursor_position)) {
+            range.end = self.cursor_position;
+            if range.start == range.end {
+                self.pasted_ranges.pop();
+            }
+        }
+    }
+
+    fn record_keystroke(&mut self) {
+        if let Some(elapsed) = self.elapsed() {
+            self.keystroke_times.push(elapsed.as_secs_f64());
+        }
+        self.keys_since_snapshot += 1;
+    }
+
+    /// Ends the running test now and records its result, as if the text or time had run out.
+    pub fn finish(&mut self) {
+        if self.mode == AppMode::Typing {
+            self.finish_test();
+        }
+    }
+
+    fn check_completion(&mut self) {
+        self.refill_text();
+        // Compare in graphemes, not bytes, so texts with multi-byte characters end at the right place
+        if text::grapheme_count(&self.input) < text::grapheme_count(&self.target_text) {
+            return;
+        }
+        // The last character has more to come, e.g. the accent of a decomposed "é"
+        if self.last_grapheme_incomplete() {
+            return;
+        }
+        match self.test_mode {
+            // With `require_correct_words` every error has to be fixed before the test can end
+            TestMode::Words(_) if self.settings.require_correct_words && self.input != self.target_text => {}
+            // With `finish_on_trailing_space` the test waits for a space after the last word
+            TestMode::Words(_) if self.settings.finish_on_trailing_space => {}
+            TestMode::Words(_) => self.finish_test(),
+            // Only a drill's fixed text can run out before the clock does
+            TestMode::Time(_) => self.finish_test(),
+        }
+    }
+
+    /// All of the text has been typed and the test only ends on the trailing space.
+    fn waiting_for_trailing_space(&self) -> bool {
+        self.settings.finish_on_trailing_space
+            && matches!(self.test_mode, TestMode::Words(_))
+            && !self.input.is_empty()
+            && text::grapheme_count(&self.input) >= text::grapheme_count(&self.target_text)
+            && !self.blocked_by_errors()
+    }
+
+    /// Lights the "fix the errors" cue when a key lands on finished text that still has errors,
+    /// and puts it out once they're gone.
+    fn update_blocked_cue(&mut self, typed: bool) {
+        if !self.blocked_by_errors() {
+            self.blocked_since = None;
+        } else if typed {
+            self.blocked_since = Some(self.clock.now());
+        }
+    }
+
+    /// Whether the "fix the errors" cue is lit right now, or `None` while it isn't needed. It
+    /// blinks for a moment after each key that couldn't finish the test, then stays on.
+    pub fn blocked_cue(&self) -> Option<bool> {
+        let elapsed = self.clock.now().duration_since(self.blocked_since?);
+        Some(elapsed >= BLOCKED_FLASH || (elapsed.as_millis() / BLOCKED_BLINK.as_millis()).is_multiple_of(2))
+    }
+
+    /// All of the text has been typed, but `require_correct_words` holds the test open until the
+    /// remaining errors are fixed.
+    pub fn blocked_by_errors(&self) -> bool {
+        self.settings.require_correct_words
+            && matches!(self.test_mode, TestMode::Words(_))
+            && text::grapheme_count(&self.input) >= text::grapheme_count(&self.target_text)
+            && self.input != self.target_text
+    }
+
+    fn save_result(&mut self) {
+        let result = TestResult {
+            timestamp: Local::now(),
+            mode: self.drill_label.clone().unwrap_or_else(|| self.mode_label()),
+            wpm: self.calculate_wpm(),
+            accuracy: self.calculate_accuracy(),
+            raw_accuracy: self.calculate_raw_accuracy(),
+            consistency: self.calculate_consistency(),
+            wpm_history: self.wpm_history.clone(),
+            error_points: self.error_points.clone(),
+            error_positions: self.error_positions.clone(),
+            target_text: self.target_text.clone(),
+            punctuation: self.include_punctuation && self.shuffled_words(),
+            numbers: self.include_numbers && self.shuffled_words(),
+            complete_words_wpm: self.wpm_drops_cut_off_word(),
+            kpm: self.calculate_kpm(),
+            sudden_death: self.settings.sudden_death.then(|| self.error_free_chars()),
+        };
+
+        // Aborted runs of a couple of keystrokes are shown but kept out of history
+        let seconds = self.test_duration().map(|d| d.as_secs_f64()).unwrap_or(0.0);
+        let chars = text::grapheme_count(&self.input).saturating_sub(self.measured_from);
+        // A test whose clock ran from the start with nothing typed isn't worth keeping either
+        self.result_recorded = chars > 0
+            && (seconds >= self.settings.min_record_seconds as f64 || chars >= self.settings.min_record_chars);
+
+        if self.result_recorded && !self.history_disabled {
+            self.history.push(result.clone());
+            let mut compacted = false;
+            if self.settings.compact_history {
+                // Always keep the run that was just finished
+                let recent = self.settings.keep_recent.max(1);
+                let kept = history::compact_history(&self.history, self.settings.keep_best_per_mode, recent);
+                if kept.len() != self.history.len() {
+                    self.history = kept;
+                    self.compare_index = None;
+                    compacted = true;
+                }
+            }
+            // Only dropping runs needs the whole file rewritten; otherwise the run is appended
+            let _ = if compacted {
+                history::save_history(&self.history)
+            } else {
+                history::append_history(&result)
+            };
+        }
+        if self.result_recorded {
+            self.session_results.push(result.clone());
+        }
+        self.last_result = Some(result);
+    }
+
+    /// A one-line wrap-up of the runs recorded since launch, or `None` if there were none.
+    pub fn session_summary(&self) -> Option<String> {
+        if self.session_results.is_empty() {
+            return None;
+        }
+        let count = self.session_results.len();
+        let average = self.session_results.iter().map(|r| r.wpm).sum::<f64>() / count as f64;
+        let best = self.session_results.iter().map(|r| r.wpm).fold(0.0, f64::max);
+        let tests = if count == 1 { "test" } else { "tests" };
+        Some(format!(
+            "Session: {} {} | Average: {:.0} WPM | Best: {:.0} WPM",
+            count, tests, average, best
+        ))
+    }
+
+    /// Time left before the practice loop starts the next test, if it is counting down.
+    pub fn auto_restart_remaining(&self) -> Option<Duration> {
+        let at = self.auto_restart_at?;
+        Some(at.saturating_duration_since(self.clock.now()))
+    }
+
+    /// Flips a toggle or steps a multi-valued option forwards or backwards, flashes it and
+    /// saves the change right away.
+    fn change_option(&mut self, option: SettingOption, forward: bool) {
+        // A preview made with the old options no longer matches what a test would use
+        self.word_preview = None;
+        match option {
+            SettingOption::Mode => {
+                self.test_mode = match self.test_mode {
+                    TestMode::Words(_) => TestMode::Time(30),
+                    TestMode::Time(_) => TestMode::Words(25),
+                };
+            }
+            SettingOption::Length => {
+                self.test_mode = match self.test_mode {
+                    TestMode::Words(n) => TestMode::Words(step_number(&WORD_COUNTS, n, forward)),
+                    TestMode::Time(s) => TestMode::Time(step_number(&TIME_LIMITS, s, forward)),
+                };
+            }
+            SettingOption::Goal => {
+                // Each mode has its own goal; this sets the one for the selected mode
+                let mode = self.mode_label();
+                let current = self.settings.wpm_goals.get(&mode).copied().unwrap_or(0);
+                match step_number(&WPM_GOALS, current, forward) {
+                    0 => self.settings.wpm_goals.remove(&mode),
+                    goal => self.settings.wpm_goals.insert(mode, goal),
+                };
+            }
+            SettingOption::Source => self.settings.text_source = self.settings.text_source.toggle(),
+            SettingOption::Punctuation => self.include_punctuation = !self.include_punctuation,
+            SettingOption::PunctuationLevel => {
+                let level = self.settings.punctuation_level;
+                self.settings.punctuation_level = if forward { level.next() } else { level.prev() };
+            }
+            SettingOption::Numbers => self.include_numbers = !self.include_numbers,
+            SettingOption::Cursor => {
+                let style = self.settings.cursor_style;
+                self.settings.cursor_style = if forward { style.next() } else { style.prev() };
+            }
+            SettingOption::Warmup => {
+                self.settings.warmup_words = step_number(&WARMUP_WORDS, self.settings.warmup_words, forward);
+            }
+            SettingOption::TimerStart => self.settings.timer_start = self.settings.timer_start.toggle(),
+            SettingOption::FixedCursor => self.settings.fixed_cursor = !self.settings.fixed_cursor,
+            SettingOption::Lookahead => {
+                self.settings.lookahead_chars = step_number(&LOOKAHEAD_CHARS, self.settings.lookahead_chars, forward);
+            }
+            SettingOption::FadeTyped => {
+                self.settings.fade_typed_chars = step_number(&FADE_TYPED_CHARS, self.settings.fade_typed_chars, forward);
+            }
+            SettingOption::Metronome => {
+                self.settings.metronome_bpm = step_number(&METRONOME_BPMS, self.settings.metronome_bpm, forward);
+            }
+            SettingOption::RollingAverage => self.settings.show_rolling_average = !self.settings.show_rolling_average,
+            SettingOption::Smoothing => {
+                let smoothing = self.settings.chart_smoothing;
+                self.settings.chart_smoothing = if forward { smoothing.next() } else { smoothing.prev() };
+            }
+            SettingOption::FocusMode => self.settings.focus_mode = !self.settings.focus_mode,
+            SettingOption::FinishWord => self.settings.finish_word_on_timeout = !self.settings.finish_word_on_timeout,
+            SettingOption::CompleteWords => self.settings.complete_words_wpm = !self.settings.complete_words_wpm,
+            SettingOption::FinishOnSpace => self.settings.finish_on_trailing_space = !self.settings.finish_on_trailing_space,
+            SettingOption::FinishCue => {
+                let cue = self.settings.completion_cue;
+                self.settings.completion_cue = if forward { cue.next() } else { cue.prev() };
+            }
+            SettingOption::HideResults => self.settings.hide_results = !self.settings.hide_results,
+            SettingOption::FixErrors => self.settings.require_correct_words = !self.settings.require_correct_words,
+            SettingOption::SuddenDeath => self.settings.sudden_death = !self.settings.sudden_death,
+            SettingOption::EnterAsSpace => self.settings.enter_as_space = !self.settings.enter_as_space,
+            SettingOption::Paste => self.settings.paste = self.settings.paste.toggle(),
+            SettingOption::AutoSave => {
+                self.settings.snapshot_interval = step(&SNAPSHOT_INTERVALS, self.settings.snapshot_interval, forward);
+            }
+            SettingOption::PracticeLoop => self.settings.practice_loop = !self.settings.practice_loop,
+            SettingOption::Beginner if !self.settings.beginner => self.apply_beginner_preset(),
+            SettingOption::Beginner => self.settings.beginner = false,
+            SettingOption::Combos => {
+                // Steps through drilling all combinations, then each one on its own; a custom
+                // list from the settings file starts over from all of them
+                let current = match self.settings.drill_combos.as_slice() {
+                    [one] => words::COMBOS.iter().position(|c| c == one),
+                    _ => None,
+                };
+                let next = match (current, forward) {
+                    (None, true) => Some(0),
+                    (None, false) => Some(words::COMBOS.len() - 1),
+                    (Some(i), true) => (i + 1 < words::COMBOS.len()).then_some(i + 1),
+                    (Some(i), false) => i.checked_sub(1),
+                };
+                self.settings.drill_combos = next.map(|i| vec![words::COMBOS[i].to_string()]).unwrap_or_default();
+            }
+            SettingOption::KeyboardLayout => {
+                let layout = self.settings.keyboard_layout;
+                self.settings.keyboard_layout = if forward { layout.next() } else { layout.prev() };
+            }
+            SettingOption::Theme => {
+                let names: Vec<&str> = theme::THEMES.iter().map(|t| t.name).collect();
+                let name = step(&names, self.theme.name, forward);
+                self.theme = Theme::by_name(name).unwrap_or_default();
+                self.settings.theme = Some(name.to_string());
+            }
+        }
+        self.toggle_flash = Some((option, self.clock.now()));
+        self.save_settings();
+    }
+
+    fn save_settings(&mut self) {
+        self.settings.test_mode = self.test_mode;
+        self.settings.include_punctuation = self.include_punctuation;
+        self.settings.include_numbers = self.include_numbers;
+        let _ = settings::save_settings(&self.settings);
+    }
+
+    /// Saves the performance chart on screen (the last result, or the history entry being
+    /// viewed) as an SVG file and reports where it went.
+    fn export_chart(&mut self) {
+        let result = match self.mode {
+            AppMode::HistoryDetails => self.selected_history_entry().and_then(|i| self.history.get(i)),
+            _ => self.last_result.as_ref(),
+        };
+        let Some(result) = result else {
+            return;
+        };
+        let path = svg::chart_file_path(&self.export_dir(), result);
+        match svg::write_chart(&path, result, &self.theme) {
+            Ok(()) => {
+                self.status_message = Some(format!("Chart saved to {}", path.display()));
+                self.note_export(path);
+            }
+            Err(err) => self.status_message = Some(format!("Couldn't save chart: {}", err)),
+        }
+    }
+
+    /// Writes every sample, error and keystroke time of the run just finished to a JSON file
+    /// next to where charts go.
+    fn export_run_data(&mut self) {
+        let Some(result) = &self.last_result else {
+            return;
+        };
+        let path = export::run_data_file_path(&self.export_dir(), result);
+        let data = export::RunData {
+            input: &self.input,
+            keystroke_times: &self.keystroke_times,
+            char_times: &self.char_times,
+            ..export::RunData::new(result, self.settings.punctuation_level)
+        };
+        match export::write_run_data_to(&path, &data) {
+            Ok(()) => {
+                self.status_message = Some(format!("Run data saved to {}", path.display()));
+                self.note_export(path);
+            }
+            Err(err) => self.status_message = Some(format!("Couldn't save run data: {}", err)),
+        }
+    }
+
+    /// Where exported charts and run data go.
+    fn export_dir(&self) -> PathBuf {
+        match &self.settings.chart_export_dir {
+            Some(dir) => dir.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    /// Lists an exported file for the exit message, once however often it was written.
+    fn note_export(&mut self, path: PathBuf) {
+        if !self.exported_files.contains(&path) {
+            self.exported_files.push(path);
+        }
+    }
+
+    /// The option that was just changed, while its highlight is still showing.
+    pub fn flashing_option(&self) -> Option<SettingOption> {
+        let (option, at) = self.toggle_flash?;
+        (self.clock.now().duration_since(at) < TOGGLE_FLASH).then_some(option)
+    }
+
+    fn cycle_word_mode(&mut self) {
         self.test_mode = match self.test_mode {
-            TestMode::Time(15) => TestMode::Time(30),
-            TestMode::Time(30) => TestMode::Time(60),
-            _ => TestMode::Time(15),
+            TestMode::Words(n) => TestMode::Words(step_number(&WORD_COUNTS, n, true)),
+            TestMode::Time(_) => TestMode::Words(WORD_COUNTS[0]),
+        };
+    }
+
+    fn cycle_time_mode(&mut self) {
+        self.test_mode = match self.test_mode {
+            TestMode::Time(s) => TestMode::Time(step_number(&TIME_LIMITS, s, true)),
+            TestMode::Words(_) => TestMode::Time(TIME_LIMITS[0]),
+        };
+    }
+    
+    /// How long the test has run: up to `end_time` once finished, otherwise up to now.
+    pub fn test_duration(&self) -> Option<Duration> {
+        let start = self.start_time?;
+        let end = self.end_time.unwrap_or_else(|| self.clock.now());
+        Some(end.duration_since(start))
+    }
+
+    /// Characters of the input that were pasted rather than typed.
+    pub fn pasted_chars(&self) -> usize {
+        self.pasted_ranges.iter().map(|range| range.len()).sum()
+    }
+
+    pub fn calculate_wpm(&self) -> f64 {
+        let Some(duration) = self.test_duration() else {
+            return 0.0;
+        };
+
+        let minutes = duration.as_secs_f64() / 60.0;
+        if minutes == 0.0 {
+            return 0.0;
+        }
+        
+        // Gross WPM: every character typed so far, right or wrong
+        let chars = if self.wpm_drops_cut_off_word() {
+            // Up to and including the last space, i.e. only the words that were finished
+            self.input.rfind(' ').map_or(0, |i| text::grapheme_count(&self.input[..=i]))
+        } else {
+            self.cursor_position
+        };
+        // The warmup and anything pasted aren't scored
+        let words = chars.saturating_sub(self.measured_from + self.pasted_chars()) as f64 / 5.0;
+        words / minutes
+    }
+
+    /// Characters typed before the first one that doesn't match the text.
+    pub fn error_free_chars(&self) -> usize {
+        self.input
+            .graphemes(true)
+            .zip(self.target_text.graphemes(true))
+            .skip(self.measured_from)
+            .take_while(|(typed, target)| typed == target)
+            .count()
+    }
+
+    /// Keystrokes per minute: every character typed, right or wrong, including ones later
+    /// corrected. Unlike WPM this doesn't assume five characters to a word.
+    pub fn calculate_kpm(&self) -> f64 {
+        let Some(duration) = self.test_duration() else {
+            return 0.0;
+        };
+        let minutes = duration.as_secs_f64() / 60.0;
+        if minutes == 0.0 {
+            return 0.0;
+        }
+        (self.total_correct_strokes + self.total_incorrect_strokes) as f64 / minutes
+    }
+
+    /// The scored word that took longest to type for its length, with its WPM.
+    pub fn slowest_word(&self) -> Option<(&str, f64)> {
+        stats::slowest_word(&self.target_text, &self.char_times, self.measured_from)
+    }
+
+    /// The test was cut off by the timer mid-word and `complete_words_wpm` leaves that word out.
+    ///
+    /// A word finished after the timer (with `finish_word_on_timeout`) or the end of the text
+    /// still counts.
+    pub fn wpm_drops_cut_off_word(&self) -> bool {
+        self.settings.complete_words_wpm
+            && matches!(self.test_mode, TestMode::Time(_))
+            && self.end_time.is_some()
+            && !self.time_up
+            && text::grapheme_count(&self.input) < text::grapheme_count(&self.target_text)
+    }
+
+    /// The last typed character matches the start of its target but not all of it yet.
+    fn last_grapheme_incomplete(&self) -> bool {
+        let Some(last) = text::last_grapheme(&self.input) else {
+            return false;
+        };
+        self.target_text
+            .graphemes(true)
+            .nth(self.cursor_position - 1)
+            .is_some_and(|target| target != last && target.starts_with(last))
+    }
+
+    /// How far the results count-up animation has got, from 0.0 to 1.0 (finished or skipped).
+    pub fn result_animation_progress(&self) -> f64 {
+        match self.result_animation_start {
+            Some(start) => {
+                let elapsed = self.clock.now().duration_since(start);
+                (elapsed.as_secs_f64() / RESULT_ANIMATION.as_secs_f64()).min(1.0)
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Time between two metronome beats, or `None` with the metronome off.
+    pub fn metronome_interval(&self) -> Option<Duration> {
+        (self.settings.metronome_bpm > 0).then(|| Duration::from_secs(60) / self.settings.metronome_bpm)
+    }
+
+    /// Starts the metronome when the typing screen comes up and stops it when it goes, counting
+    /// beats so the bell, if on, rings once on each.
+    fn tick_metronome(&mut self) {
+        let interval = match self.metronome_interval() {
+            Some(interval) if self.mode == AppMode::Typing => interval,
+            _ => {
+                self.metronome_start = None;
+                return;
+            }
+        };
+        let now = self.clock.now();
+        let start = *self.metronome_start.get_or_insert_with(|| {
+            self.metronome_beats = 0;
+            now
+        });
+        let beats = (now.duration_since(start).as_nanos() / interval.as_nanos()) as u64 + 1;
+        if beats > self.metronome_beats {
+            self.metronome_beats = beats;
+            self.ring_bell |= self.settings.metronome_bell;
+        }
+    }
+
+    /// Whether the metronome is lit for a beat right now.
+    pub fn metronome_pulse(&self) -> bool {
+        match (self.metronome_start, self.metronome_interval()) {
+            (Some(start), Some(interval)) => {
+                let into_beat = self.clock.now().duration_since(start).as_nanos() % interval.as_nanos();
+                into_beat < METRONOME_PULSE.min(interval / 4).as_nanos()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the screen is still flashing for a test that just ended.
+    pub fn completion_flash_active(&self) -> bool {
+        self.completion_flash.is_some_and(|start| self.clock.now().duration_since(start) < COMPLETION_FLASH)
+    }
+
+    /// Index into `history` of the row selected in the history table.
+    ///
+    /// The table lists the newest run first, so row `i` is `history[len - 1 - i]`.
+    pub fn selected_history_entry(&self) -> Option<usize> {
+        self.history.len().checked_sub(1 + self.selected_history_index)
+    }
+
+    /// Time since the test's clock started, or `None` before the first keystroke.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.start_time.map(|start| self.clock.now().duration_since(start))
+    }
+
+    pub fn calculate_consistency(&self) -> f64 {
+        history::calculate_consistency(&self.wpm_history)
+    }
+
+    /// The selected mode as it's recorded in the history, e.g. "Words: 25", or
+    /// "Words: 25 (sentences)" with the sentences text source.
+    pub fn mode_label(&self) -> String {
+        self.settings.text_source.mode_label(self.test_mode)
+    }
+
+    /// Whether the text is random words with the punctuation and numbers toggles applied, rather
+    /// than sentences or a drill.
+    fn shuffled_words(&self) -> bool {
+        self.drill_label.is_none() && self.settings.text_source == TextSource::Words
+    }
+
+    /// Best WPM recorded for `mode` next to its goal, as `(best, goal)`, if it has a goal.
+    ///
+    /// With no runs of the mode yet the best is 0.
+    pub fn goal_progress(&self, mode: &str) -> Option<(f64, u32)> {
+        let goal = self.settings.wpm_goals.get(mode).copied().filter(|&goal| goal > 0)?;
+        Some((history::best_wpm(&self.history, mode).unwrap_or(0.0), goal))
+    }
+
+    /// History from before the just-finished run.
+    pub fn past_results(&self) -> &[TestResult] {
+        // A recorded run is the last entry in history
+        if self.result_recorded {
+            &self.history[..self.history.len().saturating_sub(1)]
+        } else {
+            &self.history[..]
+        }
+    }
+
+    /// Where the just-finished run ranks among earlier runs of the same mode, as a "top N%" value.
+    pub fn result_percentile(&self) -> Option<f64> {
+        let current = self.last_result.as_ref()?;
+        history::wpm_percentile(self.past_results(), &current.mode, current.wpm)
+    }
+
+    /// Number of target words finished so far, counted by the spaces typed before the cursor.
+    pub fn completed_words(&self) -> usize {
+        self.input
+            .graphemes(true)
+            .take(self.cursor_position)
+            .filter(|&g| g == " ")
+            .count()
+    }
+
+    /// How much of the typed text matches the target once corrections are taken into account.
+    pub fn calculate_accuracy(&self) -> f64 {
+        let typed = self.cursor_position.saturating_sub(self.measured_from);
+        if typed == 0 {
+            return 100.0;
+        }
+        (self.correct_chars as f64 / typed as f64) * 100.0
+    }
+
+    /// Characters of the input from `measured_from` on that match the text, counted from
+    /// scratch. `correct_chars` keeps the same number up to date key by key.
+    fn count_correct_chars(&self) -> usize {
+        self.input
+            .graphemes(true)
+            .zip(self.target_text.graphemes(true))
+            .skip(self.measured_from)
+            .filter(|(typed, target)| typed == target)
+            .count()
+    }
+
+    /// Share of all keystrokes that were correct when typed, so corrected mistakes still count.
+    pub fn calculate_raw_accuracy(&self) -> f64 {
+        let total_strokes = self.total_correct_strokes + self.total_incorrect_strokes;
+        if total_strokes == 0 {
+            return 100.0;
+        }
+        (self.total_correct_strokes as f64 / total_strokes as f64) * 100.0
+    }
+}
+
+/// The value after (or before) `current` in `values`, wrapping around; the first value if
+/// `current` isn't one of them.
+fn step<T: Copy + PartialEq>(values: &[T], current: T, forward: bool) -> T {
+    match values.iter().position(|&v| v == current) {
+        Some(i) if forward => values[(i + 1) % values.len()],
+        Some(i) => values[(i + values.len() - 1) % values.len()],
+        None => values[0],
+    }
+}
+
+/// Like [`step`] over ascending numbers, but a number that isn't offered, e.g. one set in the
+/// settings file, steps to its nearest neighbour in that direction instead of back to the start.
+fn step_number<T: Copy + PartialOrd>(values: &[T], current: T, forward: bool) -> T {
+    if values.contains(&current) {
+        return step(values, current, forward);
+    }
+    let next = if forward {
+        values.iter().find(|&&v| v > current).or(values.first())
+    } else {
+        values.iter().rev().find(|&&v| v < current).or(values.last())
+    };
+    *next.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::settings::{CompletionCue, CursorStyle};
+    use crate::theme::THEMES;
+
+    #[test]
+    fn test_goal_off_the_list_steps_to_a_neighbour() {
+        let mut app = App::with_clock(MockClock::new());
+        app.test_mode = TestMode::Words(25);
+        app.settings.wpm_goals.insert("Words: 25".to_string(), 85);
+        app.change_option(SettingOption::Goal, true);
+        assert_eq!(app.settings.wpm_goals.get("Words: 25"), Some(&90));
+
+        app.settings.wpm_goals.insert("Words: 25".to_string(), 85);
+        app.change_option(SettingOption::Goal, false);
+        assert_eq!(app.settings.wpm_goals.get("Words: 25"), Some(&80));
+
+        // Past either end it wraps around like a listed value
+        assert_eq!(step_number(&WPM_GOALS, 250, true), 0);
+        assert_eq!(step_number(&WPM_GOALS, 250, false), 200);
+        assert_eq!(step_number(&WORD_COUNTS, 5, false), 100);
+    }
+
+    #[test]
+    fn test_calculate_accuracy_perfect() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing; // Enable typing mode
+        app.target_text = "hello world".to_string();
+        // Simulate typing correctly
+        for c in "hello world".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.calculate_accuracy(), 100.0);
+    }
+
+    #[test]
+    fn test_calculate_accuracy_partial() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing; // Enable typing mode
+        app.target_text = "hello".to_string();
+        
+        // Type 'h', 'e', 'x' (wrong), 'l', 'l', 'o'
+        // Correct: h, e, l, l, o (5)
+        // Incorrect: x (1)
+        // Total: 6
+        // Accuracy: 5/6 * 100 = 83.33%
+        
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('h')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('e')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('x'))); // Wrong
+        app.handle_key_event(KeyEvent::from(KeyCode::Backspace)); // Correct it
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('o')));
+
+        // The corrected text is all right, but the raw tally remembers the mistake
+        assert_eq!(app.calculate_accuracy(), 100.0);
+        let expected = (5.0 / 6.0) * 100.0;
+        assert!((app.calculate_raw_accuracy() - expected).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_accuracy_counts_uncorrected_errors() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world".to_string();
+        for c in "hxllo".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.calculate_accuracy(), 80.0);
+        assert_eq!(app.calculate_raw_accuracy(), 80.0);
+    }
+
+    #[test]
+    fn test_calculate_accuracy_empty() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "hello".to_string();
+        assert_eq!(app.calculate_accuracy(), 100.0);
+    }
+
+    #[test]
+    fn test_calculate_wpm() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing; // Enable typing mode
+        app.target_text = "hello world!".to_string();
+        // Simulate typing, then let a minute pass
+        for c in "hello world".chars() {
+             app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        app.clock.advance(Duration::from_secs(60));
+
+        // 11 chars = 2.2 words
+        // 2.2 words / 1 minute = 2.2 WPM
+        assert!((app.calculate_wpm() - 2.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_wpm_counts_chars_not_bytes() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "café crème".to_string();
+        for c in "café".chars() {
+            app.type_char(c);
+        }
+        app.clock.advance(Duration::from_secs(1));
+        // 4 chars, not 5 bytes, in 1 second: 0.8 words / (1/60) minute
+        assert!((app.calculate_wpm() - 48.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_combining_accent_is_one_character() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "cafe\u{301} ok".to_string();
+        for c in "cafe".chars() {
+            app.type_char(c);
+        }
+        // The plain "e" is the start of the accented one, not a mistake
+        assert_eq!(app.cursor_position, 4);
+        assert_eq!(app.total_incorrect_strokes, 0);
+
+        app.type_char('\u{301}');
+        // The accent joins the "e" instead of moving the cursor on
+        assert_eq!(app.cursor_position, 4);
+        assert_eq!(app.total_correct_strokes, 5);
+        assert_eq!(app.calculate_accuracy(), 100.0);
+
+        // Backspace removes the accented letter as a whole
+        app.backspace();
+        assert_eq!(app.input, "caf");
+        assert_eq!(app.cursor_position, 3);
+
+        // The wrong accent on the right letter is a mistake
+        for c in "e\u{300}".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.cursor_position, 4);
+        assert_eq!(app.total_incorrect_strokes, 1);
+        assert!(app.missed_words.contains(&0));
+        assert_eq!(app.calculate_accuracy(), 75.0);
+    }
+
+    #[test]
+    fn test_waits_for_final_combining_mark() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "ole\u{301}".to_string();
+        for c in "ole".chars() {
+            app.type_char(c);
+        }
+        // Every character has been started, but the last one still needs its accent
+        assert_eq!(app.mode, AppMode::Typing);
+        app.type_char('\u{301}');
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.calculate_accuracy(), 100.0);
+    }
+
+    #[test]
+    fn test_emoji_sequence_is_one_character() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "hi 👩\u{200d}💻 there".to_string();
+        for c in "hi 👩\u{200d}💻 ".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.cursor_position, 5);
+        assert_eq!(app.completed_words(), 2);
+        assert_eq!(app.total_incorrect_strokes, 0);
+    }
+
+    #[test]
+    fn test_kpm_counts_keystrokes() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "café crème".to_string();
+        assert_eq!(app.calculate_kpm(), 0.0);
+        // One keystroke per character (not per byte), including the corrected mistake
+        for c in "cafx".chars() {
+            app.type_char(c);
+        }
+        app.backspace();
+        app.type_char('é');
+        app.clock.advance(Duration::from_secs(2));
+        assert!((app.calculate_kpm() - 150.0).abs() < 0.001);
+
+        app.finish();
+        assert!((app.last_result.as_ref().unwrap().kpm - 150.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_slowest_word() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "one because two".to_string();
+        let type_str = |app: &mut App<MockClock>, text: &str, secs: u64| {
+            for c in text.chars() {
+                app.clock.advance(Duration::from_secs(secs));
+                app.type_char(c);
+            }
+        };
+        type_str(&mut app, "one ", 1);
+        // A mistake fixed along the way is part of the word's time
+        type_str(&mut app, "bex", 1);
+        app.backspace();
+        type_str(&mut app, "cause ", 2);
+        type_str(&mut app, "tw", 1);
+        assert_eq!(app.char_times.len(), app.cursor_position);
+
+        // 7 characters in 13 seconds, from the space before it to its last letter
+        let (word, wpm) = app.slowest_word().unwrap();
+        assert_eq!(word, "because");
+        assert!((wpm - 7.0 / 5.0 / (13.0 / 60.0)).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_tick_samples_wpm_every_second() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world again".to_string();
+
+        for c in "hello".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        // No samples during the first second
+        app.clock.advance(Duration::from_millis(500));
+        app.tick();
+        assert!(app.wpm_history.is_empty());
+
+        app.clock.advance(Duration::from_millis(500));
+        app.tick();
+        assert_eq!(app.wpm_history.len(), 1);
+        // 5 chars in 1 second = 1 word / (1/60) minute = 60 WPM
+        assert!((app.wpm_history[0].1 - 60.0).abs() < 0.001);
+
+        // Not yet a second since the last sample
+        app.clock.advance(Duration::from_millis(900));
+        app.tick();
+        assert_eq!(app.wpm_history.len(), 1);
+
+        app.clock.advance(Duration::from_millis(100));
+        app.tick();
+        assert_eq!(app.wpm_history.len(), 2);
+        assert!((app.wpm_history[1].0 - 2.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_completed_words() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world fine".to_string();
+        assert_eq!(app.completed_words(), 0);
+
+        for c in "hello wo".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.completed_words(), 1);
+
+        for c in "rld ".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.completed_words(), 2);
+    }
+
+    #[test]
+    fn test_time_mode_finishes_current_word() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.finish_word_on_timeout = true;
+        app.test_mode = TestMode::Time(15);
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world".to_string();
+
+        for c in "hel".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        // The timer runs out mid-word
+        app.clock.advance(Duration::from_secs(16));
+        app.tick();
+        assert!(app.time_up);
+        assert_eq!(app.mode, AppMode::Typing);
+
+        for c in "lo".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.mode, AppMode::Typing);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(' ')));
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.input, "hello");
+    }
+
+    #[test]
+    fn test_time_mode_end_sample_not_duplicated() {
+        let mut app = App::with_clock(MockClock::new());
+        app.test_mode = TestMode::Time(3);
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world".to_string();
+        app.type_char('h');
+        for _ in 0..3 {
+            app.clock.advance(Duration::from_secs(1));
+            app.tick();
+        }
+        // The tick that ran out the clock took its sample, then the end captured the same moment
+        assert_eq!(app.mode, AppMode::Results);
+        let times: Vec<f64> = app.wpm_history.iter().map(|&(t, _)| t).collect();
+        assert_eq!(times, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_shuffle_preview() {
+        let mut app = App::with_clock(MockClock::new());
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        let preview = app.word_preview.clone().expect("shuffling should make a preview");
+        assert_eq!(preview.split(' ').count(), 10);
+
+        // Enter starts on exactly the previewed text
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.target_text, preview);
+        assert!(app.word_preview.is_none());
+
+        // Changing an option throws the preview away
+        app.mode = AppMode::Welcome;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('w')));
+        assert!(app.word_preview.is_none());
+    }
+
+    #[test]
+    fn test_beginner_preset() {
+        let mut app = App::with_clock(MockClock::new());
+        app.include_punctuation = true;
+        app.include_numbers = true;
+        app.test_mode = TestMode::Time(60);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('b')));
+        assert!(app.settings.beginner);
+        assert!(!app.include_punctuation);
+        assert!(!app.include_numbers);
+        assert_eq!(app.test_mode, TestMode::Words(10));
+
+        app.start_typing();
+        let pool = words::word_pool(true);
+        assert!(app.target_text.split(' ').all(|w| pool.contains(&w)));
+
+        // Turning it off only widens the word pool again
+        app.mode = AppMode::Welcome;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('b')));
+        assert!(!app.settings.beginner);
+        assert_eq!(app.test_mode, TestMode::Words(10));
+    }
+
+    #[test]
+    fn test_review_from_results() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat dig".chars() {
+            app.type_char(c);
+        }
+        app.result_animation_start = None;
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('v')));
+        assert_eq!(app.mode, AppMode::Review);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('k')));
+        assert_eq!(app.review_scroll, 0);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('j')));
+        assert_eq!(app.review_scroll, 1);
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Results);
+        // The run is still there to restart from
+        assert_eq!(app.input, "cat dig");
+    }
+
+    #[test]
+    fn test_export_chart_from_results() {
+        let mut app = App::with_clock(MockClock::new());
+        let dir = paths::data_dir().join("charts");
+        std::fs::create_dir_all(&dir).unwrap();
+        app.settings.chart_export_dir = Some(dir.clone());
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat dig".chars() {
+            app.type_char(c);
+        }
+        app.result_animation_start = None;
+
+        // Exporting the same run twice overwrites the file and only lists it once
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('e')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('e')));
+        assert_eq!(app.exported_files.len(), 1);
+        let path = &app.exported_files[0];
+        assert!(path.starts_with(&dir));
+        assert!(std::fs::read_to_string(path).unwrap().contains("<svg"));
+        assert_eq!(app.status_message, Some(format!("Chart saved to {}", path.display())));
+
+        // The run data goes next to it, with the keystroke timings the history doesn't keep
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('d')));
+        assert_eq!(app.exported_files.len(), 2);
+        let data: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&app.exported_files[1]).unwrap()).unwrap();
+        assert_eq!(data["input"], "cat dig");
+        assert_eq!(data["target_text"], "cat dog");
+        assert_eq!(data["keystroke_times"].as_array().unwrap().len(), 7);
+        assert_eq!(data["error_positions"], serde_json::json!([5]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sudden_death_ends_on_first_error() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Welcome;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('d')));
+        assert!(app.settings.sudden_death);
+
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat dx".chars() {
+            app.type_char(c);
+        }
+        // The wrong key ends the test and stays in the input for review
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.input, "cat dx");
+        assert_eq!(app.error_free_chars(), 5);
+        assert_eq!(app.last_result.as_ref().unwrap().sudden_death, Some(5));
+
+        // Without sudden death the same mistake is just an error
+        app.settings.sudden_death = false;
+        app.start_typing();
+        app.target_text = "cat dog".to_string();
+        for c in "cat dx".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Typing);
+    }
+
+    #[test]
+    fn test_complete_words_wpm() {
+        let run = |complete_words: bool, finish_word: bool| {
+            let mut app = App::with_clock(MockClock::new());
+            app.settings.complete_words_wpm = complete_words;
+            app.settings.finish_word_on_timeout = finish_word;
+            app.test_mode = TestMode::Time(15);
+            app.mode = AppMode::Typing;
+            app.target_text = "hello world again".to_string();
+            for c in "hello wor".chars() {
+                app.type_char(c);
+            }
+            app.clock.advance(Duration::from_secs(15));
+            app.tick();
+            app
         };
+
+        // "hello wor" is 9 chars over a quarter minute
+        let app = run(false, false);
+        assert!((app.calculate_wpm() - 9.0 / 5.0 * 4.0).abs() < 1e-9);
+        assert!(!app.wpm_drops_cut_off_word());
+
+        // Only "hello " counts
+        let app = run(true, false);
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(app.wpm_drops_cut_off_word());
+        assert!((app.calculate_wpm() - 6.0 / 5.0 * 4.0).abs() < 1e-9);
+        assert!(app.last_result.as_ref().unwrap().complete_words_wpm);
+
+        // A word finished after the timer is complete, so it counts
+        let mut app = run(true, true);
+        for c in "ld ".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.wpm_drops_cut_off_word());
+        assert!((app.calculate_wpm() - 11.0 / 5.0 * 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_clear_input_keeps_target() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world".to_string();
+
+        for c in "hx".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+
+        assert_eq!(app.input, "");
+        assert_eq!(app.cursor_position, 0);
+        assert_eq!(app.total_correct_strokes, 0);
+        assert_eq!(app.total_incorrect_strokes, 0);
+        assert!(app.start_time.is_none());
+        assert_eq!(app.target_text, "hello world");
+        assert_eq!(app.mode, AppMode::Typing);
+    }
+
+    #[test]
+    fn test_missed_words_drill() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "one two three".to_string();
+
+        // Mistype a letter in "one" and the space after "two"
+        for c in "onx twoxthree".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.missed_words.iter().copied().collect::<Vec<_>>(), vec![0, 1]);
+
+        app.result_animation_start = None;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('m')));
+        assert_eq!(app.mode, AppMode::Typing);
+        let mut drilled: Vec<&str> = app.target_text.split(' ').collect();
+        drilled.sort();
+        assert_eq!(drilled, vec!["one", "one", "one", "two", "two", "two"]);
+    }
+
+    #[test]
+    fn test_error_review() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "one two".to_string();
+        for c in "onx twx".chars() {
+            app.clock.advance(Duration::from_millis(200));
+            app.type_char(c);
+        }
+        let result = app.last_result.clone().unwrap();
+        assert_eq!(result.target_text, "one two");
+        assert_eq!(result.error_positions, vec![2, 6]);
+        assert_eq!(result.reviewable_errors(), 2);
+
+        app.history.push(result.clone());
+        app.mode = AppMode::History;
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::HistoryDetails);
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.error_review, Some(0));
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.error_review, Some(1));
+        app.handle_key_event(KeyEvent::from(KeyCode::Left));
+        app.handle_key_event(KeyEvent::from(KeyCode::Left));
+        assert_eq!(app.error_review, None);
+
+        // Runs saved before the text was kept can't be stepped through
+        app.history[0].target_text.clear();
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.error_review, None);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_repeat_history_entry() {
+        assert_eq!(TestMode::parse("Words: 25"), Some(TestMode::Words(25)));
+        assert_eq!(TestMode::parse("Time: 60s"), Some(TestMode::Time(60)));
+        assert_eq!(TestMode::parse("Drill: missed words"), None);
+
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.mode = AppMode::Typing;
+        app.test_mode = TestMode::Time(15);
+        app.include_numbers = true;
+        app.target_text = "one 2 three".to_string();
+        for c in "one 2".chars() {
+            app.clock.advance(Duration::from_millis(200));
+            app.type_char(c);
+        }
+        app.finish();
+        app.history.push(app.last_result.clone().unwrap());
+
+        app.test_mode = TestMode::Words(50);
+        app.include_numbers = false;
+        app.mode = AppMode::History;
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('r')));
+        assert_eq!(app.mode, AppMode::Typing);
+        // Including the words a timed test added as it ran
+        assert_eq!(app.target_text, app.history[0].target_text);
+        assert!(app.target_text.starts_with("one 2 three "));
+        assert_eq!(app.test_mode, TestMode::Time(15));
+        assert!(app.include_numbers);
+        assert!(app.input.is_empty());
+
+        // A drill is repeated as the same drill
+        app.history[0].mode = "Drill: missed words".to_string();
+        app.mode = AppMode::HistoryDetails;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('r')));
+        assert_eq!(app.drill_label.as_deref(), Some("Drill: missed words"));
+
+        app.history[0].target_text.clear();
+        app.mode = AppMode::HistoryDetails;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('r')));
+        assert_eq!(app.mode, AppMode::HistoryDetails);
+    }
+
+    #[test]
+    fn test_tutorial_on_first_launch() {
+        let mut app = App::load(true);
+        assert_eq!(app.mode, AppMode::Tutorial);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Left));
+        assert_eq!(app.tutorial_page, 0);
+        for _ in 1..TUTORIAL_PAGES {
+            app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        }
+        assert_eq!(app.tutorial_page, TUTORIAL_PAGES - 1);
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.mode, AppMode::Welcome);
+        assert!(app.settings.seen_tutorial);
+
+        // Never again once it's been seen
+        assert_eq!(App::load(true).mode, AppMode::Welcome);
+        let mut settings = settings::load_settings().unwrap();
+        settings.seen_tutorial = false;
+        settings::save_settings(&settings).unwrap();
+        assert_eq!(App::load(true).mode, AppMode::Welcome);
+
+        // Skipping counts as seeing it
+        std::fs::remove_file(settings::get_settings_file_path().unwrap()).unwrap();
+        let mut app = App::load(true);
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Welcome);
+        assert!(settings::load_settings().unwrap().seen_tutorial);
+        std::fs::remove_file(settings::get_settings_file_path().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_drill_presets_menu() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.test_mode = TestMode::Words(5);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('i')));
+        assert_eq!(app.mode, AppMode::Drills);
+        app.handle_key_event(KeyEvent::from(KeyCode::Up));
+        assert_eq!(DrillPreset::ALL[app.drill_selection], DrillPreset::NumberRow);
+        app.handle_key_event(KeyEvent::from(KeyCode::Down));
+        app.handle_key_event(KeyEvent::from(KeyCode::Down));
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.target_text, "asdf jkl; fdsa ;lkj asdfjkl;");
+
+        for c in "asdf jkl; fdsa ;lkj asdfjkl;".chars() {
+            app.clock.advance(Duration::from_millis(100));
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.last_result.as_ref().unwrap().mode, "Drill: home row");
+    }
+
+    #[test]
+    fn test_switch_profile() {
+        let mut app = App::with_clock(MockClock::new());
+        app.test_mode = TestMode::Time(60);
+        app.theme = THEMES[1];
+        app.settings.theme = Some(THEMES[1].name.to_string());
+        app.history.push(TestResult {
+            timestamp: Local::now(),
+            mode: "Time: 60s".to_string(),
+            wpm: 50.0,
+            accuracy: 100.0,
+            raw_accuracy: 100.0,
+            consistency: 100.0,
+            wpm_history: Vec::new(),
+            error_points: Vec::new(),
+            punctuation: false,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        });
+        app.compare_index = Some(0);
+        app.save_settings();
+        history::save_history(&app.history).unwrap();
+
+        // A new profile starts from scratch, with its own files
+        app.switch_profile(Some("work".to_string()));
+        assert_eq!(app.profile_name(), "work");
+        assert_eq!(app.test_mode, TestMode::Words(10));
+        assert_eq!(app.theme.name, Theme::default().name);
+        assert!(app.history.is_empty());
+        assert_eq!(app.compare_index, None);
+        assert_eq!(app.status_message.as_deref(), Some("Switched to profile work"));
+        app.test_mode = TestMode::Words(50);
+        app.save_settings();
+
+        // Switching back brings the default profile's settings and history back
+        app.switch_profile(None);
+        assert_eq!(app.test_mode, TestMode::Time(60));
+        assert_eq!(app.theme.name, THEMES[1].name);
+        assert_eq!(app.history.len(), 1);
+        app.switch_profile(Some("work".to_string()));
+        assert_eq!(app.test_mode, TestMode::Words(50));
+        app.switch_profile(None);
+    }
+
+    #[test]
+    fn test_chart_view_cycles() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Results;
+        assert_eq!(app.chart_view, ChartView::Wpm);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.chart_view, ChartView::Accuracy);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.chart_view, ChartView::Latency);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.chart_view, ChartView::Keys);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.chart_view, ChartView::Wpm);
+    }
+
+    #[test]
+    fn test_long_words_test_refills() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.test_mode = TestMode::Words(100);
+        app.start_typing();
+        assert_eq!(app.target_text.split(' ').count(), WORD_BATCH);
+        assert_eq!(app.total_words(), 100);
+
+        // Typing through the text adds the rest in one batch once it runs low
+        let mut typed = 0;
+        while app.pending_words > 0 {
+            let word = app.target_text.split(' ').nth(typed).unwrap().to_string();
+            for c in word.chars().chain(std::iter::once(' ')) {
+                app.type_char(c);
+            }
+            typed += 1;
+        }
+        assert_eq!(typed, WORD_BATCH - REFILL_AHEAD + 1);
+        assert_eq!(app.target_text.split(' ').count(), 100);
+
+        // And the test still ends on the 100th word
+        let rest: String = app.target_text.split(' ').skip(typed).collect::<Vec<_>>().join(" ");
+        for c in rest.chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+    }
+
+    #[test]
+    fn test_sentences_source() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.include_punctuation = true;
+        app.settings.text_source = TextSource::Sentences;
+        app.test_mode = TestMode::Words(25);
+        app.start_typing();
+
+        // Whole sentences, at least as many words as asked for and no more to come
+        let words = app.target_text.split(' ').count();
+        assert!(words >= 25);
+        assert_eq!(app.pending_words, 0);
+        assert!(words::SENTENCES.iter().any(|s| app.target_text.starts_with(s)));
+        assert!(app.target_text.ends_with(['.', '?']));
+
+        let text = app.target_text.clone();
+        for c in text.chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        let result = app.last_result.as_ref().unwrap();
+        assert_eq!(result.mode, "Words: 25 (sentences)");
+        // The sentences bring their own punctuation; the toggle isn't applied
+        assert!(!result.punctuation);
+    }
+
+    #[test]
+    fn test_time_test_never_runs_out() {
+        let mut app = App::with_clock(MockClock::new());
+        app.test_mode = TestMode::Time(60);
+        app.start_typing();
+        let text = app.target_text.clone();
+        for c in text.chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Typing);
+        assert!(app.target_text.split(' ').count() >= 2 * WORD_BATCH);
+        assert!(app.target_text.starts_with(&text));
+    }
+
+    #[test]
+    fn test_combo_drill() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.settings.drill_combos = vec!["qu".to_string(), "ing".to_string()];
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('o')));
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.drill_label.as_deref(), Some("Drill: combos qu, ing"));
+        let words: Vec<&str> = app.target_text.split(' ').collect();
+        assert_eq!(words.len(), app.generated_word_count());
+        assert!(words.iter().all(|w| w.contains("qu") || w.contains("ing")));
+
+        // The setting goes from all combinations through each one on its own and back
+        app.settings.drill_combos.clear();
+        app.change_option(SettingOption::Combos, true);
+        assert_eq!(app.settings.drill_combos, vec!["th".to_string()]);
+        app.change_option(SettingOption::Combos, false);
+        assert!(app.settings.drill_combos.is_empty());
+        app.change_option(SettingOption::Combos, false);
+        assert_eq!(app.settings.drill_combos, vec!["ght".to_string()]);
+        app.change_option(SettingOption::Combos, true);
+        assert!(app.settings.drill_combos.is_empty());
+    }
+
+    #[test]
+    fn test_missed_words_drill_perfect_run() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "one two".to_string();
+        for c in "one two".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+
+        app.result_animation_start = None;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('m')));
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.status_message.as_deref(), Some("Perfect run — nothing to drill."));
+    }
+
+    #[test]
+    fn test_programmatic_input() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "hi there".to_string();
+
+        for c in "hx".chars() {
+            app.type_char(c);
+        }
+        app.backspace();
+        app.type_char('i');
+        assert_eq!(app.input, "hi");
+        assert_eq!(app.total_incorrect_strokes, 1);
+
+        app.finish();
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(app.end_time.is_some());
+
+        // Input is ignored once the test is over
+        app.type_char('x');
+        assert_eq!(app.input, "hi");
+    }
+
+    #[test]
+    fn test_short_runs_are_not_recorded() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "hi".to_string();
+        for c in "hi".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.result_recorded);
+        assert!(app.history.is_empty());
+        assert!(app.last_result.is_some());
+
+        // Long enough in time, even with few characters
+        app.mode = AppMode::Typing;
+        app.reset_run();
+        app.type_char('h');
+        app.clock.advance(Duration::from_secs(5));
+        app.type_char('i');
+        assert!(app.result_recorded);
+        assert_eq!(app.history.len(), 1);
+    }
+
+    #[test]
+    fn test_practice_loop_restarts_after_delay() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.practice_loop = true;
+        app.settings.practice_loop_delay_secs = 3;
+        app.mode = AppMode::Typing;
+        app.target_text = "hi".to_string();
+        app.type_char('h');
+        app.type_char('i');
+        assert_eq!(app.mode, AppMode::Results);
+
+        app.clock.advance(Duration::from_secs(2));
+        app.tick();
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.auto_restart_remaining(), Some(Duration::from_secs(1)));
+
+        app.clock.advance(Duration::from_secs(1));
+        app.tick();
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.auto_restart_at, None);
     }
-    
-    pub fn calculate_wpm(&self) -> f64 {
-        let duration = if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
-            end.duration_since(start)
-        } else if let Some(start) = self.start_time {
-             Instant::now().duration_since(start)
-        } else {
-            return 0.0;
-        };
 
-        let minutes = duration.as_secs_f64() / 60.0;
-        if minutes == 0.0 {
-            return 0.0;
+    #[test]
+    fn test_practice_loop_cancelled_by_key() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.practice_loop = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "hi".to_string();
+        app.type_char('h');
+        app.type_char('i');
+        app.result_animation_start = None;
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('x')));
+        app.clock.advance(Duration::from_secs(60));
+        app.tick();
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(app.running);
+
+        // The key that stops the countdown does its usual job as well
+        app.start_typing();
+        app.target_text = "hi".to_string();
+        app.type_char('h');
+        app.type_char('i');
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('v')));
+        assert_eq!(app.mode, AppMode::Review);
+        assert_eq!(app.auto_restart_at, None);
+    }
+
+    #[test]
+    fn test_history_disabled() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "hello world".to_string();
+        for c in "hello world".chars() {
+            app.type_char(c);
         }
-        
-        let words = self.input.len() as f64 / 5.0;
-        words / minutes
+        assert!(app.result_recorded);
+        assert!(app.history.is_empty());
+        assert_eq!(app.session_results.len(), 1);
     }
 
-    pub fn calculate_accuracy(&self) -> f64 {
-        let total_strokes = self.total_correct_strokes + self.total_incorrect_strokes;
-        if total_strokes == 0 {
-            return 100.0;
+    #[test]
+    fn test_enter_as_space() {
+        let mut app = App::new();
+        app.mode = AppMode::Typing;
+        app.target_text = "hi there".to_string();
+        for c in "hi".chars() {
+            app.type_char(c);
         }
-        (self.total_correct_strokes as f64 / total_strokes as f64) * 100.0
+
+        app.settings.enter_as_space = false;
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.input, "hi");
+
+        app.settings.enter_as_space = true;
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.input, "hi ");
+        assert_eq!(app.total_incorrect_strokes, 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_result_animation() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "hi there".to_string();
+        app.type_char('h');
+        app.finish();
+        assert_eq!(app.result_animation_progress(), 0.0);
+
+        app.clock.advance(Duration::from_millis(250));
+        app.tick();
+        assert!((app.result_animation_progress() - 0.5).abs() < 1e-9);
+
+        // A key press skips the animation and still does its job
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.result_animation_progress(), 1.0);
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.chart_view, ChartView::Accuracy);
+    }
 
     #[test]
-    fn test_calculate_accuracy_perfect() {
-        let mut app = App::new();
-        app.mode = AppMode::Typing; // Enable typing mode
-        app.target_text = "hello world".to_string();
-        // Simulate typing correctly
-        for c in "hello world".chars() {
-            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+    fn test_settings_screen() {
+        let mut app = App::with_clock(MockClock::new());
+        assert!(!app.include_numbers);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('s')));
+        assert_eq!(app.mode, AppMode::Settings);
+
+        // j/k pick an option and Enter changes it
+        for _ in 0..6 {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char('j')));
+        }
+        assert_eq!(SettingOption::ALL[app.settings_selection], SettingOption::Numbers);
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(app.include_numbers);
+        assert!(app.settings.include_numbers);
+        assert_eq!(app.flashing_option(), Some(SettingOption::Numbers));
+
+        app.clock.advance(Duration::from_millis(500));
+        app.tick();
+        assert_eq!(app.flashing_option(), None);
+
+        // Left and right step multi-valued options both ways
+        for _ in 0..5 {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char('k')));
+        }
+        assert_eq!(SettingOption::ALL[app.settings_selection], SettingOption::Length);
+        app.handle_key_event(KeyEvent::from(KeyCode::Right));
+        assert_eq!(app.test_mode, TestMode::Words(25));
+        assert_eq!(app.settings.test_mode, TestMode::Words(25));
+        app.handle_key_event(KeyEvent::from(KeyCode::Left));
+        app.handle_key_event(KeyEvent::from(KeyCode::Left));
+        assert_eq!(app.test_mode, TestMode::Words(100));
+
+        // Wrapping up from the top lands on the last option
+        app.settings_selection = 0;
+        app.handle_key_event(KeyEvent::from(KeyCode::Up));
+        assert_eq!(SettingOption::ALL[app.settings_selection], SettingOption::Theme);
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.settings.theme.as_deref(), Some(app.theme.name));
+        assert_ne!(app.theme.name, "default");
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Welcome);
+        assert!(app.running);
+    }
+
+    #[test]
+    fn test_warmup_is_not_scored() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.warmup_words = 2;
+        app.test_mode = TestMode::Words(4);
+        app.mode = AppMode::Typing;
+        app.target_text = "aa bb cc dd".to_string();
+        app.reset_run();
+        assert!(app.warming_up);
+
+        for c in "ax bb".chars() {
+            app.type_char(c);
         }
+        app.clock.advance(Duration::from_secs(10));
+        app.type_char(' ');
+        // The space after the last warmup word starts the scored part afresh
+        assert!(!app.warming_up);
+        assert_eq!(app.measured_from, 6);
+        assert_eq!(app.start_time, None);
+        assert_eq!(app.total_incorrect_strokes, 0);
+        assert!(app.missed_words.is_empty());
+
+        // The warmup can't be backspaced into
+        app.backspace();
+        assert_eq!(app.input, "ax bb ");
+
+        for c in "cc dd".chars() {
+            app.type_char(c);
+            app.clock.advance(Duration::from_millis(250));
+        }
+        assert_eq!(app.mode, AppMode::Results);
         assert_eq!(app.calculate_accuracy(), 100.0);
+        assert_eq!(app.calculate_raw_accuracy(), 100.0);
+        // 5 scored characters in 1 second: 1 word / (1/60) minute
+        assert!((app.calculate_wpm() - 60.0).abs() < 0.001);
     }
 
     #[test]
-    fn test_calculate_accuracy_partial() {
-        let mut app = App::new();
-        app.mode = AppMode::Typing; // Enable typing mode
-        app.target_text = "hello".to_string();
-        
-        // Type 'h', 'e', 'x' (wrong), 'l', 'l', 'o'
-        // Correct: h, e, l, l, o (5)
-        // Incorrect: x (1)
-        // Total: 6
-        // Accuracy: 5/6 * 100 = 83.33%
-        
+    fn test_warmup_needs_text_after_it() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.warmup_words = 3;
+        app.target_text = "one two three".to_string();
+        app.reset_run();
+        assert!(!app.warming_up);
+    }
+
+    #[test]
+    fn test_paste_never_counts_as_typing() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "one two three".to_string();
+
+        // Ignored by default
+        app.handle_paste("one two three");
+        assert_eq!(app.input, "");
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.status_message.as_deref(), Some("Paste ignored"));
+
+        // Inserted, but left out of WPM and keystrokes
+        app.settings.paste = PasteHandling::Insert;
+        app.handle_paste("one\r\ntwo");
+        assert_eq!(app.input, "one two");
+        assert_eq!(app.cursor_position, 7);
+        assert_eq!(app.pasted_chars(), 7);
+        assert_eq!(app.total_correct_strokes, 0);
+        for c in " three".chars() {
+            app.clock.advance(Duration::from_millis(200));
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        // 6 typed characters in 1.2 seconds
+        assert!((app.calculate_wpm() - 60.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_paste_across_warmup_and_backspace() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.settings.paste = PasteHandling::Insert;
+        app.settings.warmup_words = 1;
+        app.target_text = "abc def ghi".to_string();
+        app.reset_run();
+
+        // Pasted during the warmup: left out with the warmup, not a second time after it
+        app.handle_paste("abc ");
+        assert!(!app.warming_up);
+        assert_eq!(app.pasted_chars(), 0);
+        for c in "def ".chars() {
+            app.clock.advance(Duration::from_millis(200));
+            app.type_char(c);
+        }
+        app.clock.advance(Duration::from_millis(600));
+        // 4 typed characters in 1.2 seconds, the clock starting on the first of them
+        assert!((app.calculate_wpm() - 40.0).abs() < 0.001);
+
+        // Pasted, then deleted and typed by hand, counts as typed
+        app.handle_paste("gh");
+        assert_eq!(app.pasted_chars(), 2);
+        app.backspace();
+        assert_eq!(app.pasted_chars(), 1);
+        app.type_char('h');
+        assert_eq!(app.pasted_chars(), 1);
+        app.backspace();
+        app.backspace();
+        assert_eq!(app.pasted_chars(), 0);
+        assert!(app.pasted_ranges.is_empty());
+    }
+
+    #[test]
+    fn test_goal_per_mode() {
+        let mut app = App::with_clock(MockClock::new());
+        app.test_mode = TestMode::Words(25);
+        app.change_option(SettingOption::Goal, true);
+        app.change_option(SettingOption::Goal, true);
+        assert_eq!(app.settings.wpm_goals.get("Words: 25"), Some(&30));
+        // Other modes keep their own goal
+        assert_eq!(app.goal_progress("Time: 30s"), None);
+        assert_eq!(app.goal_progress("Words: 25"), Some((0.0, 30)));
+
+        let run = |wpm| TestResult {
+            timestamp: Local::now(),
+            mode: "Words: 25".to_string(),
+            wpm,
+            accuracy: 100.0,
+            raw_accuracy: 100.0,
+            consistency: 100.0,
+            wpm_history: Vec::new(),
+            error_points: Vec::new(),
+            punctuation: false,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        };
+        app.history = vec![run(24.0), run(33.0), run(28.0)];
+        assert_eq!(app.goal_progress("Words: 25"), Some((33.0, 30)));
+
+        // Stepping back past the lowest goal turns it off
+        app.change_option(SettingOption::Goal, false);
+        app.change_option(SettingOption::Goal, false);
+        assert!(app.settings.wpm_goals.is_empty());
+        assert_eq!(app.goal_progress("Words: 25"), None);
+    }
+
+    #[test]
+    fn test_history_selection_kept() {
+        let mut app = App::with_clock(MockClock::new());
+        let run = |wpm| TestResult {
+            timestamp: Local::now(),
+            mode: "Words: 25".to_string(),
+            wpm,
+            accuracy: 100.0,
+            raw_accuracy: 100.0,
+            consistency: 100.0,
+            wpm_history: Vec::new(),
+            error_points: Vec::new(),
+            punctuation: false,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        };
+        app.history = vec![run(24.0), run(33.0), run(28.0), run(40.0)];
+
         app.handle_key_event(KeyEvent::from(KeyCode::Char('h')));
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('e')));
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('x'))); // Wrong
-        app.handle_key_event(KeyEvent::from(KeyCode::Backspace)); // Correct it
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
-        app.handle_key_event(KeyEvent::from(KeyCode::Char('o')));
+        assert_eq!(app.selected_history_index, 0);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('j')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('j')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.mode, AppMode::Welcome);
 
-        let expected = (5.0 / 6.0) * 100.0;
-        assert!((app.calculate_accuracy() - expected).abs() < 0.001);
+        // Reopening lands on the same row
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('h')));
+        assert_eq!(app.mode, AppMode::History);
+        assert_eq!(app.selected_history_index, 2);
+        assert_eq!(app.selected_history_entry(), Some(1));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('j')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+
+        // The row is gone once runs are dropped, so the last one is selected instead
+        app.history.truncate(2);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('h')));
+        assert_eq!(app.selected_history_index, 1);
+        assert_eq!(app.selected_history_entry(), Some(0));
     }
 
     #[test]
-    fn test_calculate_accuracy_empty() {
-        let mut app = App::new();
+    fn test_finish_on_trailing_space() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.finish_on_trailing_space = true;
         app.mode = AppMode::Typing;
-        app.target_text = "hello".to_string();
+        app.target_text = "héllo".to_string();
+        for c in "héllo".chars() {
+            app.type_char(c);
+        }
+        // All of the text is typed, but the test waits for the space
+        assert_eq!(app.mode, AppMode::Typing);
+
+        app.type_char(' ');
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.input, "héllo");
+        assert_eq!(app.total_incorrect_strokes, 0);
+        assert_eq!(app.calculate_raw_accuracy(), 100.0);
+    }
+
+    #[test]
+    fn test_resume_interrupted_test() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat".chars() {
+            app.type_char(c);
+        }
+        app.clock.advance(Duration::from_secs(3));
+        app.tick();
+        // The session dies here; the next launch finds the snapshot
+        let saved = snapshot::load_snapshot().expect("snapshot should be saved while typing");
+        assert_eq!(saved.input, "cat");
+
+        let mut app = App::with_clock(MockClock::new());
+        app.pending_snapshot = Some(saved);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('r')));
+        assert_eq!(app.mode, AppMode::Typing);
+        assert_eq!(app.input, "cat");
+        assert_eq!(app.cursor_position, 3);
+        assert_eq!(app.elapsed(), Some(Duration::from_secs(3)));
+
+        for c in " dog".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.total_correct_strokes, 7);
+        assert_eq!(snapshot::load_snapshot(), None);
+    }
+
+    #[test]
+    fn test_resume_keeps_text_source() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.settings.text_source = TextSource::Sentences;
+        app.test_mode = TestMode::Words(10);
+        app.start_typing();
+        let text = app.target_text.clone();
+        app.type_char(text.chars().next().unwrap());
+        let saved = app.snapshot().unwrap();
+        assert_eq!(saved.describe().split(',').next(), Some("Words: 10 (sentences)"));
+
+        // Resumed after switching back to random words
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.pending_snapshot = Some(saved);
+        app.resume_snapshot();
+        assert_eq!(app.settings.text_source, TextSource::Sentences);
+        for c in text.chars().skip(1) {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.last_result.as_ref().unwrap().mode, "Words: 10 (sentences)");
+    }
+
+    #[test]
+    fn test_snapshot_interval() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "one two three".to_string();
+        app.settings.snapshot_interval = SnapshotInterval::Keystrokes(3);
+        app.type_char('o');
+        app.tick();
+        assert_eq!(snapshot::load_snapshot().unwrap().input, "o");
+        // Time alone doesn't make a save due, only keystrokes
+        app.type_char('n');
+        app.clock.advance(Duration::from_secs(10));
+        app.tick();
+        assert_eq!(snapshot::load_snapshot().unwrap().input, "o");
+        app.type_char('e');
+        app.type_char(' ');
+        app.tick();
+        assert_eq!(snapshot::load_snapshot().unwrap().input, "one ");
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+
+        // Off: nothing is written
+        app.settings.snapshot_interval = SnapshotInterval::Seconds(0);
+        app.mode = AppMode::Typing;
+        app.type_char('o');
+        app.tick();
+        assert_eq!(snapshot::load_snapshot(), None);
+    }
+
+    #[test]
+    fn test_discard_and_cancel_clear_snapshot() {
+        let mut app = App::with_clock(MockClock::new());
+        app.start_typing();
+        let first = app.target_text.chars().next().unwrap();
+        app.type_char(first);
+        app.tick();
+        assert!(snapshot::load_snapshot().is_some());
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(snapshot::load_snapshot(), None);
+
+        app.start_typing();
+        app.type_char(first);
+        app.tick();
+        let mut app = App::with_clock(MockClock::new());
+        app.pending_snapshot = snapshot::load_snapshot();
+        assert!(app.pending_snapshot.is_some());
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('x')));
+        assert!(app.pending_snapshot.is_none());
+        assert_eq!(snapshot::load_snapshot(), None);
+    }
+
+    #[test]
+    fn test_require_correct_words() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.require_correct_words = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+
+        // The lengths match but "dig" is wrong, so the test stays open
+        for c in "cat dig".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Typing);
+        assert!(app.blocked_by_errors());
+
+        // Extra characters don't end it either
+        app.type_char('x');
+        assert_eq!(app.mode, AppMode::Typing);
+
+        app.backspace();
+        app.backspace();
+        app.backspace();
+        app.type_char('o');
+        assert_eq!(app.mode, AppMode::Typing);
+        app.type_char('g');
+        assert_eq!(app.mode, AppMode::Results);
         assert_eq!(app.calculate_accuracy(), 100.0);
     }
 
     #[test]
-    fn test_calculate_wpm() {
-        let mut app = App::new();
-        app.mode = AppMode::Typing; // Enable typing mode
-        app.target_text = "hello world".to_string();
-        // Simulate typing
-        for c in "hello world".chars() {
-             app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+    fn test_correct_chars_match_recount() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.warmup_words = 1;
+        app.test_mode = TestMode::Words(50);
+        app.start_typing();
+        app.target_text = "warm café naïve words to type over and over again".to_string();
+        app.reset_run();
+
+        // A fixed pseudo-random mix of right keys, wrong keys, combining marks and backspaces
+        let mut seed: u32 = 7;
+        app.settings.paste = PasteHandling::Insert;
+        for i in 0..400 {
+            if i == 100 {
+                app.handle_paste("o");
+            }
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let expected = app.target_text.graphemes(true).nth(app.cursor_position).and_then(|g| g.chars().next());
+            match (seed >> 16) % 7 {
+                0 | 1 => app.backspace(),
+                2 => app.type_char('x'),
+                3 => app.type_char('\u{301}'),
+                _ => app.type_char(expected.unwrap_or('y')),
+            }
+            if app.mode != AppMode::Typing {
+                break;
+            }
+            assert_eq!(app.correct_chars, app.count_correct_chars(), "after {:?}", app.input);
         }
-        
-        let now = Instant::now();
-        app.start_time = Some(now - Duration::from_secs(60)); // 1 minute ago
-        app.end_time = Some(now);
+        assert!(!app.warming_up);
+        assert_eq!(app.mode, AppMode::Results);
+    }
 
-        // 11 chars = 2.2 words
-        // 2.2 words / 1 minute = 2.2 WPM
-        assert!((app.calculate_wpm() - 2.2).abs() < 0.001);
+    #[test]
+    fn test_timer_start() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.test_mode = TestMode::Time(15);
+        app.start_typing();
+        assert!(app.start_time.is_none());
+
+        app.settings.timer_start = TimerStart::OnStart;
+        app.start_typing();
+        assert!(app.start_time.is_some());
+        app.clock.advance(Duration::from_secs(5));
+        app.tick();
+        assert_eq!(app.elapsed(), Some(Duration::from_secs(5)));
+        assert_eq!(app.wpm_history.len(), 1);
+        // Nothing typed by the time it runs out: shown, but not kept
+        app.clock.advance(Duration::from_secs(10));
+        app.tick();
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.result_recorded);
+
+        // The warmup is never timed; the clock starts once it's done
+        app.settings.warmup_words = 1;
+        app.test_mode = TestMode::Words(10);
+        app.start_typing();
+        assert!(app.warming_up && app.start_time.is_none());
+        let first_word = app.target_text.split(' ').next().unwrap().to_string();
+        for c in first_word.chars().chain([' ']) {
+            app.type_char(c);
+        }
+        assert!(!app.warming_up);
+        assert_eq!(app.elapsed(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_completion_cue() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        let finish = |app: &mut App<MockClock>| {
+            app.mode = AppMode::Typing;
+            app.target_text = "ok".to_string();
+            app.type_char('o');
+            app.type_char('k');
+            assert_eq!(app.mode, AppMode::Results);
+        };
+
+        // Off by default
+        finish(&mut app);
+        assert!(!app.ring_bell && !app.completion_flash_active());
+
+        app.settings.completion_cue = CompletionCue::Bell;
+        finish(&mut app);
+        assert!(app.ring_bell);
+        assert!(!app.completion_flash_active());
+
+        app.ring_bell = false;
+        app.settings.completion_cue = CompletionCue::Flash;
+        finish(&mut app);
+        assert!(!app.ring_bell);
+        assert!(app.completion_flash_active());
+        // The flash keeps the screen redrawing until it's over
+        assert_eq!(app.poll_timeout(), ACTIVE_POLL);
+        app.clock.advance(COMPLETION_FLASH);
+        app.tick();
+        assert!(!app.completion_flash_active());
+        assert!(app.completion_flash.is_none());
+    }
+
+    #[test]
+    fn test_metronome() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.tick();
+        // Off by default
+        assert!(app.metronome_start.is_none() && !app.metronome_pulse());
+
+        app.settings.metronome_bpm = 120;
+        app.settings.metronome_bell = true;
+        app.tick();
+        assert!(app.metronome_pulse());
+        assert!(app.ring_bell);
+        app.ring_bell = false;
+
+        // Dark between beats, and no second bell until the next one
+        app.clock.advance(Duration::from_millis(200));
+        app.tick();
+        assert!(!app.metronome_pulse());
+        assert!(!app.ring_bell);
+        app.clock.advance(Duration::from_millis(300));
+        app.tick();
+        assert!(app.metronome_pulse());
+        assert!(app.ring_bell);
+        assert_eq!(app.metronome_beats, 2);
+
+        // It doesn't touch the test itself, and stops with the typing screen
+        assert!(app.start_time.is_none() && app.wpm_history.is_empty());
+        app.mode = AppMode::Welcome;
+        app.tick();
+        assert!(app.metronome_start.is_none() && !app.metronome_pulse());
+    }
+
+    #[test]
+    fn test_hide_results_until_revealed() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.settings.hide_results = true;
+        app.settings.practice_loop = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "ok".to_string();
+        app.type_char('o');
+        app.type_char('k');
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.results_revealed);
+        // Nothing counts up or counts down to the next test while hidden
+        assert!(app.result_animation_start.is_none());
+        assert!(app.auto_restart_remaining().is_none());
+
+        // Other results keys do nothing yet
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.results_revealed);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(' ')));
+        assert!(app.results_revealed);
+        assert!(app.result_animation_start.is_some());
+        assert!(app.auto_restart_remaining().is_some());
+    }
+
+    #[test]
+    fn test_start_harder_test() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.test_mode = TestMode::Words(50);
+        let harder = |app: &mut App<MockClock>| {
+            app.mode = AppMode::Results;
+            app.handle_key_event(KeyEvent::from(KeyCode::Char('+')));
+            assert_eq!(app.mode, AppMode::Typing);
+            app.status_message.clone().unwrap()
+        };
+
+        assert_eq!(harder(&mut app), "Harder: now 100 words");
+        assert_eq!(app.test_mode, TestMode::Words(100));
+        assert_eq!(app.target_text.split(' ').count() + app.pending_words, 100);
+        assert_eq!(harder(&mut app), "Harder: now with punctuation");
+        assert!(app.include_punctuation && !app.include_numbers);
+        assert_eq!(harder(&mut app), "Harder: now with numbers");
+        assert!(app.include_numbers);
+        assert_eq!(harder(&mut app), "Already at the hardest settings");
+        assert_eq!(app.test_mode, TestMode::Words(100));
+
+        // Timed tests get longer first too
+        app.test_mode = TestMode::Time(15);
+        assert_eq!(harder(&mut app), "Harder: now 30s");
+        assert_eq!(app.settings.test_mode, TestMode::Time(30));
+    }
+
+    #[test]
+    fn test_blocked_cue() {
+        let mut app = App::with_clock(MockClock::new());
+        app.settings.require_correct_words = true;
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat di".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.blocked_cue(), None);
+
+        // The last key can't finish the test: the cue blinks, then stays lit
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        assert_eq!(app.blocked_cue(), Some(true));
+        app.clock.advance(BLOCKED_BLINK);
+        assert_eq!(app.blocked_cue(), Some(false));
+        app.clock.advance(BLOCKED_FLASH);
+        assert_eq!(app.blocked_cue(), Some(true));
+
+        // Gone once the error is being fixed
+        app.handle_key_event(KeyEvent::from(KeyCode::Backspace));
+        assert_eq!(app.blocked_cue(), None);
+        app.handle_key_event(KeyEvent::from(KeyCode::Backspace));
+        for c in "og".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert_eq!(app.blocked_cue(), None);
+    }
+
+    #[test]
+    fn test_errors_dont_block_finish_by_default() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "cat dog".to_string();
+        for c in "cat dig".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(app.mode, AppMode::Results);
+        assert!(!app.blocked_by_errors());
+    }
+
+    #[test]
+    fn test_multibyte_text_finishes_on_last_char() {
+        let mut app = App::with_clock(MockClock::new());
+        app.mode = AppMode::Typing;
+        app.target_text = "日本".to_string();
+        app.type_char('日');
+        assert_eq!(app.mode, AppMode::Typing);
+        app.type_char('本');
+        assert_eq!(app.mode, AppMode::Results);
+    }
+
+    #[test]
+    fn test_focus_mode_toggle_while_typing() {
+        let mut app = App::with_clock(MockClock::new());
+        app.start_typing();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+        assert!(app.settings.focus_mode);
+        assert!(app.input.is_empty());
+        assert_eq!(app.start_time, None);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL));
+        assert!(!app.settings.focus_mode);
+    }
+
+    #[test]
+    fn test_ctrl_c_quits_from_any_mode() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let modes = [
+            AppMode::Welcome,
+            AppMode::Typing,
+            AppMode::Results,
+            AppMode::History,
+            AppMode::HistoryDetails,
+            AppMode::HistoryCompare,
+            AppMode::Settings,
+            AppMode::Review,
+            AppMode::Tutorial,
+            AppMode::Drills,
+        ];
+        for mode in modes {
+            let mut app = App::with_clock(MockClock::new());
+            app.history_disabled = true;
+            app.target_text = "cat".to_string();
+            app.mode = mode;
+            app.handle_key_event(ctrl_c);
+            assert!(!app.running, "{:?} should quit on Ctrl+C", mode);
+            // Quitting is all it does: no screen change, and no `c` typed or acted on
+            assert_eq!(app.mode, mode);
+            assert!(app.input.is_empty(), "{:?} typed the c", mode);
+            assert_eq!(app.chart_view, ChartView::Wpm);
+            assert_eq!(app.settings.cursor_style, CursorStyle::Block);
+        }
+
+        // Without Control the same key is typed as usual
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        app.target_text = "cat".to_string();
+        app.mode = AppMode::Typing;
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('c')));
+        assert!(app.running);
+        assert_eq!(app.input, "c");
+    }
+
+    #[test]
+    fn test_session_count() {
+        let mut app = App::with_clock(MockClock::new());
+        app.history_disabled = true;
+        for expected in 1..=2 {
+            app.mode = AppMode::Typing;
+            app.target_text = "ab".to_string();
+            app.reset_run();
+            // Too short to record, but still a finished test
+            app.type_char('a');
+            app.type_char('b');
+            assert_eq!(app.mode, AppMode::Results);
+            assert_eq!(app.session_count, expected);
+        }
+        // Cancelled tests don't count
+        app.start_typing();
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.session_count, 2);
+    }
+
+    #[test]
+    fn test_session_summary() {
+        let mut app = App::with_clock(MockClock::new());
+        assert_eq!(app.session_summary(), None);
+
+        for wpm in [40.0, 60.0] {
+            app.session_results.push(TestResult {
+                timestamp: Local::now(),
+                mode: "Words: 25".to_string(),
+                wpm,
+                accuracy: 100.0,
+                raw_accuracy: 100.0,
+                consistency: 100.0,
+                wpm_history: Vec::new(),
+                error_points: Vec::new(),
+                punctuation: false,
+                numbers: false,
+                complete_words_wpm: false,
+                kpm: 0.0,
+                sudden_death: None,
+                target_text: String::new(),
+                error_positions: Vec::new(),
+            });
+        }
+        assert_eq!(
+            app.session_summary().as_deref(),
+            Some("Session: 2 tests | Average: 50 WPM | Best: 60 WPM")
+        );
+    }
+
+    #[test]
+    fn test_poll_timeout_idles_outside_typing() {
+        let mut app = App::with_clock(MockClock::new());
+        assert_eq!(app.poll_timeout(), IDLE_POLL);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('p')));
+        assert_eq!(app.poll_timeout(), ACTIVE_POLL); // flash is animating
+        app.clock.advance(Duration::from_secs(1));
+        app.tick();
+        assert_eq!(app.poll_timeout(), IDLE_POLL);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.poll_timeout(), ACTIVE_POLL);
+    }
+
+    #[test]
+    fn test_dirty_only_when_screen_changes() {
+        let mut app = App::with_clock(MockClock::new());
+        assert!(app.dirty);
+        app.dirty = false;
+
+        // An idle menu stays as drawn
+        app.clock.advance(Duration::from_secs(1));
+        app.tick();
+        assert!(!app.dirty);
+
+        // The flash is redrawn until the tick that ends it
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('p')));
+        assert!(app.dirty);
+        app.dirty = false;
+        app.clock.advance(Duration::from_secs(1));
+        app.tick();
+        assert!(app.dirty);
+        app.dirty = false;
+        app.tick();
+        assert!(!app.dirty);
+
+        // A running test redraws every tick for the timer
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        app.dirty = false;
+        app.tick();
+        assert!(app.dirty);
     }
 
     #[test]
@@ -505,8 +3731,9 @@ mod tests {
         assert_eq!(app.total_incorrect_strokes, 1);
         assert_eq!(app.total_correct_strokes, 1);
         
-        // Accuracy should be 50% (1 correct / 2 total keystrokes)
-        assert_eq!(app.calculate_accuracy(), 50.0);
+        // Raw accuracy should be 50% (1 correct / 2 total keystrokes)
+        assert_eq!(app.calculate_raw_accuracy(), 50.0);
+        assert_eq!(app.calculate_accuracy(), 100.0);
         
         // Scenario 2: Deleting a correct character and re-typing it
         // Type 'e'
@@ -521,7 +3748,8 @@ mod tests {
         assert_eq!(app.total_correct_strokes, 3);
         
         // Total strokes = 1 (x) + 1 (h) + 1 (e) + 1 (e) = 4
-        // Accuracy = 3 / 4 = 75%
-        assert_eq!(app.calculate_accuracy(), 75.0);
+        // Raw accuracy = 3 / 4 = 75%
+        assert_eq!(app.calculate_raw_accuracy(), 75.0);
+        assert_eq!(app.calculate_accuracy(), 100.0);
     }
 }
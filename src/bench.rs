@@ -0,0 +1,92 @@
+//! Headless benchmark for `typestorm --bench`.
+//!
+//! Replays a fixed typing script through [`App`] on a [`MockClock`], so the reported metrics
+//! are the same on every machine and can be checked in CI.
+
+use crate::app::{App, AppMode};
+use crate::clock::MockClock;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Text typed by the benchmark.
+pub const BENCH_TEXT: &str =
+    "the quick brown fox jumps over the lazy dog while five boxing wizards jump quickly home";
+
+/// Time between simulated keystrokes: 5 chars a second, i.e. 60 WPM.
+const KEY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Every this many characters the script makes a typo and corrects it.
+const TYPO_EVERY: usize = 20;
+
+/// Metrics computed by the engine for the benchmark run.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub chars: usize,
+    pub keystrokes: usize,
+    pub seconds: f64,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub raw_accuracy: f64,
+    pub consistency: f64,
+}
+
+/// Types [`BENCH_TEXT`] at a steady 60 WPM, making and fixing a typo every [`TYPO_EVERY`] chars.
+///
+/// Nothing is read from or written to disk.
+pub fn run() -> BenchReport {
+    let mut app = App::with_clock(MockClock::new());
+    app.history_disabled = true;
+    app.mode = AppMode::Typing;
+    app.target_text = BENCH_TEXT.to_string();
+
+    let mut keystrokes = 0;
+    let mut press = |app: &mut App<MockClock>, key: Option<char>| {
+        if keystrokes > 0 {
+            app.clock.advance(KEY_INTERVAL);
+            app.tick();
+        }
+        match key {
+            Some(c) => app.type_char(c),
+            None => app.backspace(),
+        }
+        keystrokes += 1;
+    };
+
+    for (i, c) in BENCH_TEXT.chars().enumerate() {
+        if i % TYPO_EVERY == TYPO_EVERY - 1 {
+            press(&mut app, Some('#'));
+            press(&mut app, None);
+        }
+        press(&mut app, Some(c));
+    }
+    debug_assert_eq!(app.mode, AppMode::Results);
+
+    BenchReport {
+        chars: BENCH_TEXT.chars().count(),
+        keystrokes,
+        seconds: app.test_duration().unwrap_or_default().as_secs_f64(),
+        wpm: app.calculate_wpm(),
+        accuracy: app.calculate_accuracy(),
+        raw_accuracy: app.calculate_raw_accuracy(),
+        consistency: app.calculate_consistency(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_metrics() {
+        let report = run();
+        let typos = report.chars / TYPO_EVERY;
+        assert_eq!(report.keystrokes, report.chars + 2 * typos);
+        // The first keystroke starts the clock
+        let expected_seconds = (report.keystrokes - 1) as f64 * KEY_INTERVAL.as_secs_f64();
+        assert!((report.seconds - expected_seconds).abs() < 1e-6);
+        assert!((report.wpm - report.chars as f64 / 5.0 / (expected_seconds / 60.0)).abs() < 1e-6);
+        assert_eq!(report.accuracy, 100.0);
+        let expected_raw = report.chars as f64 / (report.chars + typos) as f64 * 100.0;
+        assert!((report.raw_accuracy - expected_raw).abs() < 1e-6);
+    }
+}
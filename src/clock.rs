@@ -0,0 +1,53 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// Source of the current time for the typing engine.
+///
+/// The app is generic over its clock so tests and headless runs can control time, while the
+/// terminal app uses [`SystemClock`], which compiles down to a plain `Instant::now()` call.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    base: Instant,
+    offset: Cell<Duration>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: Cell::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.offset.set(self.offset.get() + by);
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.base + self.offset.get()
+    }
+}
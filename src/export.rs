@@ -0,0 +1,210 @@
+//! CSV and JSON export of the history, for spreadsheets, other tools and reading, and the full
+//! time series of a single run for plotting elsewhere.
+
+use crate::history::{self, ModeSummary, TestResult};
+use crate::words::PunctuationLevel;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// One row per run, oldest first.
+pub fn history_csv(history: &[TestResult]) -> String {
+    let mut csv = String::from("timestamp,mode,wpm,accuracy,raw_accuracy,consistency,kpm,punctuation,numbers\n");
+    for result in history {
+        csv.push_str(&format!(
+            "{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{},{}\n",
+            result.timestamp.to_rfc3339(),
+            field(&result.mode),
+            result.wpm,
+            result.accuracy,
+            result.raw_accuracy,
+            result.consistency,
+            result.kpm,
+            result.punctuation,
+            result.numbers
+        ));
+    }
+    csv
+}
+
+/// One row per mode with its run count, average and best WPM and average accuracy.
+pub fn summary_csv(modes: &BTreeMap<String, ModeSummary>) -> String {
+    let mut csv = String::from("mode,count,average_wpm,best_wpm,average_accuracy\n");
+    for (mode, summary) in modes {
+        csv.push_str(&format!(
+            "{},{},{:.2},{:.2},{:.2}\n",
+            field(mode),
+            summary.tests,
+            summary.average_wpm,
+            summary.best_wpm,
+            summary.average_accuracy
+        ));
+    }
+    csv
+}
+
+/// Writes the history to `path`: a row per run, or a row per mode with `summary`.
+pub fn write_csv_to(path: &Path, history: &[TestResult], summary: bool) -> Result<()> {
+    let csv = if summary {
+        summary_csv(&history::mode_summaries(history))
+    } else {
+        history_csv(history)
+    };
+    history::write_replacing(path, &csv)
+}
+
+/// Writes the history to `path` as one pretty-printed JSON document, easier to read than the
+/// line-per-run history file.
+pub fn write_json_to(path: &Path, history: &[TestResult]) -> Result<()> {
+    history::write_replacing(path, &history::history_json(history)?)
+}
+
+/// Everything recorded about one run, exported from the results screen for plotting elsewhere.
+/// Times are seconds since the clock started.
+#[derive(Debug, Serialize)]
+pub struct RunData<'a> {
+    pub timestamp: String,
+    pub mode: &'a str,
+    pub punctuation: bool,
+    pub punctuation_level: PunctuationLevel,
+    pub numbers: bool,
+    pub sudden_death: bool,
+    pub complete_words_wpm: bool,
+    pub wpm: f64,
+    pub accuracy: f64,
+    pub raw_accuracy: f64,
+    pub consistency: f64,
+    pub kpm: f64,
+    /// The text itself rather than a seed: the words aren't drawn from a seeded generator, and
+    /// the text is what it takes to repeat the run anyway.
+    pub target_text: &'a str,
+    pub input: &'a str,
+    /// `(time, wpm)` samples, about one a second.
+    pub wpm_history: &'a [(f64, f64)],
+    /// `(time, wpm)` at each error, and the character of the text it was typed at.
+    pub error_points: &'a [(f64, f64)],
+    pub error_positions: &'a [usize],
+    /// Time of every key press, including backspaces and mistakes.
+    pub keystroke_times: &'a [f64],
+    /// When each character of the final input was typed, from the end of any warmup.
+    pub char_times: &'a [f64],
+}
+
+impl<'a> RunData<'a> {
+    pub fn new(result: &'a TestResult, punctuation_level: PunctuationLevel) -> Self {
+        Self {
+            timestamp: result.timestamp.to_rfc3339(),
+            mode: &result.mode,
+            punctuation: result.punctuation,
+            punctuation_level,
+            numbers: result.numbers,
+            sudden_death: result.sudden_death.is_some(),
+            complete_words_wpm: result.complete_words_wpm,
+            wpm: result.wpm,
+            accuracy: result.accuracy,
+            raw_accuracy: result.raw_accuracy,
+            consistency: result.consistency,
+            kpm: result.kpm,
+            target_text: &result.target_text,
+            input: "",
+            wpm_history: &result.wpm_history,
+            error_points: &result.error_points,
+            error_positions: &result.error_positions,
+            keystroke_times: &[],
+            char_times: &[],
+        }
+    }
+}
+
+/// Where the run data of `result` is written in `dir`, named like its SVG chart so the two sort
+/// together: `typestorm-run-20260314-092653.json`.
+pub fn run_data_file_path(dir: &Path, result: &TestResult) -> PathBuf {
+    dir.join(format!("typestorm-run-{}.json", result.timestamp.format("%Y%m%d-%H%M%S")))
+}
+
+/// Writes `data` to `path` as pretty-printed JSON.
+pub fn write_run_data_to(path: &Path, data: &RunData) -> Result<()> {
+    history::write_replacing(path, &serde_json::to_string_pretty(data)?)
+}
+
+/// Quotes a field that contains a comma, quote or line break.
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn run(mode: &str, wpm: f64, accuracy: f64) -> TestResult {
+        TestResult {
+            timestamp: Local.with_ymd_and_hms(2026, 5, 1, 8, 30, 0).unwrap(),
+            mode: mode.to_string(),
+            wpm,
+            accuracy,
+            raw_accuracy: accuracy,
+            consistency: 80.0,
+            wpm_history: Vec::new(),
+            error_points: Vec::new(),
+            punctuation: false,
+            numbers: true,
+            complete_words_wpm: false,
+            kpm: 300.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_csv() {
+        let csv = history_csv(&[run("Words: 25", 60.0, 95.0), run("Drill \"a, b\"", 40.0, 100.0)]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(",Words: 25,60.00,95.00,95.00,80.00,300.00,false,true"));
+        assert!(lines[2].contains(",\"Drill \"\"a, b\"\"\",40.00,"));
+    }
+
+    #[test]
+    fn test_run_data() {
+        let mut result = run("Words: 10", 60.0, 95.0);
+        result.wpm_history = vec![(1.0, 50.0), (2.0, 60.0)];
+        result.error_points = vec![(1.5, 55.0)];
+        result.error_positions = vec![3];
+        result.target_text = "cat dog".to_string();
+        let data = RunData {
+            input: "cat dig",
+            keystroke_times: &[0.0, 0.2, 0.4],
+            ..RunData::new(&result, PunctuationLevel::Heavy)
+        };
+        let json: serde_json::Value = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["mode"], "Words: 10");
+        assert_eq!(json["punctuation_level"], "Heavy");
+        assert_eq!(json["numbers"], true);
+        assert_eq!(json["wpm_history"], serde_json::json!([[1.0, 50.0], [2.0, 60.0]]));
+        assert_eq!(json["error_positions"], serde_json::json!([3]));
+        assert_eq!(json["keystroke_times"].as_array().unwrap().len(), 3);
+        assert_eq!(json["input"], "cat dig");
+
+        let path = run_data_file_path(Path::new("/tmp"), &result);
+        assert_eq!(path, Path::new("/tmp/typestorm-run-20260501-083000.json"));
+    }
+
+    #[test]
+    fn test_summary_csv() {
+        let history = [run("Words: 25", 60.0, 90.0), run("Time: 30s", 70.0, 99.0), run("Words: 25", 80.0, 100.0)];
+        let csv = summary_csv(&history::mode_summaries(&history));
+        assert_eq!(
+            csv,
+            "mode,count,average_wpm,best_wpm,average_accuracy\n\
+             Time: 30s,1,70.00,70.00,99.00\n\
+             Words: 25,2,70.00,80.00,95.00\n"
+        );
+    }
+}
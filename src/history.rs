@@ -1,45 +1,510 @@
 use anyhow::Result;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestResult {
     pub timestamp: DateTime<Local>,
     pub mode: String,
     pub wpm: f64,
+    /// Correctness of the final text, after corrections.
     pub accuracy: f64,
+    /// Share of keystrokes that were right when typed, including ones later corrected.
+    pub raw_accuracy: f64,
+    pub consistency: f64,
     pub wpm_history: Vec<(f64, f64)>,
     pub error_points: Vec<(f64, f64)>,
+    /// Whether the text had punctuation and numbers mixed in. Runs saved before these were
+    /// tracked load as plain text.
+    #[serde(default)]
+    pub punctuation: bool,
+    #[serde(default)]
+    pub numbers: bool,
+    /// WPM left out the word cut off by the timer (`complete_words_wpm`).
+    #[serde(default)]
+    pub complete_words_wpm: bool,
+    /// Keystrokes per minute, right or wrong. Older runs load as 0.
+    #[serde(default)]
+    pub kpm: f64,
+    /// Characters typed before the first error, for runs in sudden death mode.
+    #[serde(default)]
+    pub sudden_death: Option<usize>,
+    /// The text the run was typed against, and the character of it each of `error_points` was
+    /// typed at. Runs saved before these were kept load empty and can't have their errors reviewed.
+    #[serde(default)]
+    pub target_text: String,
+    #[serde(default)]
+    pub error_positions: Vec<usize>,
+}
+
+impl TestResult {
+    /// How many errors can be stepped through in the details view: none for older runs.
+    pub fn reviewable_errors(&self) -> usize {
+        if self.target_text.is_empty() || self.error_positions.len() != self.error_points.len() {
+            return 0;
+        }
+        self.error_positions.len()
+    }
 }
 
 pub fn get_history_file_path() -> Result<PathBuf> {
-    #[cfg(test)]
-    return Ok(std::env::temp_dir().join("typestorm_test_history.json"));
+    Ok(crate::paths::data_dir().join(format!(".typestorm_{}.json", crate::paths::profile_stem("history"))))
+}
+
+/// Schema version written to the history file.
+///
+/// Bump this together with a new entry in [`MIGRATIONS`] whenever `TestResult` changes shape.
+pub const HISTORY_VERSION: u32 = 3;
+
+/// The history file is JSON lines: a header line `{"version": 3}`, then one result per line, so
+/// a finished run is appended without rewriting the rest.
+#[derive(Serialize)]
+struct HistoryHeader {
+    version: u32,
+}
+
+/// The readable layout older versions stored history in, `{ "version": 3, "results": [...] }`,
+/// now only written by `--export-json`.
+#[derive(Serialize)]
+struct HistoryFile<'a> {
+    version: u32,
+    results: &'a [TestResult],
+}
+
+/// Upgrades the raw results of a history file by exactly one version.
+type Migration = fn(&mut [serde_json::Value]) -> Result<()>;
+
+/// Migration steps keyed by the version they upgrade from, applied in order.
+const MIGRATIONS: &[(u32, Migration)] = &[
+    (0, migrate_v0_to_v1),
+    (1, migrate_v1_to_v2),
+    (2, migrate_v2_to_v3),
+];
+
+/// v0 files were a bare array of results; v1 only added the envelope around them.
+fn migrate_v0_to_v1(_results: &mut [serde_json::Value]) -> Result<()> {
+    Ok(())
+}
+
+/// v2 added `consistency`, which can be recomputed from the stored WPM samples.
+fn migrate_v1_to_v2(results: &mut [serde_json::Value]) -> Result<()> {
+    for result in results.iter_mut() {
+        let samples: Vec<(f64, f64)> = serde_json::from_value(result["wpm_history"].clone())?;
+        let object = result
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("History entry is not an object"))?;
+        object.insert("consistency".to_string(), calculate_consistency(&samples).into());
+    }
+    Ok(())
+}
+
+/// v3 split accuracy in two. The old `accuracy` was the keystroke tally, which is now
+/// `raw_accuracy`; the corrected-text accuracy can't be recovered, so it keeps the same value.
+fn migrate_v2_to_v3(results: &mut [serde_json::Value]) -> Result<()> {
+    for result in results.iter_mut() {
+        let accuracy = result["accuracy"].clone();
+        let object = result
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("History entry is not an object"))?;
+        object.insert("raw_accuracy".to_string(), accuracy);
+    }
+    Ok(())
+}
 
-    #[cfg(not(test))]
-    {
-        let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        Ok(home_dir.join(".typestorm_history.json"))
+/// Brings raw results written at `version` up to [`HISTORY_VERSION`].
+fn migrate(version: u32, results: &mut [serde_json::Value]) -> Result<()> {
+    if version > HISTORY_VERSION {
+        anyhow::bail!("History file version {} is newer than supported version {}", version, HISTORY_VERSION);
     }
+    for (from, step) in MIGRATIONS {
+        if *from >= version {
+            step(results)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn load_history() -> Result<(Vec<TestResult>, usize)> {
+    load_history_from(&get_history_file_path()?)
+}
+
+pub fn save_history(history: &[TestResult]) -> Result<()> {
+    save_history_to(&get_history_file_path()?, history)
+}
+
+pub fn append_history(result: &TestResult) -> Result<()> {
+    append_history_to(&get_history_file_path()?, result)
+}
+
+/// The version in `line` if it's the header of a JSON lines history file.
+fn header_version(line: &str) -> Option<u32> {
+    let header: serde_json::Value = serde_json::from_str(line).ok()?;
+    if header.get("results").is_some() {
+        // A single-line file in the old array layout
+        return None;
+    }
+    header.get("version")?.as_u64().map(|v| v as u32)
+}
+
+/// Lines of a JSON lines history that can't be read, e.g. a run cut off by a crash or an edit
+/// gone wrong. Files in the old single-document layout either load whole or not at all.
+fn unreadable_lines(content: &str) -> usize {
+    if content.lines().next().and_then(header_version).is_none() {
+        return 0;
+    }
+    content
+        .lines()
+        .skip(1)
+        .filter(|line| serde_json::from_str::<serde_json::Value>(line).is_err())
+        .count()
 }
 
-pub fn load_history() -> Result<Vec<TestResult>> {
-    let path = get_history_file_path()?;
+/// Loads history from `path`, upgrading older files and writing the upgraded copy back. Files
+/// in the old single-document layout are rewritten as JSON lines.
+///
+/// Also returns how many lines couldn't be read and were skipped. The file is then left as it
+/// is, so rewriting it doesn't lose those runs for good.
+pub fn load_history_from(path: &Path) -> Result<(Vec<TestResult>, usize)> {
+    let (history, outdated, unreadable) = read_history_from(path)?;
+    if outdated && unreadable == 0 {
+        save_history_to(path, &history)?;
+    }
+    Ok((history, unreadable))
+}
+
+/// Reads history from `path` in any layout it has been saved in, without touching the file.
+/// The flag says whether the file is in an older layout than the one written now, and the
+/// count is of the lines that couldn't be read.
+fn read_history_from(path: &Path) -> Result<(Vec<TestResult>, bool, usize)> {
     if !path.exists() {
-        return Ok(Vec::new());
+        return Ok((Vec::new(), false, 0));
     }
 
     let content = fs::read_to_string(path)?;
-    let history: Vec<TestResult> = serde_json::from_str(&content)?;
-    Ok(history)
+    if let Some(version) = content.lines().next().and_then(header_version) {
+        // A crash mid-append can leave a line cut off; the rest still load around it
+        let mut results: Vec<serde_json::Value> =
+            content.lines().skip(1).filter_map(|line| serde_json::from_str(line).ok()).collect();
+        migrate(version, &mut results)?;
+        let history: Vec<TestResult> = serde_json::from_value(serde_json::Value::Array(results))?;
+        return Ok((history, version < HISTORY_VERSION, unreadable_lines(&content)));
+    }
+
+    let (version, mut results) = match serde_json::from_str(&content)? {
+        // Files written before the envelope existed are a bare array of results
+        serde_json::Value::Array(results) => (0, results),
+        serde_json::Value::Object(mut file) => {
+            let version = file
+                .get("version")
+                .and_then(|v| v.as_u64())
+                .ok_or_else(|| anyhow::anyhow!("History file is missing its version"))? as u32;
+            let results = match file.remove("results") {
+                Some(serde_json::Value::Array(results)) => results,
+                _ => anyhow::bail!("History file is missing its results"),
+            };
+            (version, results)
+        }
+        _ => anyhow::bail!("Unrecognized history file format"),
+    };
+
+    migrate(version, &mut results)?;
+    let history: Vec<TestResult> = serde_json::from_value(serde_json::Value::Array(results))?;
+    Ok((history, true, 0))
 }
 
-pub fn save_history(history: &[TestResult]) -> Result<()> {
-    let path = get_history_file_path()?;
-    let content = serde_json::to_string_pretty(history)?;
-    fs::write(path, content)?;
+/// Reads another install's history file for `--import`, in any layout it was saved in (JSON
+/// lines or an `--export-json` document). The file itself is left as it is.
+pub fn read_import(path: &Path) -> Result<Vec<TestResult>> {
+    if !path.exists() {
+        anyhow::bail!("{} doesn't exist", path.display());
+    }
+    Ok(read_history_from(path)?.0)
+}
+
+/// Adds the runs of `imported` that aren't in `history` yet, telling runs apart by timestamp,
+/// and keeps `history` in chronological order. Returns how many runs were added, so importing
+/// the same file twice adds nothing the second time.
+pub fn merge_history(history: &mut Vec<TestResult>, imported: Vec<TestResult>) -> usize {
+    let mut seen: HashSet<DateTime<Local>> = history.iter().map(|result| result.timestamp).collect();
+    let before = history.len();
+    history.extend(imported.into_iter().filter(|result| seen.insert(result.timestamp)));
+    // Stable, so runs already in order stay where they were
+    history.sort_by_key(|result| result.timestamp);
+    history.len() - before
+}
+
+/// Writes the whole history to `path` as JSON lines, replacing the file in one step.
+///
+/// A file with lines that can't be read is left alone, since `history` doesn't have those runs.
+pub fn save_history_to(path: &Path, history: &[TestResult]) -> Result<()> {
+    let unreadable = fs::read_to_string(path).map_or(0, |content| unreadable_lines(&content));
+    if unreadable > 0 {
+        anyhow::bail!(
+            "{} has {} unreadable line{}; fix or remove {} before it can be rewritten",
+            path.display(),
+            unreadable,
+            if unreadable == 1 { "" } else { "s" },
+            if unreadable == 1 { "it" } else { "them" }
+        );
+    }
+    let mut content = serde_json::to_string(&HistoryHeader { version: HISTORY_VERSION })?;
+    content.push('\n');
+    for result in history {
+        content.push_str(&serde_json::to_string(result)?);
+        content.push('\n');
+    }
+    write_replacing(path, &content)
+}
+
+/// Adds one run to the end of the history file at `path`, starting the file if there is none.
+///
+/// Only the header is read, so this takes the same time however long the history is. A file in
+/// the old layout is left alone (it's converted when history is loaded).
+pub fn append_history_to(path: &Path, result: &TestResult) -> Result<()> {
+    use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+
+    let mut file = fs::OpenOptions::new().read(true).append(true).create(true).open(path)?;
+    let mut line = String::new();
+    if file.metadata()?.len() == 0 {
+        line = serde_json::to_string(&HistoryHeader { version: HISTORY_VERSION })?;
+        line.push('\n');
+    } else {
+        let mut first = String::new();
+        BufReader::new(&file).read_line(&mut first)?;
+        if header_version(first.trim_end()) != Some(HISTORY_VERSION) {
+            anyhow::bail!("History file needs converting before runs can be appended");
+        }
+        // Finish off a line cut short by a crash so the new run gets a line of its own
+        let mut last = [0u8];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            line.push('\n');
+        }
+    }
+    line.push_str(&serde_json::to_string(result)?);
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// The history as one readable JSON document, the layout `--export-json` writes.
+pub fn history_json(history: &[TestResult]) -> Result<String> {
+    let file = HistoryFile {
+        version: HISTORY_VERSION,
+        results: history,
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Trims history to the `best_per_mode` fastest runs of each mode plus the `recent` latest runs.
+///
+/// Survivors keep their chronological order.
+pub fn compact_history(history: &[TestResult], best_per_mode: usize, recent: usize) -> Vec<TestResult> {
+    let mut keep = vec![false; history.len()];
+
+    for flag in keep.iter_mut().rev().take(recent) {
+        *flag = true;
+    }
+
+    let mut by_mode: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, result) in history.iter().enumerate() {
+        by_mode.entry(result.mode.as_str()).or_default().push(i);
+    }
+    for indices in by_mode.values_mut() {
+        indices.sort_by(|&a, &b| history[b].wpm.total_cmp(&history[a].wpm));
+        for &i in indices.iter().take(best_per_mode) {
+            keep[i] = true;
+        }
+    }
+
+    history
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(result, _)| result.clone())
+        .collect()
+}
+
+/// How steady the WPM samples of a run are, from 0 (erratic) to 100 (perfectly even).
+///
+/// Based on the coefficient of variation of the samples; runs with fewer than two samples
+/// are treated as perfectly consistent.
+pub fn calculate_consistency(wpm_history: &[(f64, f64)]) -> f64 {
+    if wpm_history.len() < 2 {
+        return 100.0;
+    }
+    let n = wpm_history.len() as f64;
+    let mean = wpm_history.iter().map(|(_, w)| w).sum::<f64>() / n;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    let variance = wpm_history.iter().map(|(_, w)| (w - mean).powi(2)).sum::<f64>() / n;
+    (100.0 * (1.0 - variance.sqrt() / mean)).clamp(0.0, 100.0)
+}
+
+/// Percentage of runs of `mode` that are at least as fast as `wpm`, counting the run itself.
+///
+/// Returns `None` when there are no earlier runs of the mode to compare against. Ties share the
+/// better rank, so matching your best puts you at the top.
+pub fn wpm_percentile(past: &[TestResult], mode: &str, wpm: f64) -> Option<f64> {
+    let runs: Vec<f64> = past.iter().filter(|r| r.mode == mode).map(|r| r.wpm).collect();
+    if runs.is_empty() {
+        return None;
+    }
+    let faster = runs.iter().filter(|&&w| w > wpm).count();
+    Some((faster + 1) as f64 / (runs.len() + 1) as f64 * 100.0)
+}
+
+/// Fastest run of `mode` in `history`.
+pub fn best_wpm(history: &[TestResult], mode: &str) -> Option<f64> {
+    history.iter().filter(|r| r.mode == mode).map(|r| r.wpm).reduce(f64::max)
+}
+
+/// Longest error-free stretch of any sudden death run in `history`.
+pub fn best_sudden_death(history: &[TestResult]) -> Option<usize> {
+    history.iter().filter_map(|r| r.sudden_death).max()
+}
+
+/// Average WPM of the last `n` runs and of the `n` runs before them, oldest runs last.
+///
+/// Either window averages however many runs it has when history is short; the previous one is
+/// `None` when there are no runs before the latest `n`, and the whole thing is `None` with no runs.
+pub fn recent_wpm_averages(history: &[TestResult], n: usize) -> Option<(f64, Option<f64>)> {
+    let average = |runs: &[TestResult]| runs.iter().map(|r| r.wpm).sum::<f64>() / runs.len() as f64;
+    let split = history.len().saturating_sub(n);
+    let (earlier, recent) = history.split_at(split);
+    if recent.is_empty() {
+        return None;
+    }
+    let previous = &earlier[earlier.len().saturating_sub(n)..];
+    Some((average(recent), (!previous.is_empty()).then(|| average(previous))))
+}
+
+/// How steady WPM has been across the latest `n` runs of `mode`, on the same 0 to 100 scale as a
+/// run's own consistency, and across the `n` runs of it before those if there are at least two.
+/// `None` until the mode has two runs.
+pub fn history_consistency(history: &[TestResult], mode: &str, n: usize) -> Option<(f64, Option<f64>)> {
+    let runs: Vec<(f64, f64)> = history.iter().filter(|r| r.mode == mode).map(|r| (0.0, r.wpm)).collect();
+    let split = runs.len().saturating_sub(n);
+    let (earlier, recent) = runs.split_at(split);
+    if recent.len() < 2 {
+        return None;
+    }
+    let previous = &earlier[earlier.len().saturating_sub(n)..];
+    Some((calculate_consistency(recent), (previous.len() >= 2).then(|| calculate_consistency(previous))))
+}
+
+/// Aggregate stats over the whole history, written by `--stats-json` for dashboards.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub total_tests: usize,
+    pub average_wpm: f64,
+    pub best_wpm: f64,
+    pub average_accuracy: f64,
+    pub modes: BTreeMap<String, ModeSummary>,
+    /// Consecutive days with at least one test, ending today or yesterday.
+    pub current_streak_days: usize,
+    pub longest_streak_days: usize,
+    pub last_test: Option<DateTime<Local>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeSummary {
+    pub tests: usize,
+    pub average_wpm: f64,
+    pub best_wpm: f64,
+    pub average_accuracy: f64,
+    pub best_accuracy: f64,
+}
+
+/// Summarizes `history`, counting day streaks relative to `today`.
+/// Stats for each mode in `history`, keyed and sorted by mode name.
+pub fn mode_summaries(history: &[TestResult]) -> BTreeMap<String, ModeSummary> {
+    let mut by_mode: BTreeMap<String, Vec<&TestResult>> = BTreeMap::new();
+    for result in history {
+        by_mode.entry(result.mode.clone()).or_default().push(result);
+    }
+    by_mode
+        .into_iter()
+        .map(|(mode, runs)| {
+            let count = runs.len() as f64;
+            let summary = ModeSummary {
+                tests: runs.len(),
+                average_wpm: runs.iter().map(|r| r.wpm).sum::<f64>() / count,
+                best_wpm: runs.iter().map(|r| r.wpm).fold(0.0, f64::max),
+                average_accuracy: runs.iter().map(|r| r.accuracy).sum::<f64>() / count,
+                best_accuracy: runs.iter().map(|r| r.accuracy).fold(0.0, f64::max),
+            };
+            (mode, summary)
+        })
+        .collect()
+}
+
+pub fn summarize(history: &[TestResult], today: NaiveDate) -> HistorySummary {
+    let mean = |values: &mut dyn Iterator<Item = f64>| {
+        let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+        if count == 0 { 0.0 } else { sum / count as f64 }
+    };
+
+    let modes = mode_summaries(history);
+    let (current_streak_days, longest_streak_days) = day_streaks(history, today);
+    HistorySummary {
+        total_tests: history.len(),
+        average_wpm: mean(&mut history.iter().map(|r| r.wpm)),
+        best_wpm: history.iter().map(|r| r.wpm).fold(0.0, f64::max),
+        average_accuracy: mean(&mut history.iter().map(|r| r.accuracy)),
+        modes,
+        current_streak_days,
+        longest_streak_days,
+        last_test: history.iter().map(|r| r.timestamp).max(),
+    }
+}
+
+/// The current and longest runs of consecutive days with a test. The current streak survives
+/// until the end of the day after the last test.
+fn day_streaks(history: &[TestResult], today: NaiveDate) -> (usize, usize) {
+    let days: std::collections::BTreeSet<NaiveDate> = history.iter().map(|r| r.timestamp.date_naive()).collect();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let current = match previous {
+        Some(last) if last == today || last.succ_opt() == Some(today) => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+/// Writes `summary` as JSON to `path`, replacing any existing file in one step so readers never
+/// see it half written.
+pub fn write_summary_to(path: &Path, summary: &HistorySummary) -> Result<()> {
+    write_replacing(path, &serde_json::to_string_pretty(summary)?)
+}
+
+/// Writes `content` to a temporary file next to `path` and renames it over `path`, so readers
+/// never see a partial file.
+pub(crate) fn write_replacing(path: &Path, content: &str) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+    fs::write(&tmp_path, content)?;
+    if let Err(err) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err.into());
+    }
     Ok(())
 }
 
@@ -54,17 +519,310 @@ mod tests {
             mode: "Words: 10".to_string(),
             wpm: 60.0,
             accuracy: 98.5,
+            raw_accuracy: 96.0,
+            consistency: 90.0,
             wpm_history: vec![(1.0, 50.0), (2.0, 60.0)],
             error_points: vec![(1.5, 55.0)],
+            punctuation: true,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
         };
 
         let history = vec![result.clone()];
         save_history(&history).expect("Failed to save history");
 
-        let loaded = load_history().expect("Failed to load history");
+        let (loaded, _) = load_history().expect("Failed to load history");
         assert_eq!(loaded.len(), 1);
         assert_eq!(loaded[0].mode, "Words: 10");
         assert_eq!(loaded[0].wpm, 60.0);
         assert_eq!(loaded[0].wpm_history.len(), 2);
+        assert!(loaded[0].punctuation);
+        assert!(!loaded[0].numbers);
+    }
+
+    fn result_with(mode: &str, wpm: f64) -> TestResult {
+        TestResult {
+            timestamp: Local::now(),
+            mode: mode.to_string(),
+            wpm,
+            accuracy: 100.0,
+            raw_accuracy: 100.0,
+            consistency: 100.0,
+            wpm_history: Vec::new(),
+            error_points: Vec::new(),
+            punctuation: false,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_best_wpm() {
+        let history = [result_with("Words: 25", 40.0), result_with("Words: 25", 65.0), result_with("Time: 15s", 90.0)];
+        assert_eq!(best_wpm(&history, "Words: 25"), Some(65.0));
+        assert_eq!(best_wpm(&history, "Time: 60s"), None);
+    }
+
+    #[test]
+    fn test_best_sudden_death() {
+        let streak = |chars| TestResult { sudden_death: Some(chars), ..result_with("Words: 25", 50.0) };
+        assert_eq!(best_sudden_death(&[result_with("Words: 25", 50.0)]), None);
+        assert_eq!(best_sudden_death(&[streak(40), result_with("Words: 25", 90.0), streak(12)]), Some(40));
+    }
+
+    #[test]
+    fn test_wpm_percentile() {
+        let past = vec![
+            result_with("Words: 25", 40.0),
+            result_with("Words: 25", 50.0),
+            result_with("Words: 25", 60.0),
+            result_with("Time: 15s", 200.0),
+        ];
+
+        assert_eq!(wpm_percentile(&[], "Words: 25", 50.0), None);
+        assert_eq!(wpm_percentile(&past, "Time: 30s", 50.0), None);
+        // Best run so far: 1st of 4
+        assert_eq!(wpm_percentile(&past, "Words: 25", 70.0), Some(25.0));
+        // Tied with the best is still the top
+        assert_eq!(wpm_percentile(&past, "Words: 25", 60.0), Some(25.0));
+        // Slowest run: 4th of 4
+        assert_eq!(wpm_percentile(&past, "Words: 25", 30.0), Some(100.0));
+    }
+
+    #[test]
+    fn test_load_legacy_and_versioned_formats() {
+        let dir = crate::paths::data_dir();
+        let results = vec![result_with("Words: 10", 55.0)];
+
+        // Legacy files are a bare array
+        let legacy_path = dir.join("typestorm_test_history_legacy.json");
+        fs::write(&legacy_path, serde_json::to_string(&results).unwrap()).unwrap();
+        let (loaded, _) = load_history_from(&legacy_path).expect("Failed to load legacy history");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].wpm, 55.0);
+
+        // ...and are converted to JSON lines once loaded
+        let content = fs::read_to_string(&legacy_path).unwrap();
+        assert_eq!(content.lines().next(), Some(format!(r#"{{"version":{}}}"#, HISTORY_VERSION).as_str()));
+        assert_eq!(content.lines().count(), 2);
+
+        // So are files in the versioned envelope that came after
+        let versioned_path = dir.join("typestorm_test_history_versioned.json");
+        fs::write(&versioned_path, history_json(&results).unwrap()).unwrap();
+        let (loaded, _) = load_history_from(&versioned_path).expect("Failed to load versioned history");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].mode, "Words: 10");
+        assert_eq!(header_version(fs::read_to_string(&versioned_path).unwrap().lines().next().unwrap()), Some(HISTORY_VERSION));
+
+        let _ = fs::remove_file(legacy_path);
+        let _ = fs::remove_file(versioned_path);
+    }
+
+    #[test]
+    fn test_migrate_v0_to_latest() {
+        let path = crate::paths::data_dir().join("history_v0.json");
+        // A v0 file: bare array, no consistency field
+        let v0 = r#"[{
+            "timestamp": "2025-01-01T12:00:00+00:00",
+            "mode": "Time: 15s",
+            "wpm": 50.0,
+            "accuracy": 95.0,
+            "wpm_history": [[1.0, 40.0], [2.0, 60.0]],
+            "error_points": []
+        }]"#;
+        fs::write(&path, v0).unwrap();
+
+        let (loaded, _) = load_history_from(&path).expect("Failed to migrate v0 history");
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].wpm, 50.0);
+        // Mean 50, stddev 10 -> 80% consistent
+        assert!((loaded[0].consistency - 80.0).abs() < 0.001);
+        assert_eq!(loaded[0].raw_accuracy, 95.0);
+        assert!(!loaded[0].punctuation && !loaded[0].numbers);
+
+        // The upgraded file is written back at the latest version
+        let content = fs::read_to_string(&path).unwrap();
+        let mut lines = content.lines();
+        assert_eq!(lines.next().and_then(header_version), Some(HISTORY_VERSION));
+        let raw: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert!((raw["consistency"].as_f64().unwrap() - 80.0).abs() < 0.001);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_append_history() {
+        let path = crate::paths::data_dir().join("history_append.json");
+        append_history_to(&path, &result_with("Words: 10", 40.0)).unwrap();
+        append_history_to(&path, &result_with("Words: 25", 50.0)).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        let (loaded, unreadable) = load_history_from(&path).unwrap();
+        assert_eq!(loaded.iter().map(|r| r.wpm).collect::<Vec<_>>(), vec![40.0, 50.0]);
+        assert_eq!(unreadable, 0);
+
+        // A run cut off mid-write is dropped, and the next one still gets its own line
+        fs::write(&path, format!("{}{{\"timestamp\":", content)).unwrap();
+        append_history_to(&path, &result_with("Time: 15s", 60.0)).unwrap();
+        let (loaded, unreadable) = load_history_from(&path).unwrap();
+        assert_eq!(loaded.iter().map(|r| r.wpm).collect::<Vec<_>>(), vec![40.0, 50.0, 60.0]);
+        assert_eq!(unreadable, 1);
+
+        // Rewriting the file would lose that run for good, so it's refused
+        let before = fs::read_to_string(&path).unwrap();
+        assert!(save_history_to(&path, &loaded).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+        // Runs aren't tacked onto a file that still has the old layout
+        fs::write(&path, history_json(&[result_with("Words: 10", 40.0)]).unwrap()).unwrap();
+        assert!(append_history_to(&path, &result_with("Words: 10", 45.0)).is_err());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_rejects_newer_version() {
+        let path = crate::paths::data_dir().join("history_future.json");
+        fs::write(&path, r#"{ "version": 999, "results": [] }"#).unwrap();
+        assert!(load_history_from(&path).is_err());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_merge_history() {
+        let now = Local::now();
+        let at = |minutes: i64, wpm: f64| TestResult {
+            timestamp: now - chrono::Duration::minutes(minutes),
+            ..result_with("Words: 25", wpm)
+        };
+        let mut history = vec![at(30, 40.0), at(10, 50.0)];
+        let other = vec![at(20, 45.0), at(10, 50.0), at(5, 55.0), at(5, 55.0)];
+
+        // The run both installs share, and the copy within the file, are only added once
+        assert_eq!(merge_history(&mut history, other.clone()), 2);
+        assert_eq!(history.iter().map(|result| result.wpm).collect::<Vec<_>>(), vec![40.0, 45.0, 50.0, 55.0]);
+
+        // Importing the same file again adds nothing
+        assert_eq!(merge_history(&mut history, other.clone()), 0);
+        assert_eq!(history.len(), 4);
+
+        // An exported document reads back without being rewritten
+        let path = crate::paths::data_dir().join("import.json");
+        let document = history_json(&other).unwrap();
+        fs::write(&path, &document).unwrap();
+        assert_eq!(read_import(&path).unwrap().len(), 4);
+        assert_eq!(fs::read_to_string(&path).unwrap(), document);
+        let _ = fs::remove_file(&path);
+        assert!(read_import(&path).is_err());
+    }
+
+    #[test]
+    fn test_compact_history() {
+        let history = vec![
+            result_with("Words: 10", 80.0), // 0: best Words
+            result_with("Words: 10", 40.0), // 1: dropped
+            result_with("Time: 15s", 30.0), // 2: dropped
+            result_with("Time: 15s", 70.0), // 3: best Time
+            result_with("Words: 10", 60.0), // 4: second best Words, dropped
+            result_with("Words: 10", 20.0), // 5: recent
+            result_with("Time: 15s", 10.0), // 6: recent
+        ];
+
+        let compacted = compact_history(&history, 1, 2);
+        let wpms: Vec<f64> = compacted.iter().map(|r| r.wpm).collect();
+        assert_eq!(wpms, vec![80.0, 70.0, 20.0, 10.0]);
+
+        // Nothing is dropped when the limits cover everything
+        assert_eq!(compact_history(&history, 10, 0).len(), history.len());
+    }
+
+    #[test]
+    fn test_recent_wpm_averages() {
+        let runs = |wpms: &[f64]| wpms.iter().map(|&w| result_with("Words: 10", w)).collect::<Vec<_>>();
+        assert_eq!(recent_wpm_averages(&[], 3), None);
+        // Fewer runs than the window: average what exists, nothing to compare against
+        assert_eq!(recent_wpm_averages(&runs(&[40.0, 50.0]), 3), Some((45.0, None)));
+        // The previous window may be short too
+        assert_eq!(recent_wpm_averages(&runs(&[30.0, 40.0, 50.0, 60.0]), 3), Some((50.0, Some(30.0))));
+        // Only the 2n latest runs count
+        assert_eq!(
+            recent_wpm_averages(&runs(&[10.0, 40.0, 50.0, 60.0, 70.0]), 2),
+            Some((65.0, Some(45.0)))
+        );
+    }
+
+    #[test]
+    fn test_history_consistency() {
+        let runs = |mode: &str, wpms: &[f64]| wpms.iter().map(|&w| result_with(mode, w)).collect::<Vec<_>>();
+        assert_eq!(history_consistency(&runs("Words: 10", &[50.0]), "Words: 10", 3), None);
+        // Other modes are left out
+        let mut history = runs("Words: 10", &[50.0, 50.0]);
+        history.extend(runs("Time: 30s", &[20.0, 90.0]));
+        assert_eq!(history_consistency(&history, "Words: 10", 3), Some((100.0, None)));
+
+        // Erratic runs, then steady ones
+        let history = runs("Words: 10", &[30.0, 70.0, 30.0, 70.0, 50.0, 50.0, 50.0]);
+        let (recent, previous) = history_consistency(&history, "Words: 10", 3).unwrap();
+        assert_eq!(recent, 100.0);
+        let previous = previous.unwrap();
+        assert!(previous < 80.0, "{}", previous);
+        // A previous window of one run has nothing to measure
+        assert_eq!(history_consistency(&history[2..], "Words: 10", 4).unwrap().1, None);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let today = Local::now().date_naive();
+        let at = |days_ago: i64, mode: &str, wpm: f64, accuracy: f64| TestResult {
+            timestamp: Local::now() - chrono::Duration::days(days_ago),
+            accuracy,
+            ..result_with(mode, wpm)
+        };
+        let history = vec![
+            at(10, "Words: 25", 40.0, 90.0),
+            at(9, "Words: 25", 50.0, 100.0),
+            at(8, "Time: 15s", 70.0, 95.0),
+            at(1, "Words: 25", 60.0, 95.0),
+            at(0, "Words: 25", 50.0, 100.0),
+        ];
+
+        let summary = summarize(&history, today);
+        assert_eq!(summary.total_tests, 5);
+        assert_eq!(summary.best_wpm, 70.0);
+        assert!((summary.average_wpm - 54.0).abs() < 1e-9);
+        let words = &summary.modes["Words: 25"];
+        assert_eq!(words.tests, 4);
+        assert_eq!(words.average_wpm, 50.0);
+        assert_eq!(words.best_wpm, 60.0);
+        assert_eq!(words.best_accuracy, 100.0);
+        assert_eq!(summary.longest_streak_days, 3);
+        assert_eq!(summary.current_streak_days, 2);
+
+        // A streak lapses after a full day without tests
+        let later = today + chrono::Duration::days(2);
+        assert_eq!(summarize(&history, later).current_streak_days, 0);
+
+        let empty = summarize(&[], today);
+        assert_eq!(empty.total_tests, 0);
+        assert_eq!(empty.average_wpm, 0.0);
+        assert!(empty.last_test.is_none());
+    }
+
+    #[test]
+    fn test_write_summary_replaces_file() {
+        let path = crate::paths::data_dir().join("stats.json");
+        fs::write(&path, "old").unwrap();
+        write_summary_to(&path, &summarize(&[result_with("Words: 10", 42.0)], Local::now().date_naive())).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["total_tests"], 1);
+        assert_eq!(written["modes"]["Words: 10"]["best_wpm"], 42.0);
+        let _ = fs::remove_file(&path);
     }
 }
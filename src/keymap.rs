@@ -0,0 +1,457 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::{BitAnd, BitOr, BitOrAssign};
+
+/// A key on the keyboard. The engine has its own key types so it doesn't depend on a terminal
+/// library; the binary translates crossterm's events into them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    Char(char),
+    Enter,
+    Esc,
+    Tab,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+/// Modifier keys held down with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    pub const NONE: Self = Self(0);
+    pub const SHIFT: Self = Self(1);
+    pub const CONTROL: Self = Self(1 << 1);
+    pub const ALT: Self = Self(1 << 2);
+
+    /// Whether every modifier in `other` is held.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for KeyModifiers {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for KeyModifiers {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAnd for KeyModifiers {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+/// A key press, as fed to [`crate::App::handle_key_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyEvent {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyCode> for KeyEvent {
+    fn from(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+/// Which screen a binding applies on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Welcome,
+    Typing,
+    Results,
+    /// The results screen while its numbers are hidden until revealed.
+    Hidden,
+    History,
+    Details,
+    Settings,
+    Review,
+    Tutorial,
+    Drills,
+}
+
+/// Something a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Start,
+    Resume,
+    Discard,
+    Shuffle,
+    WordMode,
+    TimeMode,
+    Punctuation,
+    Numbers,
+    Cursor,
+    FinishWord,
+    EnterAsSpace,
+    PracticeLoop,
+    Beginner,
+    SuddenDeath,
+    OpenSettings,
+    OpenHistory,
+    Cancel,
+    Clear,
+    Focus,
+    Restart,
+    Reveal,
+    Harder,
+    Drill,
+    ComboDrill,
+    OpenDrills,
+    Profile,
+    Average,
+    ChartView,
+    Review,
+    Export,
+    ExportData,
+    Back,
+    Up,
+    Down,
+    Details,
+    Mark,
+    Compare,
+    Trend,
+    Next,
+    Previous,
+    NextError,
+    PreviousError,
+    Repeat,
+}
+
+/// Every action with its config name, the screens it works on and its default keys. An action
+/// whose defaults differ between screens has a row for each; remapping it replaces them all.
+const ACTIONS: &[(Action, &str, &[Scope], &[&str])] = &[
+    (Action::Quit, "quit", &[Scope::Welcome, Scope::Results, Scope::Hidden], &["q", "esc"]),
+    (Action::Start, "start", &[Scope::Welcome, Scope::Drills], &["enter"]),
+    (Action::Resume, "resume", &[Scope::Welcome], &["r"]),
+    (Action::Discard, "discard", &[Scope::Welcome], &["x"]),
+    (Action::Shuffle, "shuffle", &[Scope::Welcome], &["g"]),
+    (Action::WordMode, "word_mode", &[Scope::Welcome], &["w"]),
+    (Action::TimeMode, "time_mode", &[Scope::Welcome], &["t"]),
+    (Action::Punctuation, "punctuation", &[Scope::Welcome], &["p"]),
+    (Action::Numbers, "numbers", &[Scope::Welcome], &["n"]),
+    (Action::Cursor, "cursor", &[Scope::Welcome], &["c"]),
+    (Action::FinishWord, "finish_word", &[Scope::Welcome], &["f"]),
+    (Action::EnterAsSpace, "enter_as_space", &[Scope::Welcome], &["e"]),
+    (Action::PracticeLoop, "practice_loop", &[Scope::Welcome], &["l"]),
+    (Action::Beginner, "beginner", &[Scope::Welcome], &["b"]),
+    (Action::SuddenDeath, "sudden_death", &[Scope::Welcome], &["d"]),
+    (Action::ComboDrill, "combo_drill", &[Scope::Welcome], &["o"]),
+    (Action::OpenDrills, "drills", &[Scope::Welcome], &["i"]),
+    (Action::Profile, "profile", &[Scope::Welcome], &["u"]),
+    (Action::OpenSettings, "settings", &[Scope::Welcome], &["s"]),
+    (Action::OpenHistory, "history", &[Scope::Welcome], &["h"]),
+    (Action::Cancel, "cancel", &[Scope::Typing], &["esc"]),
+    (Action::Clear, "clear", &[Scope::Typing], &["ctrl+u"]),
+    (Action::Focus, "focus", &[Scope::Typing], &["ctrl+f"]),
+    (Action::Restart, "restart", &[Scope::Results], &["enter", "r"]),
+    (Action::Reveal, "reveal", &[Scope::Hidden], &["space"]),
+    (Action::Harder, "harder", &[Scope::Results], &["+"]),
+    (Action::Drill, "drill", &[Scope::Results], &["m"]),
+    (Action::Average, "average", &[Scope::Results, Scope::Details], &["a"]),
+    (Action::ChartView, "chart", &[Scope::Results], &["c"]),
+    (Action::Review, "review", &[Scope::Results], &["v"]),
+    (Action::Export, "export", &[Scope::Results, Scope::Details], &["e"]),
+    (Action::ExportData, "export_data", &[Scope::Results], &["d"]),
+    (Action::Back, "back", &[Scope::History, Scope::Details, Scope::Settings, Scope::Review, Scope::Tutorial, Scope::Drills], &["esc", "q"]),
+    (Action::Back, "back", &[Scope::Details], &["backspace"]),
+    (Action::Up, "up", &[Scope::History, Scope::Settings, Scope::Review, Scope::Drills], &["up", "k"]),
+    (Action::Down, "down", &[Scope::History, Scope::Settings, Scope::Review, Scope::Drills], &["down", "j"]),
+    (Action::Details, "details", &[Scope::History], &["enter"]),
+    (Action::Mark, "mark", &[Scope::History], &["m"]),
+    (Action::Compare, "compare", &[Scope::History], &["c"]),
+    (Action::Trend, "trend", &[Scope::History], &["t"]),
+    (Action::Next, "next", &[Scope::Settings, Scope::Tutorial], &["enter", "right", "l"]),
+    (Action::Previous, "previous", &[Scope::Settings, Scope::Tutorial], &["left", "h"]),
+    (Action::Repeat, "repeat", &[Scope::Details], &["r"]),
+    (Action::NextError, "next_error", &[Scope::Details], &["right", "l"]),
+    (Action::PreviousError, "previous_error", &[Scope::Details], &["left", "h"]),
+];
+
+/// Keys for one action in the settings file: either `"r"` or `["r", "space"]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn specs(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(spec) => vec![spec.as_str()],
+            KeyList::Many(specs) => specs.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// A key plus the Ctrl/Alt modifiers that must be held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// The binding a key press matches. Shift is already reflected in the character itself.
+    fn from_event(key: KeyEvent) -> Self {
+        let modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match key.code {
+            KeyCode::Char(c) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.modifiers.is_empty() => write!(f, "{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Parses a key spec such as `"q"`, `"space"`, `"enter"` or `"ctrl+u"`.
+pub fn parse_key(spec: &str) -> Option<KeyBinding> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec.trim();
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if lower.starts_with("ctrl+") && rest.len() > 5 {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = &rest[5..];
+        } else if lower.starts_with("alt+") && rest.len() > 4 {
+            modifiers |= KeyModifiers::ALT;
+            rest = &rest[4..];
+        } else {
+            break;
+        }
+    }
+
+    let code = match rest.to_ascii_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        name => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if modifiers.is_empty() => KeyCode::Char(c),
+                (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+                _ => KeyCode::F(name.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+            }
+        }
+    };
+    Some(KeyBinding { code, modifiers })
+}
+
+/// Which action each key triggers on each screen.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<(Scope, KeyBinding), Action>,
+    keys: HashMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&BTreeMap::new()).0
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the defaults with `overrides` (action name to keys) replacing the
+    /// keys of the actions they name.
+    ///
+    /// Problems don't stop the app: unknown actions, unparseable keys and plain characters bound
+    /// while typing are skipped, and when two actions on the same screen want the same key the
+    /// remapped one wins (or the one listed first). Each of these comes back as a warning.
+    pub fn from_config(overrides: &BTreeMap<String, KeyList>) -> (Keymap, Vec<String>) {
+        let mut warnings = Vec::new();
+        for name in overrides.keys() {
+            if !ACTIONS.iter().any(|(_, n, _, _)| n == name) {
+                warnings.push(format!("unknown action \"{}\" in keybindings", name));
+            }
+        }
+
+        // Remapped actions claim their keys first so they win conflicts with defaults
+        let mut order: Vec<_> = ACTIONS.iter().filter(|(_, name, _, _)| overrides.contains_key(*name)).collect();
+        order.extend(ACTIONS.iter().filter(|(_, name, _, _)| !overrides.contains_key(*name)));
+
+        let mut keymap = Keymap { bindings: HashMap::new(), keys: HashMap::new() };
+        let mut remapped = HashSet::new();
+        for &(action, name, scopes, defaults) in order {
+            let specs = match overrides.get(name) {
+                // The remapped keys go on the screens of the action's first row only
+                Some(_) if !remapped.insert(name) => continue,
+                Some(list) => list.specs(),
+                None => defaults.to_vec(),
+            };
+            for spec in specs {
+                let Some(binding) = parse_key(spec) else {
+                    warnings.push(format!("can't parse key \"{}\" for \"{}\"", spec, name));
+                    continue;
+                };
+                let mut bound = false;
+                for &scope in scopes {
+                    if scope == Scope::Typing && binding.modifiers.is_empty() && matches!(binding.code, KeyCode::Char(_)) {
+                        warnings.push(format!("\"{}\" can't use \"{}\" because it is needed for typing", name, spec));
+                        continue;
+                    }
+                    match keymap.bindings.get(&(scope, binding)) {
+                        Some(&other) => warnings.push(format!(
+                            "\"{}\" is bound to both \"{}\" and \"{}\" on the {:?} screen, using \"{}\"",
+                            spec,
+                            action_name(other),
+                            name,
+                            scope,
+                            action_name(other)
+                        )),
+                        None => {
+                            keymap.bindings.insert((scope, binding), action);
+                            bound = true;
+                        }
+                    }
+                }
+                if bound {
+                    keymap.keys.entry(action).or_default().push(binding);
+                }
+            }
+        }
+        (keymap, warnings)
+    }
+
+    /// The action `key` triggers on `scope`, if any.
+    pub fn action(&self, scope: Scope, key: KeyEvent) -> Option<Action> {
+        self.bindings.get(&(scope, KeyBinding::from_event(key))).copied()
+    }
+
+    /// The first key bound to `action`, for hints like "<Enter> restart".
+    pub fn label(&self, action: Action) -> String {
+        self.bound(action).unwrap_or_else(|| "unbound".to_string())
+    }
+
+    /// Like [`Keymap::label`], but `None` when the action has no keys at all.
+    pub fn bound(&self, action: Action) -> Option<String> {
+        self.keys.get(&action).and_then(|keys| keys.first()).map(|binding| binding.to_string())
+    }
+}
+
+fn action_name(action: Action) -> &'static str {
+    ACTIONS.iter().find(|(a, _, _, _)| *a == action).map(|(_, name, _, _)| *name).unwrap_or("?")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pairs: &[(&str, &[&str])]) -> BTreeMap<String, KeyList> {
+        pairs
+            .iter()
+            .map(|(name, specs)| (name.to_string(), KeyList::Many(specs.iter().map(|s| s.to_string()).collect())))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("q"), Some(KeyBinding { code: KeyCode::Char('q'), modifiers: KeyModifiers::NONE }));
+        assert_eq!(parse_key("Space").unwrap().code, KeyCode::Char(' '));
+        assert_eq!(parse_key("ctrl+U"), Some(KeyBinding { code: KeyCode::Char('u'), modifiers: KeyModifiers::CONTROL }));
+        assert_eq!(parse_key("f5").unwrap().code, KeyCode::F(5));
+        assert_eq!(parse_key("nope"), None);
+        assert_eq!(parse_key("ctrl+"), None);
+    }
+
+    #[test]
+    fn test_defaults_and_overrides() {
+        let keymap = Keymap::default();
+        let enter = KeyEvent::from(KeyCode::Enter);
+        assert_eq!(keymap.action(Scope::Results, enter), Some(Action::Restart));
+        assert_eq!(keymap.action(Scope::Welcome, enter), Some(Action::Start));
+        assert_eq!(keymap.label(Action::Clear), "Ctrl+U");
+
+        let (keymap, warnings) = Keymap::from_config(&keys(&[("restart", &["space"]), ("quit", &["z"])]));
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let space = KeyEvent::from(KeyCode::Char(' '));
+        assert_eq!(keymap.action(Scope::Results, space), Some(Action::Restart));
+        assert_eq!(keymap.action(Scope::Results, enter), None);
+        assert_eq!(keymap.action(Scope::Welcome, KeyEvent::from(KeyCode::Char('z'))), Some(Action::Quit));
+        assert_eq!(keymap.action(Scope::Welcome, KeyEvent::from(KeyCode::Char('q'))), None);
+
+        // Backspace only goes back from a run's details, and remapping back replaces it too
+        let backspace = KeyEvent::from(KeyCode::Backspace);
+        assert_eq!(Keymap::default().action(Scope::Details, backspace), Some(Action::Back));
+        assert_eq!(Keymap::default().action(Scope::Settings, backspace), None);
+        let (keymap, warnings) = Keymap::from_config(&keys(&[("back", &["b"])]));
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(keymap.action(Scope::Details, backspace), None);
+        assert_eq!(keymap.action(Scope::Details, KeyEvent::from(KeyCode::Char('b'))), Some(Action::Back));
+    }
+
+    #[test]
+    fn test_conflicts_and_bad_entries_warn() {
+        // "m" is drill's default on Results; the remapped restart takes it
+        let (keymap, warnings) = Keymap::from_config(&keys(&[
+            ("restart", &["m"]),
+            ("clear", &["u"]),
+            ("bogus", &["x"]),
+            ("drill", &["???"]),
+        ]));
+        assert_eq!(keymap.action(Scope::Results, KeyEvent::from(KeyCode::Char('m'))), Some(Action::Restart));
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings.iter().any(|w| w.contains("bogus")));
+        assert!(warnings.iter().any(|w| w.contains("needed for typing")));
+        assert!(warnings.iter().any(|w| w.contains("???")));
+    }
+}
@@ -0,0 +1,50 @@
+//! The TypeStorm typing engine.
+//!
+//! [`App`] is the complete test state machine used by the terminal UI. It can also be driven
+//! without a terminal by feeding it input directly:
+//!
+//! ```
+//! use typestorm::{App, AppMode};
+//!
+//! let mut app = App::default();
+//! app.start_typing();
+//! let text = app.target_text.clone();
+//! for c in text.chars().take(3) {
+//!     app.type_char(c);
+//! }
+//! assert_eq!(app.mode, AppMode::Typing);
+//! assert_eq!(app.calculate_accuracy(), 100.0);
+//! ```
+//!
+//! Finishing a test (by typing all of the text, running out of time, or calling
+//! [`App::finish`]) records the result in the history file.
+//!
+//! Key presses go to [`App::handle_key_event`] as the engine's own [`keymap::KeyEvent`], so
+//! nothing here depends on a terminal library:
+//!
+//! ```
+//! use typestorm::keymap::{KeyCode, KeyEvent};
+//! use typestorm::{App, AppMode};
+//!
+//! let mut app = App::default();
+//! app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+//! assert_eq!(app.mode, AppMode::Typing);
+//! ```
+
+pub mod app;
+pub mod bench;
+pub mod clock;
+pub mod export;
+pub mod history;
+pub mod keymap;
+pub mod paths;
+pub mod settings;
+pub mod snapshot;
+pub mod stats;
+pub mod svg;
+pub mod text;
+pub mod theme;
+pub mod words;
+
+pub use app::{App, AppMode, TestMode};
+pub use history::TestResult;
@@ -1,30 +1,220 @@
 use anyhow::Result;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    cursor::SetCursorStyle,
+    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use ratatui::{backend::CrosstermBackend, widgets::TableState, Terminal};
+use std::io::{self, Write};
+use std::path::PathBuf;
 
-mod app;
 mod ui;
-mod words;
-mod history;
 
-use app::App;
+use typestorm::keymap::{KeyCode, KeyEvent, KeyModifiers, Keymap};
+use typestorm::{export, history, theme, App};
+
+const USAGE: &str = "Usage: typestorm [--profile <name>] [--no-history] [--theme <name>] [--stats-json <path>] [--bench]
+       typestorm [--profile <name>] --export-csv <path> [--export-csv-summary]
+       typestorm [--profile <name>] --export-json <path>
+       typestorm [--profile <name>] --import <path>
+
+Options:
+  --profile <name>
+                  Use a separate settings and history for <name> (letters, digits, - and _),
+                  e.g. per keyboard or per person; created on first use
+  --no-history    Don't read or write the history file for this run
+  --bench         Run a scripted test without the UI and print its metrics as JSON
+  --theme <name>  Color theme (default, mono, ocean, light, deuteranopia); overrides TYPESTORM_THEME
+  --stats-json <path>
+                  On exit, write aggregate stats over the whole history to <path> as JSON
+  --export-csv <path>
+                  Write the history to <path> as CSV, one row per run, and exit
+  --export-csv-summary
+                  With --export-csv, write one row per mode (count, average and best WPM,
+                  average accuracy) instead
+  --export-json <path>
+                  Write the history to <path> as one readable JSON document and exit
+  --import <path> Merge the history file at <path>, e.g. from another machine, into this
+                  history and exit; runs already here are skipped
+  -h, --help      Print this help";
+
+/// Command line flags.
+#[derive(Debug, Default)]
+struct Args {
+    profile: Option<String>,
+    no_history: bool,
+    bench: bool,
+    theme: Option<String>,
+    stats_json: Option<PathBuf>,
+    export_csv: Option<PathBuf>,
+    export_csv_summary: bool,
+    export_json: Option<PathBuf>,
+    import: Option<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut argv = std::env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "--profile" => match argv.next() {
+                Some(name) => args.profile = Some(name),
+                None => {
+                    eprintln!("--profile needs a profile name\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--profile=") => args.profile = Some(other["--profile=".len()..].to_string()),
+            "--no-history" => args.no_history = true,
+            "--bench" => args.bench = true,
+            "--theme" => match argv.next() {
+                Some(name) => args.theme = Some(name),
+                None => {
+                    eprintln!("--theme needs a theme name\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--theme=") => args.theme = Some(other["--theme=".len()..].to_string()),
+            "--stats-json" => match argv.next() {
+                Some(path) => args.stats_json = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--stats-json needs a file path\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--stats-json=") => {
+                args.stats_json = Some(PathBuf::from(&other["--stats-json=".len()..]))
+            }
+            "--export-csv" => match argv.next() {
+                Some(path) => args.export_csv = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--export-csv needs a file path\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--export-csv=") => {
+                args.export_csv = Some(PathBuf::from(&other["--export-csv=".len()..]))
+            }
+            "--export-csv-summary" => args.export_csv_summary = true,
+            "--export-json" => match argv.next() {
+                Some(path) => args.export_json = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--export-json needs a file path\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--export-json=") => {
+                args.export_json = Some(PathBuf::from(&other["--export-json=".len()..]))
+            }
+            "--import" => match argv.next() {
+                Some(path) => args.import = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("--import needs a file path\n\n{}", USAGE);
+                    std::process::exit(2);
+                }
+            },
+            other if other.starts_with("--import=") => args.import = Some(PathBuf::from(&other["--import=".len()..])),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => {
+                eprintln!("Unknown argument: {}\n\n{}", other, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+    if let Some(name) = &args.profile {
+        if name != "default" && !typestorm::paths::valid_profile_name(name) {
+            eprintln!("Invalid profile name: {} (use letters, digits, - and _)\n\n{}", name, USAGE);
+            std::process::exit(2);
+        }
+    }
+    if args.export_csv_summary && args.export_csv.is_none() {
+        eprintln!("--export-csv-summary needs --export-csv <path>\n\n{}", USAGE);
+        std::process::exit(2);
+    }
+    args
+}
 
 fn main() -> Result<()> {
+    let args = parse_args();
+
+    if args.bench {
+        let report = typestorm::bench::run();
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    // Resolve where data lives before the alternate screen hides any warning about it
+    typestorm::paths::data_dir();
+    // `default` names the default profile, whose files have no profile in their name
+    typestorm::paths::set_profile(args.profile.clone().filter(|name| name != "default"));
+
+    if args.export_csv.is_some() || args.export_json.is_some() {
+        if args.no_history {
+            eprintln!("Exporting reads the history, which --no-history turns off");
+            std::process::exit(2);
+        }
+        let (history, _) = history::load_history()?;
+        if let Some(path) = &args.export_csv {
+            export::write_csv_to(path, &history, args.export_csv_summary)?;
+            println!("Exported {} runs to {}", history.len(), path.display());
+        }
+        if let Some(path) = &args.export_json {
+            export::write_json_to(path, &history)?;
+            println!("Exported {} runs to {}", history.len(), path.display());
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = &args.import {
+        if args.no_history {
+            eprintln!("Importing writes the history, which --no-history turns off");
+            std::process::exit(2);
+        }
+        let imported = history::read_import(path)?;
+        let total = imported.len();
+        let (mut history, _) = history::load_history()?;
+        let added = history::merge_history(&mut history, imported);
+        if added > 0 {
+            history::save_history(&history)?;
+        }
+        println!(
+            "Imported {} new runs from {} ({} already in the history)",
+            added,
+            path.display(),
+            total - added
+        );
+        return Ok(());
+    }
+
+    // Load the app and report any theme or keybinding problem before the alternate screen hides it
+    let mut app = App::load(args.no_history);
+    let env_theme = std::env::var("TYPESTORM_THEME").ok();
+    let (theme, warning) = theme::resolve(args.theme.as_deref(), env_theme.as_deref(), app.settings.theme.as_deref());
+    if let Some(warning) = warning {
+        eprintln!("warning: {}", warning);
+    }
+    app.theme = theme;
+    app.theme_override = args.theme.is_some() || env_theme.is_some();
+    let (keymap, warnings) = Keymap::from_config(&app.settings.keybindings);
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    app.keymap = keymap;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    // Bracketed paste delivers a paste as one event instead of a burst of typed keys
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    // The terminal cursor only shows up as the Bar cursor style, which this shapes it for
+    execute!(stdout, SetCursorStyle::SteadyBar)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app
-    let mut app = App::new();
-
     // Run app loop
     let res = run_app(&mut terminal, &mut app);
 
@@ -33,26 +223,106 @@ fn main() -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        SetCursorStyle::DefaultUserShape
     )?;
     terminal.show_cursor()?;
 
     if let Err(err) = res {
         println!("{:?}", err);
+    } else if let Some(path) = &args.stats_json {
+        if app.history_disabled {
+            eprintln!("warning: history is disabled, not writing {}", path.display());
+        } else {
+            let summary = history::summarize(&app.history, chrono::Local::now().date_naive());
+            if let Err(err) = history::write_summary_to(path, &summary) {
+                eprintln!("warning: couldn't write {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    for path in &app.exported_files {
+        println!("Saved {}", path.display());
+    }
+
+    if let Some(summary) = app.session_summary() {
+        println!("{}", summary);
     }
 
     Ok(())
 }
 
 fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    let mut history_state = TableState::default();
     loop {
-        terminal.draw(|f| ui::draw(f, app))?;
+        // Idle screens are only redrawn when something on them changes
+        if app.dirty {
+            terminal.draw(|f| ui::draw(f, app, &mut history_state))?;
+            app.dirty = false;
+        }
+        if std::mem::take(&mut app.ring_bell) {
+            // BEL: terminals with the bell enabled play their alert sound
+            io::stdout().write_all(b"\x07")?;
+            io::stdout().flush()?;
+        }
 
         app.tick(); // Update time-based logic
-        app.handle_events()?;
+        handle_events(app)?;
 
         if !app.running {
             return Ok(());
         }
     }
 }
+
+/// Waits up to the app's poll timeout for a terminal event and hands it to the app.
+fn handle_events(app: &mut App) -> Result<()> {
+    if event::poll(app.poll_timeout())? {
+        match event::read()? {
+            Event::Key(key) => {
+                if let Some(key) = key_event(key) {
+                    app.handle_key_event(key);
+                }
+            }
+            Event::Paste(text) => app.handle_paste(&text),
+            Event::Resize(_, _) => app.dirty = true,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The engine's version of a crossterm key press, or `None` for keys it has no use for.
+fn key_event(key: event::KeyEvent) -> Option<KeyEvent> {
+    use event::KeyCode as Code;
+    let code = match key.code {
+        Code::Char(c) => KeyCode::Char(c),
+        Code::Enter => KeyCode::Enter,
+        Code::Esc => KeyCode::Esc,
+        Code::Tab => KeyCode::Tab,
+        Code::Backspace => KeyCode::Backspace,
+        Code::Delete => KeyCode::Delete,
+        Code::Up => KeyCode::Up,
+        Code::Down => KeyCode::Down,
+        Code::Left => KeyCode::Left,
+        Code::Right => KeyCode::Right,
+        Code::Home => KeyCode::Home,
+        Code::End => KeyCode::End,
+        Code::PageUp => KeyCode::PageUp,
+        Code::PageDown => KeyCode::PageDown,
+        Code::F(n) => KeyCode::F(n),
+        _ => return None,
+    };
+    let mut modifiers = KeyModifiers::NONE;
+    for (from, to) in [
+        (event::KeyModifiers::SHIFT, KeyModifiers::SHIFT),
+        (event::KeyModifiers::CONTROL, KeyModifiers::CONTROL),
+        (event::KeyModifiers::ALT, KeyModifiers::ALT),
+    ] {
+        if key.modifiers.contains(from) {
+            modifiers |= to;
+        }
+    }
+    Some(KeyEvent::new(code, modifiers))
+}
@@ -0,0 +1,156 @@
+use std::path::PathBuf;
+
+/// Directory that TypeStorm keeps its files in.
+///
+/// This is the home directory, or the system temp directory when there is no home directory
+/// (as in some containers and CI sandboxes). The fallback is reported once on stderr; history
+/// then only persists for as long as the temp directory does. Each test gets an empty directory
+/// of its own instead.
+pub fn data_dir() -> PathBuf {
+    #[cfg(test)]
+    return TEST_DIR.with(|dir| dir.0.clone());
+
+    #[cfg(not(test))]
+    {
+        static DATA_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+        DATA_DIR
+            .get_or_init(|| match dirs::home_dir() {
+                Some(home) => home,
+                None => {
+                    let fallback = std::env::temp_dir();
+                    eprintln!(
+                        "warning: could not find home directory, storing TypeStorm data in {}",
+                        fallback.display()
+                    );
+                    fallback
+                }
+            })
+            .clone()
+    }
+}
+
+/// A directory of its own for each test's files, removed when the test is over.
+#[cfg(test)]
+struct TestDir(PathBuf);
+
+#[cfg(test)]
+impl TestDir {
+    fn new() -> Self {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        // Named after the process too, so nothing left by an earlier run is read back
+        let id = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("typestorm_test_{}_{}", std::process::id(), id));
+        std::fs::create_dir_all(&dir).expect("Failed to create test directory");
+        TestDir(dir)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    // Each test runs on its own thread, which drops the directory as it finishes
+    static TEST_DIR: TestDir = TestDir::new();
+}
+
+#[cfg(not(test))]
+static PROFILE: std::sync::RwLock<Option<String>> = std::sync::RwLock::new(None);
+
+#[cfg(test)]
+thread_local! {
+    // Each test runs on its own thread, so one switching profiles doesn't move another's files
+    static PROFILE: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+/// The active profile, or `None` for the default one.
+pub fn profile() -> Option<String> {
+    #[cfg(test)]
+    return PROFILE.with(|profile| profile.borrow().clone());
+
+    #[cfg(not(test))]
+    PROFILE.read().map(|profile| profile.clone()).unwrap_or_default()
+}
+
+/// Switches the files that settings, history and snapshots are read from and written to.
+pub fn set_profile(name: Option<String>) {
+    #[cfg(test)]
+    PROFILE.with(|profile| *profile.borrow_mut() = name);
+
+    #[cfg(not(test))]
+    if let Ok(mut profile) = PROFILE.write() {
+        *profile = name;
+    }
+}
+
+/// Whether `name` can be used as a profile: letters, digits, `-` and `_`, so it's safe in a
+/// file name. `default` is taken by the default profile.
+pub fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "default"
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The part of a data file's name after `.typestorm_`, for the active profile: `history` for
+/// the default profile (so existing files keep working) and e.g. `work_history` for `work`.
+pub fn profile_stem(kind: &str) -> String {
+    match profile() {
+        Some(profile) => format!("{}_{}", profile, kind),
+        None => kind.to_string(),
+    }
+}
+
+/// The profile a data file belongs to, e.g. `work` for `.typestorm_work_settings.json`.
+fn profile_of(file_name: &str) -> Option<&str> {
+    let rest = file_name.strip_prefix(".typestorm_")?;
+    let name = rest
+        .strip_suffix("_settings.json")
+        .or_else(|| rest.strip_suffix("_history.json"))?;
+    valid_profile_name(name).then_some(name)
+}
+
+/// Named profiles that have settings or history saved, sorted.
+pub fn profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = std::fs::read_dir(data_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| profile_of(&entry.file_name().to_string_lossy()).map(str::to_string))
+        .collect();
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_stem() {
+        assert_eq!(profile_stem("history"), "history");
+        set_profile(Some("work".to_string()));
+        assert_eq!(profile_stem("history"), "work_history");
+        set_profile(None);
+        assert_eq!(profile_stem("settings"), "settings");
+    }
+
+    #[test]
+    fn test_profile_names() {
+        assert!(valid_profile_name("ergo-split_2"));
+        assert!(!valid_profile_name("default"));
+        assert!(!valid_profile_name("../home"));
+        assert!(!valid_profile_name(""));
+
+        assert_eq!(profile_of(".typestorm_work_settings.json"), Some("work"));
+        assert_eq!(profile_of(".typestorm_laptop_history.json"), Some("laptop"));
+        // The default profile's own files
+        assert_eq!(profile_of(".typestorm_history.json"), None);
+        assert_eq!(profile_of(".typestorm_settings.json"), None);
+        assert_eq!(profile_of(".typestorm_work_in_progress.json"), None);
+    }
+}
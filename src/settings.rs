@@ -0,0 +1,532 @@
+use crate::app::TestMode;
+use crate::keymap::KeyList;
+use crate::words::{NumberOptions, PunctuationLevel};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CursorStyle {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+impl CursorStyle {
+    pub fn next(self) -> Self {
+        match self {
+            CursorStyle::Block => CursorStyle::Underline,
+            CursorStyle::Underline => CursorStyle::Bar,
+            CursorStyle::Bar => CursorStyle::Block,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            CursorStyle::Block => CursorStyle::Bar,
+            CursorStyle::Underline => CursorStyle::Block,
+            CursorStyle::Bar => CursorStyle::Underline,
+        }
+    }
+}
+
+impl std::fmt::Display for CursorStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CursorStyle::Block => write!(f, "Block"),
+            CursorStyle::Underline => write!(f, "Underline"),
+            CursorStyle::Bar => write!(f, "Bar"),
+        }
+    }
+}
+
+/// Where the live accuracy gauge changes color: green at `green` percent and up, yellow at
+/// `yellow` and up, red below that.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccuracyThresholds {
+    pub green: f64,
+    pub yellow: f64,
+}
+
+impl Default for AccuracyThresholds {
+    fn default() -> Self {
+        Self { green: 95.0, yellow: 85.0 }
+    }
+}
+
+/// When the clock of a test starts running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimerStart {
+    /// With the first key typed, so reading the first word isn't timed.
+    #[default]
+    OnFirstKey,
+    /// As soon as the test is on screen, like some typing sites do.
+    OnStart,
+}
+
+impl TimerStart {
+    pub fn toggle(self) -> Self {
+        match self {
+            TimerStart::OnFirstKey => TimerStart::OnStart,
+            TimerStart::OnStart => TimerStart::OnFirstKey,
+        }
+    }
+}
+
+impl std::fmt::Display for TimerStart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimerStart::OnFirstKey => write!(f, "First key"),
+            TimerStart::OnStart => write!(f, "Test start"),
+        }
+    }
+}
+
+/// Where the text of a test comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextSource {
+    /// Random words from the word list, with punctuation and numbers mixed in if turned on.
+    #[default]
+    Words,
+    /// Real sentences with their own capitals and punctuation, one after another.
+    Sentences,
+}
+
+impl TextSource {
+    pub fn toggle(self) -> Self {
+        match self {
+            TextSource::Words => TextSource::Sentences,
+            TextSource::Sentences => TextSource::Words,
+        }
+    }
+
+    /// `mode` as it's recorded in the history, e.g. "Words: 25", or "Words: 25 (sentences)".
+    pub fn mode_label(self, mode: TestMode) -> String {
+        match self {
+            TextSource::Words => mode.to_string(),
+            TextSource::Sentences => format!("{} (sentences)", mode),
+        }
+    }
+}
+
+impl std::fmt::Display for TextSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextSource::Words => write!(f, "Random words"),
+            TextSource::Sentences => write!(f, "Sentences"),
+        }
+    }
+}
+
+/// How the WPM curve is drawn between samples. The spline looks smoothest but can overshoot
+/// spiky data, so the straight-line and averaged views are there for an honest look.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChartSmoothing {
+    Raw,
+    MovingAverage,
+    #[default]
+    Spline,
+}
+
+impl ChartSmoothing {
+    pub fn next(self) -> Self {
+        match self {
+            ChartSmoothing::Raw => ChartSmoothing::MovingAverage,
+            ChartSmoothing::MovingAverage => ChartSmoothing::Spline,
+            ChartSmoothing::Spline => ChartSmoothing::Raw,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            ChartSmoothing::Raw => ChartSmoothing::Spline,
+            ChartSmoothing::MovingAverage => ChartSmoothing::Raw,
+            ChartSmoothing::Spline => ChartSmoothing::MovingAverage,
+        }
+    }
+}
+
+impl std::fmt::Display for ChartSmoothing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChartSmoothing::Raw => write!(f, "Raw"),
+            ChartSmoothing::MovingAverage => write!(f, "Moving avg"),
+            ChartSmoothing::Spline => write!(f, "Spline"),
+        }
+    }
+}
+
+/// What signals that a test is over, for Time mode especially, where the test ends on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CompletionCue {
+    #[default]
+    Off,
+    /// Ring the terminal bell, which most terminals play as a sound.
+    Bell,
+    /// Flash the whole screen for a moment.
+    Flash,
+    Both,
+}
+
+impl CompletionCue {
+    pub fn next(self) -> Self {
+        match self {
+            CompletionCue::Off => CompletionCue::Bell,
+            CompletionCue::Bell => CompletionCue::Flash,
+            CompletionCue::Flash => CompletionCue::Both,
+            CompletionCue::Both => CompletionCue::Off,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            CompletionCue::Off => CompletionCue::Both,
+            CompletionCue::Bell => CompletionCue::Off,
+            CompletionCue::Flash => CompletionCue::Bell,
+            CompletionCue::Both => CompletionCue::Flash,
+        }
+    }
+
+    pub fn bell(self) -> bool {
+        matches!(self, CompletionCue::Bell | CompletionCue::Both)
+    }
+
+    pub fn flash(self) -> bool {
+        matches!(self, CompletionCue::Flash | CompletionCue::Both)
+    }
+}
+
+impl std::fmt::Display for CompletionCue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompletionCue::Off => write!(f, "OFF"),
+            CompletionCue::Bell => write!(f, "Bell"),
+            CompletionCue::Flash => write!(f, "Flash"),
+            CompletionCue::Both => write!(f, "Bell + flash"),
+        }
+    }
+}
+
+/// What a paste during a test does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PasteHandling {
+    /// Drop the pasted text, so it can't pass for typing.
+    #[default]
+    Ignore,
+    /// Insert it in one go, left out of WPM.
+    Insert,
+}
+
+impl PasteHandling {
+    pub fn toggle(self) -> Self {
+        match self {
+            PasteHandling::Ignore => PasteHandling::Insert,
+            PasteHandling::Insert => PasteHandling::Ignore,
+        }
+    }
+}
+
+impl std::fmt::Display for PasteHandling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasteHandling::Ignore => write!(f, "Ignore"),
+            PasteHandling::Insert => write!(f, "Insert"),
+        }
+    }
+}
+
+/// How often a running test is saved for resuming after a crash. Saving more often loses less
+/// of the test if the terminal dies, at the cost of more disk writes; 0 of either turns saving off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapshotInterval {
+    Seconds(u64),
+    Keystrokes(usize),
+}
+
+impl SnapshotInterval {
+    pub fn enabled(self) -> bool {
+        !matches!(self, SnapshotInterval::Seconds(0) | SnapshotInterval::Keystrokes(0))
+    }
+}
+
+impl Default for SnapshotInterval {
+    fn default() -> Self {
+        SnapshotInterval::Seconds(2)
+    }
+}
+
+impl std::fmt::Display for SnapshotInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            _ if !self.enabled() => write!(f, "OFF"),
+            SnapshotInterval::Seconds(s) => write!(f, "Every {}s", s),
+            SnapshotInterval::Keystrokes(n) => write!(f, "Every {} keys", n),
+        }
+    }
+}
+
+/// Keyboard layout the user types on. Keys aren't remapped; this decides which letters the row
+/// drills use and where the keyboard heatmap draws each key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+impl KeyboardLayout {
+    /// The top, home and bottom letter rows, left to right as the keys sit on the keyboard.
+    pub fn rows(self) -> [&'static str; 3] {
+        match self {
+            KeyboardLayout::Qwerty => ["qwertyuiop", "asdfghjkl;", "zxcvbnm,./"],
+            KeyboardLayout::Dvorak => ["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+            KeyboardLayout::Colemak => ["qwfpgjluy;", "arstdhneio", "zxcvbkm,./"],
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            KeyboardLayout::Qwerty => KeyboardLayout::Dvorak,
+            KeyboardLayout::Dvorak => KeyboardLayout::Colemak,
+            KeyboardLayout::Colemak => KeyboardLayout::Qwerty,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            KeyboardLayout::Qwerty => KeyboardLayout::Colemak,
+            KeyboardLayout::Dvorak => KeyboardLayout::Qwerty,
+            KeyboardLayout::Colemak => KeyboardLayout::Dvorak,
+        }
+    }
+}
+
+impl std::fmt::Display for KeyboardLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyboardLayout::Qwerty => write!(f, "QWERTY"),
+            KeyboardLayout::Dvorak => write!(f, "Dvorak"),
+            KeyboardLayout::Colemak => write!(f, "Colemak"),
+        }
+    }
+}
+
+/// User preferences persisted between launches.
+///
+/// Every field has a default so settings files written by older versions keep loading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub test_mode: TestMode,
+    pub include_punctuation: bool,
+    /// Random words or whole sentences.
+    pub text_source: TextSource,
+    pub punctuation_level: PunctuationLevel,
+    pub include_numbers: bool,
+    pub cursor_style: CursorStyle,
+    /// Keep the cursor in the middle of one line and scroll the text past it.
+    pub fixed_cursor: bool,
+    /// How many characters after the cursor are drawn brighter than the rest; 0 turns it off.
+    pub lookahead_chars: usize,
+    /// Dim correctly typed characters once they're this many characters behind the cursor, so
+    /// the eye is drawn on to what's left; 0 turns it off.
+    pub fade_typed_chars: usize,
+    /// Beats per minute of the metronome pulsing in the footer while typing, to practice an even
+    /// rhythm; 0 turns it off. It has no effect on scoring.
+    pub metronome_bpm: u32,
+    /// Ring the terminal bell on every metronome beat as well.
+    pub metronome_bell: bool,
+    /// Overlay a moving average of the WPM samples on the performance chart.
+    pub show_rolling_average: bool,
+    /// Where the accuracy gauge in the footer turns from green to yellow to red.
+    pub accuracy_thresholds: AccuracyThresholds,
+    /// How the WPM line is drawn between samples.
+    pub chart_smoothing: ChartSmoothing,
+    /// Start the WPM chart from a (0, 0) point at the first keystroke instead of the first sample.
+    pub chart_from_origin: bool,
+    /// Hide the header and footer while typing.
+    pub focus_mode: bool,
+    /// In Time mode, let the word in progress be finished when the timer runs out.
+    pub finish_word_on_timeout: bool,
+    /// In Time mode, leave a word cut off by the timer out of the final WPM.
+    pub complete_words_wpm: bool,
+    /// In Words mode, end the test on a space after the last word instead of on its last letter.
+    pub finish_on_trailing_space: bool,
+    /// Hide the results of a finished test until a key is pressed, to guess the WPM first.
+    pub hide_results: bool,
+    /// Ring the bell and/or flash the screen when a test ends.
+    pub completion_cue: CompletionCue,
+    /// In Words mode, only end the test once the input matches the text exactly, so every error
+    /// has to be fixed.
+    pub require_correct_words: bool,
+    /// Sudden death: the first wrong keystroke ends the test.
+    pub sudden_death: bool,
+    /// Words at the start of each test typed as an unscored warmup; 0 turns it off.
+    pub warmup_words: usize,
+    /// Start the clock with the first key (the default) or as soon as the test is shown.
+    pub timer_start: TimerStart,
+    /// How often a running test is saved so it can be resumed after a crash, e.g.
+    /// `{"Seconds": 2}` or `{"Keystrokes": 50}`. More often is safer but writes to disk more.
+    pub snapshot_interval: SnapshotInterval,
+    /// What pasting text during a test does.
+    pub paste: PasteHandling,
+    /// Treat Enter as a space while typing, for when it gets hit by mistake between words.
+    pub enter_as_space: bool,
+    /// Runs shorter than this many seconds *and* characters are not saved to history.
+    pub min_record_seconds: u64,
+    pub min_record_chars: usize,
+    /// Density and range of numbers when numbers are enabled.
+    pub numbers: NumberOptions,
+    /// Keep history small by only saving the best runs of each mode plus the most recent runs.
+    pub compact_history: bool,
+    pub keep_best_per_mode: usize,
+    pub keep_recent: usize,
+    /// Never read or write the history file, e.g. on a shared machine.
+    pub no_history: bool,
+    /// Beginner mode: tests only use the most common short words.
+    pub beginner: bool,
+    /// After each result, wait `practice_loop_delay_secs` and start the next test automatically.
+    pub practice_loop: bool,
+    pub practice_loop_delay_secs: u64,
+    /// Letter combinations the combo drill practices (e.g. `["th", "qu"]`); empty drills all of them.
+    pub drill_combos: Vec<String>,
+    /// Name of a built-in theme; `--theme` and `TYPESTORM_THEME` take precedence.
+    pub theme: Option<String>,
+    /// Folder exported charts are saved to; the current directory when unset.
+    pub chart_export_dir: Option<PathBuf>,
+    /// Layout the keyboard is drawn in and the row drills follow; keys are never remapped.
+    pub keyboard_layout: KeyboardLayout,
+    /// Target WPM per mode, keyed by the mode's name as shown in history (e.g. `Words: 25`).
+    pub wpm_goals: BTreeMap<String, u32>,
+    /// Remapped keys, from action name (e.g. `restart`) to one key or a list of keys.
+    pub keybindings: BTreeMap<String, KeyList>,
+    /// The first-launch tutorial was finished or skipped, so it isn't shown again.
+    pub seen_tutorial: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            test_mode: TestMode::Words(10),
+            include_punctuation: false,
+            text_source: TextSource::default(),
+            punctuation_level: PunctuationLevel::default(),
+            include_numbers: false,
+            cursor_style: CursorStyle::default(),
+            fixed_cursor: false,
+            lookahead_chars: 0,
+            fade_typed_chars: 0,
+            metronome_bpm: 0,
+            metronome_bell: false,
+            show_rolling_average: true,
+            accuracy_thresholds: AccuracyThresholds::default(),
+            chart_smoothing: ChartSmoothing::default(),
+            chart_from_origin: false,
+            focus_mode: false,
+            finish_word_on_timeout: false,
+            complete_words_wpm: false,
+            finish_on_trailing_space: false,
+            require_correct_words: false,
+            sudden_death: false,
+            warmup_words: 0,
+            timer_start: TimerStart::default(),
+            hide_results: false,
+            completion_cue: CompletionCue::default(),
+            enter_as_space: false,
+            paste: PasteHandling::default(),
+            snapshot_interval: SnapshotInterval::default(),
+            min_record_seconds: 5,
+            min_record_chars: 10,
+            numbers: NumberOptions::default(),
+            compact_history: false,
+            keep_best_per_mode: 10,
+            keep_recent: 50,
+            no_history: false,
+            theme: None,
+            keyboard_layout: KeyboardLayout::default(),
+            chart_export_dir: None,
+            beginner: false,
+            practice_loop: false,
+            practice_loop_delay_secs: 3,
+            drill_combos: Vec::new(),
+            wpm_goals: BTreeMap::new(),
+            keybindings: BTreeMap::new(),
+            seen_tutorial: false,
+        }
+    }
+}
+
+pub fn get_settings_file_path() -> Result<PathBuf> {
+    Ok(crate::paths::data_dir().join(format!(".typestorm_{}.json", crate::paths::profile_stem("settings"))))
+}
+
+pub fn load_settings() -> Result<Settings> {
+    let path = get_settings_file_path()?;
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let settings: Settings = serde_json::from_str(&content)?;
+    Ok(settings)
+}
+
+pub fn save_settings(settings: &Settings) -> Result<()> {
+    let path = get_settings_file_path()?;
+    let content = serde_json::to_string_pretty(settings)?;
+    fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_persistence() {
+        let settings = Settings {
+            cursor_style: CursorStyle::Underline,
+            ..Default::default()
+        };
+        save_settings(&settings).expect("Failed to save settings");
+
+        let loaded = load_settings().expect("Failed to load settings");
+        assert_eq!(loaded.cursor_style, CursorStyle::Underline);
+    }
+
+    #[test]
+    fn test_wpm_goals_persistence() {
+        let mut settings = Settings::default();
+        settings.wpm_goals.insert("Words: 25".to_string(), 80);
+        settings.wpm_goals.insert("Time: 60s".to_string(), 70);
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(json.contains(r#""wpm_goals":{"Time: 60s":70,"Words: 25":80}"#));
+
+        save_settings(&settings).expect("Failed to save settings");
+        let loaded = load_settings().expect("Failed to load settings");
+        assert_eq!(loaded.wpm_goals, settings.wpm_goals);
+    }
+
+    #[test]
+    fn test_settings_missing_fields_use_defaults() {
+        let settings: Settings = serde_json::from_str("{}").expect("Failed to parse settings");
+        assert_eq!(settings.cursor_style, CursorStyle::Block);
+        assert_eq!(settings.keyboard_layout, KeyboardLayout::Qwerty);
+    }
+
+    #[test]
+    fn test_keyboard_layout_rows() {
+        // The right index finger's home key types a different letter on each layout
+        assert_eq!(KeyboardLayout::Qwerty.rows()[1].chars().nth(6), Some('j'));
+        assert_eq!(KeyboardLayout::Dvorak.rows()[1].chars().nth(6), Some('h'));
+        assert_eq!(KeyboardLayout::Colemak.rows()[1].chars().nth(6), Some('n'));
+        assert!([KeyboardLayout::Qwerty, KeyboardLayout::Dvorak, KeyboardLayout::Colemak]
+            .iter()
+            .all(|layout| layout.rows().iter().all(|row| row.chars().count() == 10)));
+    }
+}
@@ -0,0 +1,161 @@
+//! In-progress test snapshots, so a test cut short by a dead terminal or SSH session can be
+//! picked up again on the next launch.
+
+use crate::app::TestMode;
+use crate::settings::TextSource;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Everything needed to carry on with a running test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub test_mode: TestMode,
+    pub punctuation: bool,
+    pub numbers: bool,
+    pub drill_label: Option<String>,
+    /// Random words or sentences, for refilling the text and recording the mode.
+    #[serde(default)]
+    pub text_source: TextSource,
+    pub target_text: String,
+    /// Words of a long Words test that hadn't been added to the text yet.
+    #[serde(default)]
+    pub pending_words: usize,
+    pub input: String,
+    /// Seconds the test had been running when the snapshot was taken.
+    pub elapsed_secs: f64,
+    pub time_up: bool,
+    pub total_correct_strokes: usize,
+    pub total_incorrect_strokes: usize,
+    pub wpm_history: Vec<(f64, f64)>,
+    pub error_points: Vec<(f64, f64)>,
+    /// Character of the target text each error was typed at.
+    #[serde(default)]
+    pub error_positions: Vec<usize>,
+    pub keystroke_times: Vec<f64>,
+    /// When each character of the input was typed, for timing words.
+    #[serde(default)]
+    pub char_times: Vec<f64>,
+    pub missed_words: BTreeSet<usize>,
+    /// Still in the unscored warmup, and how many characters it took once it was over.
+    #[serde(default)]
+    pub warming_up: bool,
+    #[serde(default)]
+    pub measured_from: usize,
+    /// Characters of the input inserted by pasting, left out of WPM.
+    #[serde(default)]
+    pub pasted_ranges: Vec<Range<usize>>,
+}
+
+impl Snapshot {
+    /// Short description for the resume prompt, e.g. "Words: 25, 40% typed".
+    pub fn describe(&self) -> String {
+        let label = self.drill_label.clone().unwrap_or_else(|| self.text_source.mode_label(self.test_mode));
+        // Words not generated yet are counted at the length of the ones that were
+        let generated = crate::text::grapheme_count(&self.target_text);
+        let words = self.target_text.split(' ').count();
+        let pending = self.pending_words * (generated + 1) / words;
+        let total = (generated + pending).max(1);
+        let typed = crate::text::grapheme_count(&self.input).min(total);
+        format!("{}, {:.0}% typed", label, typed as f64 / total as f64 * 100.0)
+    }
+}
+
+pub fn get_snapshot_file_path() -> Result<PathBuf> {
+    Ok(crate::paths::data_dir().join(format!(".typestorm_{}.json", crate::paths::profile_stem("in_progress"))))
+}
+
+/// The snapshot left behind by an interrupted test, if there is a readable one.
+pub fn load_snapshot() -> Option<Snapshot> {
+    let content = fs::read_to_string(get_snapshot_file_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Writes `snapshot` via a temporary file, so a crash mid-write leaves the previous one intact.
+pub fn save_snapshot(snapshot: &Snapshot) -> Result<()> {
+    let path = get_snapshot_file_path()?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string(snapshot)?)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+pub fn clear_snapshot() -> Result<()> {
+    match fs::remove_file(get_snapshot_file_path()?) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_persistence() {
+        let snapshot = Snapshot {
+            test_mode: TestMode::Time(30),
+            punctuation: true,
+            numbers: false,
+            drill_label: None,
+            text_source: TextSource::Words,
+            target_text: "one two three four".to_string(),
+            pending_words: 0,
+            input: "one tw".to_string(),
+            elapsed_secs: 2.5,
+            time_up: false,
+            total_correct_strokes: 6,
+            total_incorrect_strokes: 1,
+            wpm_history: vec![(1.0, 48.0), (2.0, 36.0)],
+            error_points: vec![(1.5, 40.0)],
+            error_positions: vec![2],
+            keystroke_times: vec![0.0, 0.3, 0.6],
+            char_times: vec![0.0, 0.3, 0.6],
+            missed_words: BTreeSet::from([1]),
+            warming_up: false,
+            measured_from: 0,
+            pasted_ranges: vec![],
+        };
+        save_snapshot(&snapshot).unwrap();
+        assert_eq!(load_snapshot(), Some(snapshot.clone()));
+        assert_eq!(snapshot.describe(), "Time: 30s, 33% typed");
+
+        clear_snapshot().unwrap();
+        assert_eq!(load_snapshot(), None);
+        // Clearing twice is fine
+        clear_snapshot().unwrap();
+    }
+
+    #[test]
+    fn test_describe_counts_pending_words() {
+        // Halfway through a 100-word test whose second half isn't generated yet
+        let text = vec!["word"; 50].join(" ");
+        let snapshot = Snapshot {
+            test_mode: TestMode::Words(100),
+            punctuation: false,
+            numbers: false,
+            drill_label: None,
+            text_source: TextSource::Words,
+            target_text: text.clone(),
+            pending_words: 50,
+            input: text,
+            elapsed_secs: 30.0,
+            time_up: false,
+            total_correct_strokes: 249,
+            total_incorrect_strokes: 0,
+            wpm_history: vec![],
+            error_points: vec![],
+            error_positions: vec![],
+            keystroke_times: vec![],
+            char_times: vec![],
+            missed_words: BTreeSet::new(),
+            warming_up: false,
+            measured_from: 0,
+            pasted_ranges: vec![],
+        };
+        assert_eq!(snapshot.describe(), "Words: 100, 50% typed");
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+/// Gaps in milliseconds between consecutive keystrokes, given their times in seconds.
+///
+/// The first keystroke has nothing before it, so `n` keystrokes give `n - 1` intervals.
+pub fn keystroke_intervals(keystroke_times: &[f64]) -> Vec<f64> {
+    keystroke_times
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]) * 1000.0)
+        .collect()
+}
+
+/// The shortest and longest of `intervals_ms`, or `None` without any intervals.
+pub fn interval_extremes(intervals_ms: &[f64]) -> Option<(f64, f64)> {
+    let first = *intervals_ms.first()?;
+    Some(intervals_ms.iter().fold((first, first), |(fastest, slowest), &interval| {
+        (fastest.min(interval), slowest.max(interval))
+    }))
+}
+
+/// Counts intervals into `buckets` bins of `bucket_ms` each, plus a final bin for anything slower.
+///
+/// Long pauses all land in the last bin instead of stretching the scale.
+pub fn latency_histogram(intervals_ms: &[f64], bucket_ms: f64, buckets: usize) -> Vec<u64> {
+    let mut counts = vec![0; buckets + 1];
+    for &interval in intervals_ms {
+        let bin = ((interval / bucket_ms).floor().max(0.0) as usize).min(buckets);
+        counts[bin] += 1;
+    }
+    counts
+}
+
+/// Samples closer together than this are taken at the same moment.
+pub const SAME_SAMPLE_SECS: f64 = 0.05;
+
+/// `(time, wpm)` samples with runs of samples taken at the same moment collapsed to the last of
+/// them, which is the most up to date.
+///
+/// A test that ends on a tick that also took a regular sample used to save both, and two points
+/// at one time send the spline through a loop at the end of the curve.
+pub fn dedup_samples(samples: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut deduped: Vec<(f64, f64)> = Vec::with_capacity(samples.len());
+    for &sample in samples {
+        match deduped.last_mut() {
+            Some(last) if sample.0 - last.0 < SAME_SAMPLE_SECS => *last = sample,
+            _ => deduped.push(sample),
+        }
+    }
+    deduped
+}
+
+/// Trailing moving average of `(time, wpm)` samples over up to `window` samples each.
+///
+/// The first few points average over what is available so far, so the line starts with the run.
+pub fn moving_average(samples: &[(f64, f64)], window: usize) -> Vec<(f64, f64)> {
+    let window = window.max(1);
+    samples
+        .iter()
+        .enumerate()
+        .map(|(i, &(time, _))| {
+            let from = (i + 1).saturating_sub(window);
+            let slice = &samples[from..=i];
+            let mean = slice.iter().map(|(_, wpm)| wpm).sum::<f64>() / slice.len() as f64;
+            (time, mean)
+        })
+        .collect()
+}
+
+/// Share of keystrokes so far that weren't errors, as a percentage at each of `times`.
+///
+/// Every key press counts toward the total, including backspaces, so this runs slightly above
+/// raw accuracy on runs with many corrections. Times before the first keystroke are skipped.
+pub fn accuracy_over_time(keystroke_times: &[f64], error_times: &[f64], times: &[f64]) -> Vec<(f64, f64)> {
+    times
+        .iter()
+        .filter_map(|&time| {
+            let keys = keystroke_times.iter().filter(|&&t| t <= time).count();
+            let errors = error_times.iter().filter(|&&t| t <= time).count().min(keys);
+            (keys > 0).then(|| (time, (keys - errors) as f64 / keys as f64 * 100.0))
+        })
+        .collect()
+}
+
+/// The slowest fully typed word of `target` and its WPM, from `char_times`: the time each
+/// character of the input was typed, starting at character `from` (after any warmup).
+///
+/// A word is timed from the space before it to its last character, so the pause before starting
+/// it counts against it. The first timed word has no space before it and starts at its first
+/// character instead. Ties go to the earlier word.
+pub fn slowest_word<'a>(target: &'a str, char_times: &[f64], from: usize) -> Option<(&'a str, f64)> {
+    let mut slowest: Option<(&str, f64)> = None;
+    let mut start = 0;
+    for word in target.split(' ') {
+        let len = crate::text::grapheme_count(word);
+        let end = start + len;
+        if start >= from && len > 0 && end - from <= char_times.len() {
+            let (reference, chars) = if start > from {
+                (char_times[start - 1 - from], len)
+            } else {
+                (char_times[0], len - 1)
+            };
+            let seconds = char_times[end - 1 - from] - reference;
+            if chars > 0 && seconds > 0.0 {
+                let wpm = chars as f64 / 5.0 / (seconds / 60.0);
+                if slowest.is_none_or(|(_, lowest)| wpm < lowest) {
+                    slowest = Some((word, wpm));
+                }
+            }
+        }
+        start = end + 1;
+    }
+    slowest
+}
+
+/// How many errors landed on each key, from the character of `target` each error was typed at.
+///
+/// Letters count for their key whatever the case; errors past the end of the text have no key.
+pub fn key_errors(target: &str, error_positions: &[usize]) -> HashMap<char, usize> {
+    let chars = crate::text::graphemes(target);
+    let mut counts = HashMap::new();
+    for &position in error_positions {
+        if let Some(key) = chars.get(position).and_then(|c| c.chars().next()) {
+            *counts.entry(key.to_ascii_lowercase()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_histogram() {
+        let intervals = keystroke_intervals(&[0.0, 0.05, 0.2, 0.35, 2.35]);
+        assert_eq!(intervals.len(), 4);
+        assert!((intervals[0] - 50.0).abs() < 1e-9);
+
+        // 50ms, 150ms, 150ms, 2000ms into 100ms buckets, slow beyond 300ms
+        let counts = latency_histogram(&intervals, 100.0, 3);
+        assert_eq!(counts, vec![1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn test_interval_extremes() {
+        // The first keystroke has no interval, so its time doesn't matter
+        let intervals = keystroke_intervals(&[5.0, 5.12, 5.165, 6.365, 6.5]);
+        let (fastest, slowest) = interval_extremes(&intervals).unwrap();
+        assert!((fastest - 45.0).abs() < 1e-6);
+        assert!((slowest - 1200.0).abs() < 1e-6);
+        assert_eq!(interval_extremes(&keystroke_intervals(&[1.0])), None);
+    }
+
+    #[test]
+    fn test_dedup_samples() {
+        let samples = [(1.0, 40.0), (2.0, 45.0), (3.0, 50.0), (3.0, 52.0), (3.01, 53.0)];
+        assert_eq!(dedup_samples(&samples), vec![(1.0, 40.0), (2.0, 45.0), (3.01, 53.0)]);
+        assert_eq!(dedup_samples(&samples[..3]), samples[..3].to_vec());
+        assert!(dedup_samples(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let samples = [(1.0, 10.0), (2.0, 20.0), (3.0, 30.0), (4.0, 40.0)];
+        let averaged = moving_average(&samples, 2);
+        assert_eq!(averaged, vec![(1.0, 10.0), (2.0, 15.0), (3.0, 25.0), (4.0, 35.0)]);
+        assert!(moving_average(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_slowest_word() {
+        // "the" from its first letter, "quick" from the space before it, "fox" never finished
+        let times = [0.0, 0.2, 0.4, 0.6, 0.8, 1.0, 1.2, 1.4, 3.6, 3.8, 4.0];
+        let (word, wpm) = slowest_word("the quick fox", &times, 0).unwrap();
+        assert_eq!(word, "quick");
+        // 5 characters in 3 seconds
+        assert!((wpm - 20.0).abs() < 1e-9);
+
+        // Words typed at the same pace: the first one wins
+        let even = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        assert_eq!(slowest_word("ab cd ef", &even, 0).map(|(w, _)| w), Some("ab"));
+
+        // The warmup word "go" isn't timed
+        assert_eq!(slowest_word("go ab cd", &[0.0, 0.1, 5.0, 5.1, 5.4], 3).map(|(w, _)| w), Some("cd"));
+        assert_eq!(slowest_word("the", &[0.0], 0), None);
+    }
+
+    #[test]
+    fn test_accuracy_over_time() {
+        let keys = [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5];
+        let accuracy = accuracy_over_time(&keys, &[0.5, 2.5], &[1.0, 2.0, 3.0]);
+        let expected = [(1.0, 2.0 / 3.0 * 100.0), (2.0, 80.0), (3.0, 5.0 / 7.0 * 100.0)];
+        assert_eq!(accuracy.len(), 3);
+        for ((t, a), (et, ea)) in accuracy.iter().zip(expected) {
+            assert_eq!(*t, et);
+            assert!((a - ea).abs() < 1e-9);
+        }
+        assert!(accuracy_over_time(&[1.0], &[], &[0.5]).is_empty());
+    }
+
+    #[test]
+    fn test_key_errors() {
+        let counts = key_errors("The cat", &[0, 4, 4, 3, 12]);
+        assert_eq!(counts.get(&'t'), Some(&1));
+        assert_eq!(counts.get(&'c'), Some(&2));
+        assert_eq!(counts.get(&' '), Some(&1));
+        // Position 12 is past the end
+        assert_eq!(counts.values().sum::<usize>(), 4);
+    }
+}
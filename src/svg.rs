@@ -0,0 +1,273 @@
+//! Self-contained SVG export of a run's performance chart, for embedding in notes or sharing.
+
+use crate::history::TestResult;
+use crate::theme::Theme;
+use anyhow::Result;
+use ratatui::style::Color;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 400.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 50.0;
+const MARGIN_BOTTOM: f64 = 50.0;
+const TICKS: usize = 5;
+/// Standard xterm values of the 16 named terminal colors, black to bright white.
+const ANSI_COLORS: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+/// Where a chart of `result` is saved: `typestorm-chart-<date>-<time>.svg` in `dir`.
+///
+/// The name comes from when the run finished, so exporting the same run twice overwrites the
+/// earlier file instead of piling up copies.
+pub fn chart_file_path(dir: &Path, result: &TestResult) -> PathBuf {
+    dir.join(format!("typestorm-chart-{}.svg", result.timestamp.format("%Y%m%d-%H%M%S")))
+}
+
+/// `color` as an SVG hex color, as a typical terminal shows it; `fallback` for the terminal's
+/// own default color, which has no fixed value.
+fn hex(color: Color, fallback: &str) -> String {
+    let index = match color {
+        Color::Rgb(r, g, b) => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Reset => return fallback.to_string(),
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(i) => i,
+    };
+    match index {
+        0..=15 => ANSI_COLORS[index as usize].to_string(),
+        // The 6x6x6 color cube
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            format!("#{:02x}{:02x}{:02x}", level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        // The grayscale ramp
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        }
+    }
+}
+
+/// Draws the WPM curve and error marks of `result` with labelled axes, in the chart colors of
+/// `theme` so the file matches the chart on screen.
+pub fn performance_chart(result: &TestResult, theme: &Theme) -> String {
+    let wpm_color = hex(theme.chart_wpm, "#00cdcd");
+    let error_color = hex(theme.chart_errors, "#cd0000");
+    let max_time = result
+        .wpm_history
+        .iter()
+        .chain(&result.error_points)
+        .map(|&(t, _)| t)
+        .fold(1.0, f64::max);
+    let max_wpm = result
+        .wpm_history
+        .iter()
+        .chain(&result.error_points)
+        .map(|&(_, wpm)| wpm)
+        .fold(0.0, f64::max);
+    // Round the top of the scale up to a multiple of 20 so the tick labels are whole numbers
+    let max_wpm = ((max_wpm / 20.0).ceil() * 20.0).max(20.0);
+
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let x = |t: f64| MARGIN_LEFT + t / max_time * plot_width;
+    let y = |wpm: f64| MARGIN_TOP + plot_height - wpm.max(0.0) / max_wpm * plot_height;
+
+    let mut svg = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="12">"#,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(svg, r##"<rect width="100%" height="100%" fill="#ffffff"/>"##);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="24" font-size="16" font-weight="bold">{} — {:.0} WPM, {:.1}% accuracy</text>"#,
+        MARGIN_LEFT,
+        escape(&result.mode),
+        result.wpm,
+        result.accuracy
+    );
+
+    // Grid lines and tick labels
+    for i in 0..=TICKS {
+        let fraction = i as f64 / TICKS as f64;
+        let wpm = fraction * max_wpm;
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{x1:.1}" y1="{y:.1}" x2="{x2:.1}" y2="{y:.1}" stroke="#e5e7eb"/><text x="{tx:.1}" y="{ty:.1}" text-anchor="end">{wpm:.0}</text>"##,
+            x1 = MARGIN_LEFT,
+            x2 = WIDTH - MARGIN_RIGHT,
+            y = y(wpm),
+            tx = MARGIN_LEFT - 8.0,
+            ty = y(wpm) + 4.0,
+        );
+        let t = fraction * max_time;
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{:.0}s</text>"#,
+            x(t),
+            HEIGHT - MARGIN_BOTTOM + 18.0,
+            t
+        );
+    }
+
+    // Axes and their titles
+    let _ = writeln!(
+        svg,
+        r##"<path d="M{left:.1},{top:.1} V{bottom:.1} H{right:.1}" fill="none" stroke="#374151"/>"##,
+        left = MARGIN_LEFT,
+        top = MARGIN_TOP,
+        bottom = HEIGHT - MARGIN_BOTTOM,
+        right = WIDTH - MARGIN_RIGHT,
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">Time (s)</text>"#,
+        MARGIN_LEFT + plot_width / 2.0,
+        HEIGHT - 12.0
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text transform="translate(16 {:.1}) rotate(-90)" text-anchor="middle">WPM</text>"#,
+        MARGIN_TOP + plot_height / 2.0
+    );
+
+    // The WPM curve, then a cross for every error on top of it
+    if !result.wpm_history.is_empty() {
+        let points: Vec<String> = result
+            .wpm_history
+            .iter()
+            .map(|&(t, wpm)| format!("{:.1},{:.1}", x(t), y(wpm)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points.join(" "),
+            wpm_color
+        );
+    }
+    for &(t, wpm) in &result.error_points {
+        let (cx, cy) = (x(t), y(wpm));
+        let _ = writeln!(
+            svg,
+            r#"<path d="M{:.1},{:.1} l8,8 m0,-8 l-8,8" stroke="{}" stroke-width="2"/>"#,
+            cx - 4.0,
+            cy - 4.0,
+            error_color
+        );
+    }
+
+    // Legend in the top right corner
+    let legend_x = WIDTH - MARGIN_RIGHT - 140.0;
+    let _ = writeln!(
+        svg,
+        r#"<line x1="{lx:.1}" y1="36" x2="{lx2:.1}" y2="36" stroke="{wpm}" stroke-width="2"/><text x="{tx:.1}" y="40">WPM</text>"#,
+        lx = legend_x,
+        lx2 = legend_x + 16.0,
+        tx = legend_x + 22.0,
+        wpm = wpm_color
+    );
+    let _ = writeln!(
+        svg,
+        r#"<path d="M{:.1},32 l8,8 m0,-8 l-8,8" stroke="{}" stroke-width="2"/><text x="{:.1}" y="40">Errors ({})</text>"#,
+        legend_x + 64.0,
+        error_color,
+        legend_x + 78.0,
+        result.error_points.len()
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes the chart of `result` to `path`, in the colors of `theme`.
+pub fn write_chart(path: &Path, result: &TestResult, theme: &Theme) -> Result<()> {
+    fs::write(path, performance_chart(result, theme))?;
+    Ok(())
+}
+
+/// Escapes the characters that would break out of SVG text.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn result() -> TestResult {
+        TestResult {
+            timestamp: Local.with_ymd_and_hms(2026, 3, 14, 9, 26, 53).unwrap(),
+            mode: "Drill <missed>".to_string(),
+            wpm: 52.4,
+            accuracy: 96.5,
+            raw_accuracy: 94.0,
+            consistency: 80.0,
+            wpm_history: vec![(1.0, 40.0), (2.0, 55.0), (3.0, 52.0)],
+            error_points: vec![(1.5, 48.0)],
+            punctuation: false,
+            numbers: false,
+            complete_words_wpm: false,
+            kpm: 0.0,
+            sudden_death: None,
+            target_text: String::new(),
+            error_positions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_performance_chart() {
+        let svg = performance_chart(&result(), &Theme::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("Drill &lt;missed&gt; — 52 WPM, 96.5% accuracy"));
+        // 55 WPM rounds the scale up to 60; time runs to the last sample
+        assert!(svg.contains(">60</text>"));
+        assert!(svg.contains(">3s</text>"));
+        assert_eq!(svg.matches("<polyline").count(), 1);
+        assert!(svg.contains("Errors (1)"));
+        // The default theme's cyan curve and red error marks
+        assert!(svg.contains(r##"stroke="#00cdcd""##));
+        assert!(svg.contains(r##"stroke="#cd0000""##));
+    }
+
+    #[test]
+    fn test_chart_follows_theme() {
+        let theme = Theme { chart_wpm: Color::Rgb(1, 2, 3), chart_errors: Color::Indexed(208), ..Theme::default() };
+        let svg = performance_chart(&result(), &theme);
+        assert!(svg.contains(r##"stroke="#010203""##));
+        assert!(svg.contains(r##"stroke="#ff8700""##));
+        assert_eq!(hex(Color::Indexed(244), "#000000"), "#808080");
+        assert_eq!(hex(Color::Reset, "#123456"), "#123456");
+    }
+
+    #[test]
+    fn test_chart_file_path() {
+        let path = chart_file_path(Path::new("charts"), &result());
+        assert_eq!(path, Path::new("charts/typestorm-chart-20260314-092653.svg"));
+    }
+}
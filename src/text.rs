@@ -0,0 +1,95 @@
+//! Text split into grapheme clusters: what a reader sees as one character, such as an accented
+//! letter built from a combining mark or an emoji joined from several code points.
+//!
+//! The cursor, the error marks and the character counts all work in these units, so a
+//! multi-code-point character is typed, checked and deleted as a whole.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The grapheme clusters of `text`, in order.
+pub fn graphemes(text: &str) -> Vec<&str> {
+    text.graphemes(true).collect()
+}
+
+/// Number of grapheme clusters in `text`.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// The last grapheme cluster of `text`, if it isn't empty.
+pub fn last_grapheme(text: &str) -> Option<&str> {
+    text.graphemes(true).next_back()
+}
+
+/// Whether `c` would join the last grapheme cluster of `text` (a combining mark, a joiner, a
+/// skin-tone modifier, ...) instead of starting a new one.
+pub fn extends_last(text: &str, c: char) -> bool {
+    let Some(last) = last_grapheme(text) else {
+        return false;
+    };
+    let mut joined = last.to_string();
+    joined.push(c);
+    grapheme_count(&joined) == 1
+}
+
+/// `text` cut down to at most `width` terminal cells, ending in "…" when anything was cut.
+///
+/// Cuts fall between grapheme clusters, so an accented letter or a wide character is kept or
+/// dropped whole rather than split.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    let mut used = 0;
+    // Leave a cell for the ellipsis
+    for grapheme in text.graphemes(true) {
+        let w = grapheme.width();
+        if used + w + 1 > width {
+            break;
+        }
+        truncated.push_str(grapheme);
+        used += w;
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combining_marks_and_emoji_are_one_grapheme() {
+        assert_eq!(graphemes("ce\u{301}!"), vec!["c", "e\u{301}", "!"]);
+        assert_eq!(grapheme_count("👩\u{200d}💻 ok"), 4);
+        assert_eq!(last_grapheme("ab"), Some("b"));
+        assert_eq!(last_grapheme(""), None);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Words: 25", 9), "Words: 25");
+        assert_eq!(truncate("Drill: home row", 8), "Drill: …");
+        // Combining marks stay on their letter
+        assert_eq!(truncate("cafe\u{301} au lait", 6), "cafe\u{301} …");
+        // A wide character that doesn't fit is left out whole
+        assert_eq!(truncate("日本語のテキスト", 6), "日本…");
+        assert_eq!(truncate("日本語のテキスト", 5), "日本…");
+        assert_eq!(truncate("abc", 1), "…");
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn test_extends_last() {
+        assert!(extends_last("cafe", '\u{301}'));
+        assert!(extends_last("👩", '\u{200d}'));
+        assert!(extends_last("👩\u{200d}", '💻'));
+        assert!(!extends_last("cafe", 'e'));
+        assert!(!extends_last("cafe", ' '));
+        assert!(!extends_last("", '\u{301}'));
+    }
+}
@@ -0,0 +1,172 @@
+use ratatui::style::{Color, Modifier};
+
+/// Colors used to draw the typing area and highlights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub correct: Color,
+    pub incorrect: Color,
+    pub incorrect_bg: Color,
+    pub pending: Color,
+    /// Pending text just after the cursor, when look-ahead is on.
+    pub lookahead: Color,
+    pub cursor: Color,
+    pub cursor_text: Color,
+    pub accent: Color,
+    /// Extra emphasis on typed text, so right and wrong differ by more than color.
+    pub correct_modifier: Modifier,
+    pub incorrect_modifier: Modifier,
+    /// Result chart datasets.
+    pub chart_wpm: Color,
+    pub chart_errors: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
+
+/// Built-in themes, the first being the default.
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "default",
+        correct: Color::Green,
+        incorrect: Color::Red,
+        incorrect_bg: Color::DarkGray,
+        pending: Color::DarkGray,
+        lookahead: Color::Gray,
+        cursor: Color::White,
+        cursor_text: Color::Black,
+        accent: Color::Yellow,
+        correct_modifier: Modifier::empty(),
+        incorrect_modifier: Modifier::empty(),
+        chart_wpm: Color::Cyan,
+        chart_errors: Color::Red,
+    },
+    Theme {
+        name: "mono",
+        correct: Color::White,
+        incorrect: Color::Black,
+        incorrect_bg: Color::Gray,
+        pending: Color::DarkGray,
+        lookahead: Color::Gray,
+        cursor: Color::White,
+        cursor_text: Color::Black,
+        accent: Color::White,
+        correct_modifier: Modifier::empty(),
+        incorrect_modifier: Modifier::empty(),
+        chart_wpm: Color::Cyan,
+        chart_errors: Color::Red,
+    },
+    Theme {
+        name: "ocean",
+        correct: Color::Cyan,
+        incorrect: Color::LightRed,
+        incorrect_bg: Color::Reset,
+        pending: Color::Blue,
+        lookahead: Color::LightBlue,
+        cursor: Color::LightCyan,
+        cursor_text: Color::Black,
+        accent: Color::LightBlue,
+        correct_modifier: Modifier::empty(),
+        incorrect_modifier: Modifier::empty(),
+        chart_wpm: Color::Cyan,
+        chart_errors: Color::Red,
+    },
+    Theme {
+        name: "light",
+        correct: Color::Blue,
+        incorrect: Color::Red,
+        incorrect_bg: Color::Reset,
+        pending: Color::Gray,
+        lookahead: Color::DarkGray,
+        cursor: Color::Black,
+        cursor_text: Color::White,
+        accent: Color::Magenta,
+        correct_modifier: Modifier::empty(),
+        incorrect_modifier: Modifier::empty(),
+        chart_wpm: Color::Cyan,
+        chart_errors: Color::Red,
+    },
+    // Blue and orange stay apart for red-green colorblindness, and weight and underline tell
+    // right from wrong even without color
+    Theme {
+        name: "deuteranopia",
+        correct: Color::LightBlue,
+        incorrect: Color::Indexed(208),
+        incorrect_bg: Color::Reset,
+        pending: Color::DarkGray,
+        lookahead: Color::Gray,
+        cursor: Color::White,
+        cursor_text: Color::Black,
+        accent: Color::Yellow,
+        correct_modifier: Modifier::BOLD,
+        incorrect_modifier: Modifier::UNDERLINED,
+        chart_wpm: Color::LightBlue,
+        chart_errors: Color::Indexed(208),
+    },
+];
+
+impl Theme {
+    /// Looks up a built-in theme by name, ignoring case.
+    pub fn by_name(name: &str) -> Option<Theme> {
+        THEMES.iter().copied().find(|t| t.name.eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// Picks the theme from, in order of precedence, the `--theme` flag, the `TYPESTORM_THEME`
+/// environment variable and the settings file.
+///
+/// The first source that is set decides. An unknown name falls back to the default theme and
+/// comes with a warning for the caller to show.
+pub fn resolve(flag: Option<&str>, env: Option<&str>, config: Option<&str>) -> (Theme, Option<String>) {
+    let Some((source, name)) = [("--theme", flag), ("TYPESTORM_THEME", env), ("settings", config)]
+        .into_iter()
+        .find_map(|(source, name)| Some((source, name?)))
+    else {
+        return (Theme::default(), None);
+    };
+
+    match Theme::by_name(name) {
+        Some(theme) => (theme, None),
+        None => {
+            let names: Vec<&str> = THEMES.iter().map(|t| t.name).collect();
+            let warning = format!(
+                "unknown theme \"{}\" from {}, using default (available: {})",
+                name,
+                source,
+                names.join(", ")
+            );
+            (Theme::default(), Some(warning))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_precedence() {
+        assert_eq!(resolve(None, None, None).0.name, "default");
+        assert_eq!(resolve(None, None, Some("mono")).0.name, "mono");
+        assert_eq!(resolve(None, Some("ocean"), Some("mono")).0.name, "ocean");
+        assert_eq!(resolve(Some("Light"), Some("ocean"), Some("mono")).0.name, "light");
+
+        let (theme, warning) = resolve(Some("nope"), Some("ocean"), None);
+        assert_eq!(theme.name, "default");
+        assert!(warning.unwrap().contains("nope"));
+    }
+
+    #[test]
+    fn test_deuteranopia_avoids_red_and_green() {
+        let theme = Theme::by_name("deuteranopia").unwrap();
+        let red_green = [Color::Red, Color::LightRed, Color::Green, Color::LightGreen];
+        for color in [theme.correct, theme.incorrect, theme.chart_wpm, theme.chart_errors] {
+            assert!(!red_green.contains(&color), "{:?}", color);
+        }
+        // Right and wrong differ by more than color
+        assert_ne!(theme.correct_modifier, theme.incorrect_modifier);
+    }
+}
@@ -1,13 +1,50 @@
-use crate::app::{App, AppMode, TestMode};
+use typestorm::app::{App, AppMode, ChartView, HistoryTrend, SettingOption, TestMode, TUTORIAL_PAGES};
+use typestorm::keymap::Action;
+use typestorm::history::{self, TestResult};
+use typestorm::settings::{AccuracyThresholds, ChartSmoothing, CompletionCue, CursorStyle, KeyboardLayout};
+use typestorm::stats;
+use typestorm::text;
+use typestorm::theme::Theme;
+use typestorm::words::DrillPreset;
+use unicode_width::UnicodeWidthStr;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Wrap, Table, Row, Cell},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, Paragraph, Wrap, Table, TableState, Row, Cell, LineGauge},
     Frame,
 };
+use std::time::Duration;
+
+/// Seconds before the end of a timed test when focus mode shows the time left.
+const FOCUS_TIME_WARNING: u64 = 5;
+/// How long "Go!" shows in the footer once the warmup is over.
+const GO_NOTICE: Duration = Duration::from_secs(2);
+
+/// Smallest terminal the normal layout fits in.
+const MIN_WIDTH: u16 = 50;
+const MIN_HEIGHT: u16 = 15;
+
+/// Draws the whole screen. `history_state` keeps the history table's scroll position between
+/// frames; its selection follows the app's.
+pub fn draw(f: &mut Frame, app: &mut App, history_state: &mut TableState) {
+    let area = f.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        let message = format!("Terminal too small (need at least {}x{})", MIN_WIDTH, MIN_HEIGHT);
+        let p = Paragraph::new(message)
+            .style(Style::default().fg(Color::Yellow))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        let row = Rect::new(area.x, area.y + area.height / 2, area.width, area.height - area.height / 2);
+        f.render_widget(p, row);
+        return;
+    }
+
+    if app.settings.focus_mode && app.mode == AppMode::Typing {
+        render_focus(f, app);
+        return;
+    }
 
-pub fn draw(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -18,44 +55,301 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         .split(f.area());
 
     render_header(f, app, chunks[0]);
-    render_main(f, app, chunks[1]);
+    render_main(f, app, history_state, chunks[1]);
     render_footer(f, app, chunks[2]);
+
+    if app.completion_flash_active() {
+        render_completion_flash(f, app);
+    }
+}
+
+/// The whole screen lit up for a moment as a test ends, with "Done!" in the middle.
+fn render_completion_flash(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let style = Style::default().bg(app.theme.accent).fg(Color::Black).add_modifier(Modifier::BOLD);
+    f.render_widget(Clear, area);
+    f.render_widget(Block::default().style(style), area);
+    let row = Rect::new(area.x, area.y + area.height / 2, area.width, 1);
+    f.render_widget(Paragraph::new("Done!").style(style).alignment(Alignment::Center), row);
+}
+
+/// Focus mode: only the text, centered, with the time left shown near the end of a timed test.
+fn render_focus(f: &mut Frame, app: &App) {
+    let area = f.area();
+    let v_center = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(40), Constraint::Min(1)])
+        .split(area);
+    let h_padded = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(10), Constraint::Percentage(80), Constraint::Percentage(10)])
+        .split(v_center[1]);
+    render_typing(f, app, h_padded[1]);
+
+    // No footer here, so the metronome keeps time along the top
+    let metronome = metronome_spans(app);
+    if !metronome.is_empty() {
+        let p = Paragraph::new(Line::from(metronome)).alignment(Alignment::Center);
+        f.render_widget(p, Rect::new(area.x, area.y, area.width, 1.min(area.height)));
+    }
+
+    if app.blocked_cue().is_some() {
+        let p = Paragraph::new("Fix the errors to finish")
+            .style(Style::default().fg(app.theme.incorrect))
+            .alignment(Alignment::Center);
+        f.render_widget(p, Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1.min(area.height)));
+    } else if let TestMode::Time(duration) = app.test_mode {
+        let remaining = app.elapsed().map(|e| duration.saturating_sub(e.as_secs()));
+        let notice = if app.time_up {
+            Some("Time's up! Finish your word".to_string())
+        } else {
+            remaining.filter(|&r| r <= FOCUS_TIME_WARNING).map(|r| format!("{}s", r))
+        };
+        if let Some(notice) = notice {
+            let p = Paragraph::new(notice)
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Center);
+            f.render_widget(p, Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1.min(area.height)));
+        }
+    }
 }
 
-fn render_header(f: &mut Frame, _app: &App, area: Rect) {
-    let title = Paragraph::new("TypeStorm ⚡")
+const TITLE: &str = "TypeStorm ⚡";
+
+fn render_header(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(area);
+    let title = Paragraph::new(TITLE)
         .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+        .block(block);
     f.render_widget(title, area);
+
+    // Right-aligned, and only when it fits beside the title without pushing it off center
+    let badge = format!("{} ", mode_badge(app));
+    let half_free = (inner.width as usize).saturating_sub(TITLE.width()) / 2;
+    if badge.width() < half_free {
+        let badge = Paragraph::new(badge).style(Style::default().fg(Color::DarkGray)).alignment(Alignment::Right);
+        f.render_widget(badge, inner);
+    }
 }
 
-fn render_footer(f: &mut Frame, app: &App, area: Rect) {
-    let info_text = match app.mode {
-        AppMode::Welcome => "Press <Enter> to start | <w/t> change mode | <h> history | <q> quit".to_string(),
-        AppMode::Typing => {
-            if let TestMode::Time(duration) = app.test_mode {
-                if let Some(start) = app.start_time {
-                    let elapsed = start.elapsed().as_secs();
-                    let remaining = duration.saturating_sub(elapsed);
-                    format!("Time Remaining: {}s | Press <Esc> to cancel", remaining)
-                } else {
-                    // Timer hasn't started yet - show full duration
-                    format!("Time Remaining: {}s | Press <Esc> to cancel", duration)
+/// Compact summary of the test being set up or typed, e.g. "Words:25 • punct • num".
+fn mode_badge(app: &App) -> String {
+    // A drill has its own text, without the mode's length, punctuation or numbers
+    if let (AppMode::Typing, Some(label)) = (app.mode, &app.drill_label) {
+        return label.clone();
+    }
+    let mut parts = vec![match app.test_mode {
+        TestMode::Words(n) => format!("Words:{}", n),
+        TestMode::Time(s) => format!("Time:{}s", s),
+    }];
+    if app.include_punctuation {
+        parts.push("punct".to_string());
+    }
+    if app.include_numbers {
+        parts.push("num".to_string());
+    }
+    parts.join(" • ")
+}
+
+/// Footer hints for each screen, in display order: the actions a hint covers and what it does.
+fn footer_hint_actions(app: &App) -> &'static [(&'static [Action], &'static str)] {
+    match app.mode {
+        AppMode::Welcome => &[
+            (&[Action::Start], "start"),
+            (&[Action::WordMode, Action::TimeMode], "change mode"),
+            (&[Action::OpenSettings], "settings"),
+            (&[Action::OpenHistory], "history"),
+            (&[Action::Quit], "quit"),
+        ],
+        // Only cancelling is left once the clock has run out
+        AppMode::Typing if app.time_up => &[(&[Action::Cancel], "cancel")],
+        AppMode::Typing => &[(&[Action::Clear], "clear"), (&[Action::Focus], "focus"), (&[Action::Cancel], "cancel")],
+        AppMode::Results if !app.results_revealed => &[(&[Action::Reveal], "reveal results"), (&[Action::Quit], "quit")],
+        AppMode::Results => &[
+            (&[Action::Restart], "restart"),
+            (&[Action::Harder], "harder"),
+            (&[Action::Review], "review"),
+            (&[Action::ChartView], "chart view"),
+            (&[Action::Export], "export chart"),
+            (&[Action::ExportData], "export data"),
+            (&[Action::Drill], "drill missed words"),
+            (&[Action::Average], "avg line"),
+            (&[Action::Quit], "quit"),
+        ],
+        AppMode::History => &[
+            (&[Action::Up, Action::Down], "navigate"),
+            (&[Action::Details], "details"),
+            (&[Action::Mark], "mark"),
+            (&[Action::Compare], "compare"),
+            (&[Action::Trend], "trend"),
+            (&[Action::Back], "back"),
+        ],
+        AppMode::HistoryDetails => &[
+            (&[Action::Repeat], "repeat test"),
+            (&[Action::PreviousError, Action::NextError], "step errors"),
+            (&[Action::Average], "avg line"),
+            (&[Action::Export], "export chart"),
+            (&[Action::Back], "back to list"),
+        ],
+        AppMode::HistoryCompare => &[(&[Action::Back], "back to list")],
+        AppMode::Review => &[(&[Action::Up, Action::Down], "scroll"), (&[Action::Back], "back to results")],
+        AppMode::Drills => &[
+            (&[Action::Up, Action::Down], "navigate"),
+            (&[Action::Start], "start"),
+            (&[Action::Back], "back"),
+        ],
+        AppMode::Tutorial => &[(&[Action::Next], "next"), (&[Action::Previous], "previous"), (&[Action::Back], "skip")],
+        AppMode::Settings => &[
+            (&[Action::Up, Action::Down], "navigate"),
+            (&[Action::Next], "change"),
+            (&[Action::Previous], "change back"),
+            (&[Action::Back], "back"),
+        ],
+    }
+}
+
+/// The footer hints as "<key> what", read from the keymap so remapped keys show up
+/// and unbound actions drop out. Hints that can't do anything right now are left out too.
+fn footer_hints(app: &App) -> Vec<String> {
+    footer_hint_actions(app)
+        .iter()
+        .filter(|(actions, _)| match actions {
+            [Action::Drill] => !app.missed_words.is_empty(),
+            [Action::Repeat] if app.mode == AppMode::HistoryDetails => {
+                app.selected_history_entry().and_then(|i| app.history.get(i)).is_some_and(|r| !r.target_text.is_empty())
+            }
+            [Action::PreviousError, Action::NextError] => {
+                app.selected_history_entry().and_then(|i| app.history.get(i)).is_some_and(|r| r.reviewable_errors() > 0)
+            }
+            _ => true,
+        })
+        .filter_map(|(actions, what)| {
+            let keys: Vec<String> = actions.iter().filter_map(|&action| app.keymap.bound(action)).collect();
+            (!keys.is_empty()).then(|| format!("<{}> {}", keys.join("/"), what))
+        })
+        .collect()
+}
+
+/// Lays hints out over `lines` rows of `width` columns, never breaking one across rows.
+/// Hints that don't fit are dropped and replaced with "…".
+fn fit_hints(hints: &[String], width: usize, lines: usize) -> Vec<String> {
+    const SEPARATOR: &str = " | ";
+    const MORE: &str = " …";
+    let mut rows: Vec<String> = Vec::new();
+    for (i, hint) in hints.iter().enumerate() {
+        // The last row keeps room for the "…" unless this is the final hint
+        let reserve = if rows.len() == lines && i + 1 < hints.len() { MORE.width() } else { 0 };
+        let fits = |row: &String| row.width() + SEPARATOR.len() + hint.width() + reserve <= width;
+        if let Some(row) = rows.last_mut().filter(|row| fits(row)) {
+            row.push_str(SEPARATOR);
+            row.push_str(hint);
+            continue;
+        }
+        if rows.len() < lines && hint.width() <= width {
+            rows.push(hint.clone());
+            continue;
+        }
+        // Out of room: end on "…", dropping earlier hints from the last row if needed
+        if rows.len() < lines {
+            rows.push(String::new());
+        }
+        if let Some(row) = rows.last_mut() {
+            while !row.is_empty() && row.width() + MORE.width() > width {
+                match row.rfind(SEPARATOR) {
+                    Some(at) => row.truncate(at),
+                    None => row.clear(),
                 }
-            } else {
-                "Press <Esc> to cancel".to_string()
             }
+            row.push_str(if row.is_empty() { MORE.trim_start() } else { MORE });
+        }
+        break;
+    }
+    rows
+}
+
+/// Cells in the footer's accuracy gauge.
+const ACCURACY_GAUGE_CELLS: usize = 5;
+
+/// "Acc ▰▰▰▰▱ 86%", green, yellow or red by the `thresholds`, so a slipping accuracy stands out
+/// while typing.
+fn accuracy_gauge(accuracy: f64, thresholds: AccuracyThresholds) -> Vec<Span<'static>> {
+    let color = if accuracy >= thresholds.green {
+        Color::Green
+    } else if accuracy >= thresholds.yellow {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    let filled = ((accuracy / 100.0 * ACCURACY_GAUGE_CELLS as f64).round() as usize).min(ACCURACY_GAUGE_CELLS);
+    vec![
+        Span::raw("Acc "),
+        Span::styled("▰".repeat(filled), Style::default().fg(color)),
+        Span::styled("▱".repeat(ACCURACY_GAUGE_CELLS - filled), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!(" {:.0}%", accuracy), Style::default().fg(color)),
+    ]
+}
+
+/// The metronome's tempo, with a note that lights up on each beat; nothing with the metronome off.
+fn metronome_spans(app: &App) -> Vec<Span<'static>> {
+    if app.metronome_interval().is_none() {
+        return Vec::new();
+    }
+    let note = if app.metronome_pulse() {
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(Color::DarkGray)
+    };
+    vec![
+        Span::styled("♩", note),
+        Span::raw(format!(" {} BPM", app.settings.metronome_bpm)),
+    ]
+}
+
+fn render_footer(f: &mut Frame, app: &App, area: Rect) {
+    // What's going on in the test, shown ahead of the key hints
+    let status = match app.mode {
+        AppMode::Typing => match app.test_mode {
+            _ if app.warming_up => {
+                let left = app.settings.warmup_words.saturating_sub(app.completed_words());
+                Some(format!("Warmup, not scored: {} more word{}", left, if left == 1 { "" } else { "s" }))
+            }
+            // Scoring has just started
+            _ if app.measured_from > 0 && app.elapsed().is_none_or(|elapsed| elapsed < GO_NOTICE) => {
+                Some("Go! Scoring starts now".to_string())
+            }
+            TestMode::Time(_) if app.time_up => Some("Time's up! Finish your word and press <Space>".to_string()),
+            TestMode::Time(duration) => {
+                // Timer hasn't started yet - show full duration
+                let elapsed = app.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+                Some(format!("Time Remaining: {}s", duration.saturating_sub(elapsed)))
+            }
+            TestMode::Words(_) if app.blocked_by_errors() => Some("Fix the remaining errors to finish".to_string()),
+            TestMode::Words(_) => Some(format!("{}/{} words", app.completed_words(), app.total_words())),
         },
-        AppMode::Results => "Press <Enter/r> to restart | <q> to quit".to_string(),
-        AppMode::History => "Up/Down (j/k): Navigate | Enter: Details | q/Esc: Back".to_string(),
-        AppMode::HistoryDetails => "Esc/q: Back to List".to_string(),
+        _ => None,
+    };
+    let hints = match app.auto_restart_remaining() {
+        Some(remaining) if app.mode == AppMode::Results => vec![
+            format!("Next test in {}s", remaining.as_secs_f64().ceil()),
+            "any key to stay".to_string(),
+        ],
+        _ => status.into_iter().chain(footer_hints(app)).collect(),
     };
 
-    let stats = if app.mode == AppMode::Typing {
-        format!("WPM: {:.0} | Acc: {:.0}%", app.calculate_wpm(), app.calculate_accuracy())
+    let stats = if let Some(message) = &app.status_message {
+        Line::from(message.clone())
+    } else if app.mode == AppMode::Typing {
+        let mut spans = metronome_spans(app);
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+        spans.push(Span::raw(format!("WPM: {:.0} | ", app.calculate_wpm())));
+        spans.extend(accuracy_gauge(app.calculate_accuracy(), app.settings.accuracy_thresholds));
+        Line::from(spans)
     } else {
-        String::new()
+        Line::default()
     };
 
     let footer_layout = Layout::default()
@@ -63,7 +357,11 @@ fn render_footer(f: &mut Frame, app: &App, area: Rect) {
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
         .split(area);
 
-    let info = Paragraph::new(info_text)
+    // Key hints spill onto the footer's second line, unless the timed test's gauge is drawn there
+    let gauge_shown = app.mode == AppMode::Typing && area.height >= 3 && matches!(app.test_mode, TestMode::Time(_));
+    let lines = area.height.saturating_sub(1 + gauge_shown as u16) as usize;
+    let info_lines: Vec<Line> = fit_hints(&hints, footer_layout[0].width as usize, lines).into_iter().map(Line::from).collect();
+    let info = Paragraph::new(info_lines)
         .style(Style::default().fg(Color::Gray))
         .alignment(Alignment::Left)
         .block(Block::default().borders(Borders::TOP));
@@ -75,9 +373,25 @@ fn render_footer(f: &mut Frame, app: &App, area: Rect) {
 
     f.render_widget(info, footer_layout[0]);
     f.render_widget(stats_widget, footer_layout[1]);
+
+    // Thin bar along the bottom of the footer showing how much of a timed test has passed
+    if gauge_shown {
+        if let TestMode::Time(duration) = app.test_mode {
+            // Empty until the first keystroke starts the clock
+            let elapsed = app.elapsed().map(|d| d.as_secs_f64()).unwrap_or(0.0);
+            let ratio = (elapsed / duration.max(1) as f64).clamp(0.0, 1.0);
+            let gauge = LineGauge::default()
+                .filled_style(Style::default().fg(Color::Cyan))
+                .unfilled_style(Style::default().fg(Color::DarkGray))
+                .label("")
+                .ratio(ratio);
+            let gauge_area = Rect::new(area.x, area.bottom() - 1, area.width, 1);
+            f.render_widget(gauge, gauge_area);
+        }
+    }
 }
 
-fn render_main(f: &mut Frame, app: &mut App, area: Rect) {
+fn render_main(f: &mut Frame, app: &mut App, history_state: &mut TableState, area: Rect) {
     let block = Block::default().borders(Borders::NONE).padding(ratatui::widgets::Padding::new(2, 2, 1, 1));
     let inner_area = block.inner(area);
     f.render_widget(block, area);
@@ -86,77 +400,203 @@ fn render_main(f: &mut Frame, app: &mut App, area: Rect) {
         AppMode::Welcome => {
              render_welcome(f, app, inner_area);
         }
+        AppMode::Tutorial => {
+            render_tutorial(f, app, inner_area);
+        }
+        AppMode::Drills => {
+            render_drills(f, app, inner_area);
+        }
         AppMode::Typing => {
-             render_typing(f, app, inner_area);
+            // Strict mode won't end on a text with errors in it, so say why rather than seem stuck.
+            // The frame goes in the padding, so the text doesn't move when it appears
+            if let Some(lit) = app.blocked_cue() {
+                let style = if lit {
+                    Style::default().fg(app.theme.incorrect).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let frame = Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(style)
+                    .title(Span::styled(" Fix the errors to finish ", style))
+                    .title_alignment(Alignment::Center);
+                f.render_widget(frame, area);
+            }
+            render_typing(f, app, inner_area);
         }
+        AppMode::Results if !app.results_revealed => render_hidden_results(f, app, inner_area),
         AppMode::Results => {
-            render_performance_view(
-                f, 
-                inner_area, 
-                app.calculate_wpm(), 
-                app.calculate_accuracy(), 
-                &app.wpm_history,
-                &app.error_points,
-                true // is_new_result
-            );
+            let mode = app.last_result.as_ref().map(|r| r.mode.as_str()).unwrap_or_default();
+            let streak = app.last_result.as_ref().and_then(|r| r.sudden_death);
+            let rank = match app.result_percentile() {
+                // A sudden death run is about how far it got, not how fast
+                _ if streak.is_some() => streak.map(|chars| sudden_death_message(app, chars)),
+                _ if app.history_disabled => None,
+                Some(pct) => Some(format!("Top {:.0}% of your {} runs", pct.ceil(), mode)),
+                None => Some("First run!".to_string()),
+            };
+            // Count up from zero with an ease-out so the numbers settle gently
+            let progress = 1.0 - (1.0 - app.result_animation_progress()).powi(3);
+            let view = PerformanceView {
+                wpm: app.calculate_wpm() * progress,
+                kpm: Some(app.calculate_kpm() * progress),
+                accuracy: app.calculate_accuracy() * progress,
+                raw_accuracy: app.calculate_raw_accuracy() * progress,
+                consistency: app.calculate_consistency(),
+                wpm_history: &app.wpm_history,
+                error_points: &app.error_points,
+                target_text: &app.target_text,
+                error_positions: &app.error_positions,
+                keystroke_times: &app.keystroke_times,
+                slowest_word: app.slowest_word(),
+                rank,
+                note: if app.history_disabled {
+                    Some("History disabled".to_string())
+                } else {
+                    (!app.result_recorded).then(|| "Too short to record".to_string())
+                },
+                is_new_result: true,
+                session_test: Some(app.session_count),
+                complete_words: app.wpm_drops_cut_off_word(),
+                theme: app.theme,
+                rolling_average: app.settings.show_rolling_average,
+                keyboard_layout: app.settings.keyboard_layout,
+                smoothing: app.settings.chart_smoothing,
+                from_origin: app.settings.chart_from_origin,
+                goal: app.goal_progress(mode),
+                chart_view: app.chart_view,
+            };
+            render_performance_view(f, inner_area, &view);
         }
         AppMode::History => {
-            render_history_view(f, app, inner_area);
+            render_history_view(f, app, history_state, inner_area);
+        }
+        AppMode::Settings => {
+            render_settings(f, app, inner_area);
         }
         AppMode::HistoryDetails => {
-            // Get selected history item
-             // Visual index matches array index?? No, check history view implementation below.
-             // We chose to show reversed list.
-             // To simplify, let's just assume app.selected_history_index maps correctly to the item we want to show.
-             // If we display list reversed, user pressing "down" (index++) moves visually down (older).
-             // list[0] (top) = Newest.
-             // app.history is Chronological (Oldest -> Newest).
-             // So list[0] corresponds to app.history.last().
-             // list[i] corresponds to app.history[len - 1 - i].
-             
-             let index = if app.selected_history_index < app.history.len() {
-                 app.history.len() - 1 - app.selected_history_index
-             } else {
-                 0
-             };
-             
-             if let Some(result) = app.history.get(index) {
-                render_performance_view(
-                    f, 
-                    inner_area, 
-                    result.wpm, 
-                    result.accuracy, 
-                    &result.wpm_history,
-                    &result.error_points,
-                    false
+            if let Some(result) = app.selected_history_entry().and_then(|i| app.history.get(i)) {
+                let view = PerformanceView {
+                    wpm: result.wpm,
+                    kpm: (result.kpm > 0.0).then_some(result.kpm),
+                    accuracy: result.accuracy,
+                    raw_accuracy: result.raw_accuracy,
+                    consistency: result.consistency,
+                    wpm_history: &result.wpm_history,
+                    error_points: &result.error_points,
+                    target_text: &result.target_text,
+                    error_positions: &result.error_positions,
+                    keystroke_times: &[],
+                    slowest_word: None,
+                    rank: result.sudden_death.map(|chars| format!("Sudden death: {} characters error-free", chars)),
+                    note: None,
+                    is_new_result: false,
+                    session_test: None,
+                    complete_words: result.complete_words_wpm,
+                    theme: app.theme,
+                    rolling_average: app.settings.show_rolling_average,
+                    keyboard_layout: app.settings.keyboard_layout,
+                    smoothing: app.settings.chart_smoothing,
+                    from_origin: app.settings.chart_from_origin,
+                    goal: None,
+                    chart_view: ChartView::Wpm,
+                };
+                match app.error_review.filter(|&i| i < result.reviewable_errors()) {
+                    Some(i) => {
+                        let layout = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(0)])
+                            .split(inner_area);
+                        render_error_review(f, app, result, i, layout[0]);
+                        render_performance_view(f, layout[1], &view);
+                    }
+                    None => render_performance_view(f, inner_area, &view),
+                }
+            }
+        }
+        AppMode::Review => {
+            render_review(f, app, inner_area);
+        }
+        AppMode::HistoryCompare => {
+            let marked = app.compare_index.and_then(|i| app.history.get(i));
+            let selected = app.selected_history_entry().and_then(|i| app.history.get(i));
+            if let (Some(marked), Some(selected)) = (marked, selected) {
+                render_comparison_view(
+                    f,
+                    inner_area,
+                    marked,
+                    selected,
+                    app.settings.chart_from_origin,
+                    app.settings.chart_smoothing,
                 );
-             }
+            }
         }
     }
 }
 
 fn render_welcome(f: &mut Frame, app: &App, area: Rect) {
+    let mut option_spans = Vec::new();
+    for (i, &option) in SettingOption::WELCOME.iter().enumerate() {
+        if i > 0 {
+            option_spans.push(Span::raw(" | "));
+        }
+        let (label, value, style) = setting_display(app, option);
+        let mut label_style = Style::default();
+        let mut value_style = style;
+        if app.flashing_option() == Some(option) {
+            label_style = label_style.fg(Color::Black).bg(Color::Yellow);
+            value_style = value_style.fg(Color::Black).bg(Color::Yellow);
+        }
+        option_spans.push(Span::styled(format!("{}: ", label), label_style));
+        option_spans.push(Span::styled(value, value_style));
+    }
+
+    // An interrupted test takes the place of the "Ready?" prompt until it's resumed or discarded
+    let prompt = match &app.pending_snapshot {
+        Some(saved) => Line::from(Span::styled(
+            format!(
+                "Unfinished test ({}) | <{}> resume | <{}> discard",
+                saved.describe(),
+                app.keymap.label(Action::Resume),
+                app.keymap.label(Action::Discard)
+            ),
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )),
+        None => Line::from(Span::styled("Ready?", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+    };
+
     let welcome_text = vec![
         Line::from("Welcome to TypeStorm!"),
         Line::from(""),
         Line::from("Test your typing speed in the terminal."),
         Line::from(""),
-        Line::from(Span::styled("Ready?", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+        prompt,
         Line::from(""),
         Line::from(vec![
             Span::raw("Mode: "),
-            Span::styled(format!("{}", app.test_mode), Style::default().fg(Color::Yellow)),
-            Span::raw(" | "),
-            Span::raw("Punctuation: "),
-            Span::styled(if app.include_punctuation { "ON" } else { "OFF" }, 
-                if app.include_punctuation { Style::default().fg(Color::Green) } else { Style::default().fg(Color::Red) }),
-            Span::raw(" | "),
-            Span::raw("Numbers: "),
-            Span::styled(if app.include_numbers { "ON" } else { "OFF" }, 
-                if app.include_numbers { Style::default().fg(Color::Green) } else { Style::default().fg(Color::Red) }),
+            Span::styled(app.mode_label(), Style::default().fg(app.theme.accent)),
+            Span::raw(" | Profile: "),
+            Span::styled(app.profile_name(), Style::default().fg(app.theme.accent)),
+            Span::styled(format!(" [{}]", app.keymap.label(Action::Profile)), Style::default().fg(Color::DarkGray)),
         ]),
+        Line::from(option_spans),
+        Line::from(Span::styled(test_length_summary(app), Style::default().fg(Color::DarkGray))),
+        preview_line(app, area.width as usize),
+        recent_average_line(app),
+        match app.goal_progress(&app.mode_label()) {
+            Some((best, goal)) => Line::from(goal_span(best, goal)),
+            None => Line::from(""),
+        },
         Line::from(""),
-        Line::from(Span::styled("[w]ords [t]ime [p]unctuation [n]umbers", Style::default().fg(Color::DarkGray))),
+        Line::from(Span::styled(
+            format!(
+                "[w]ords [t]ime [s]ettings [{}] shuffle [{}] combo drill [{}] drills",
+                app.keymap.label(Action::Shuffle),
+                app.keymap.label(Action::ComboDrill),
+                app.keymap.label(Action::OpenDrills)
+            ),
+            Style::default().fg(Color::DarkGray),
+        )),
         Line::from(""),
         Line::from(Span::styled("[h] view history", Style::default().fg(Color::Magenta))),
     ];
@@ -169,7 +609,7 @@ fn render_welcome(f: &mut Frame, app: &App, area: Rect) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage(30),
-            Constraint::Length(12),
+            Constraint::Length(18),
             Constraint::Percentage(50),
         ])
         .split(area);
@@ -177,285 +617,1201 @@ fn render_welcome(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(p, v_center[1]);
 }
 
-fn render_typing(f: &mut Frame, app: &App, area: Rect) {
-    let mut spans = Vec::new();
-    let target_chars: Vec<char> = app.target_text.chars().collect();
-    let input_chars: Vec<char> = app.input.chars().collect();
-
-    for (i, &target_char) in target_chars.iter().enumerate() {
-        if i < input_chars.len() {
-            let input_char = input_chars[i];
-            if input_char == target_char {
-                spans.push(Span::styled(target_char.to_string(), Style::default().fg(Color::Green)));
-            } else {
-                spans.push(Span::styled(target_char.to_string(), Style::default().fg(Color::Red).bg(Color::DarkGray)));
-            }
-        } else if i == input_chars.len() {
-                // Cursor position - highlight the character we need to type
-                spans.push(Span::styled(target_char.to_string(), Style::default().fg(Color::Black).bg(Color::White)));
-        } else {
-            spans.push(Span::styled(target_char.to_string(), Style::default().fg(Color::DarkGray)));
-        }
+/// Title and lines of one page of the first-launch tutorial, naming the keys actually bound.
+fn tutorial_page(app: &App, page: usize) -> (&'static str, Vec<String>) {
+    let key = |action| app.keymap.label(action);
+    match page {
+        0 => (
+            "Welcome to TypeStorm",
+            vec![
+                "Type the text on screen as fast and as accurately as you can.".to_string(),
+                "The clock starts with your first key; mistakes turn red.".to_string(),
+                "Backspace fixes them, and space moves on to the next word.".to_string(),
+            ],
+        ),
+        1 => (
+            "Modes",
+            vec![
+                format!("<{}> Words: type a set number of words.", key(Action::WordMode)),
+                format!("<{}> Time: type as much as you can before the clock runs out.", key(Action::TimeMode)),
+                "Press the same key again to change the length.".to_string(),
+            ],
+        ),
+        2 => (
+            "Options",
+            vec![
+                format!("<{}> punctuation and <{}> numbers make the text harder.", key(Action::Punctuation), key(Action::Numbers)),
+                format!("<{}> beginner mode sticks to short, common words.", key(Action::Beginner)),
+                format!("<{}> opens every setting, keys included.", key(Action::OpenSettings)),
+            ],
+        ),
+        _ => (
+            "Progress",
+            vec![
+                "After each test you get your speed, accuracy and a chart.".to_string(),
+                format!("<{}> drills the words you got wrong.", key(Action::Drill)),
+                format!("<{}> shows every past run, with trends over time.", key(Action::OpenHistory)),
+            ],
+        ),
     }
-
-    let text = Text::from(Line::from(spans));
-    let p = Paragraph::new(text)
-        .wrap(Wrap { trim: true });
-    f.render_widget(p, area);
 }
 
-fn render_history_view(f: &mut Frame, app: &mut App, area: Rect) {
-    let chunks = Layout::default()
+fn render_tutorial(f: &mut Frame, app: &App, area: Rect) {
+    let (title, body) = tutorial_page(app, app.tutorial_page);
+    let last = app.tutorial_page + 1 == TUTORIAL_PAGES;
+    let mut lines = vec![
+        Line::from(Span::styled(title, Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(
+            format!("{} of {}", app.tutorial_page + 1, TUTORIAL_PAGES),
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(body.into_iter().map(Line::from));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        if last {
+            format!("<{}> let's type!", app.keymap.label(Action::Next))
+        } else {
+            format!("<{}> next | <{}> skip", app.keymap.label(Action::Next), app.keymap.label(Action::Back))
+        },
+        Style::default().fg(Color::Green),
+    )));
+
+    let v_center = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(10), // Stats container
-            Constraint::Min(1),     // List
-        ])
+        .constraints([Constraint::Percentage(30), Constraint::Length(lines.len() as u16), Constraint::Min(0)])
         .split(area);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center).wrap(Wrap { trim: true }), v_center[1]);
+}
 
-    // Stats Split
-    let stats_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(50),
-            Constraint::Percentage(50),
-        ])
-        .split(chunks[0]);
+/// The start of the shuffled text the next test will use, cut to fit `width`. Blank without a
+/// preview.
+fn preview_line(app: &App, width: usize) -> Line<'static> {
+    let Some(preview) = &app.word_preview else {
+        return Line::from("");
+    };
+    let room = width.saturating_sub(8).max(10);
+    let mut text = String::from("Next: ");
+    for c in preview.chars() {
+        if text.chars().count() + 1 >= room {
+            text.push('…');
+            break;
+        }
+        text.push(c);
+    }
+    Line::from(Span::styled(text, Style::default().fg(app.theme.accent)))
+}
 
-    // Stats Calculation
-    let mut word_stats_map: std::collections::HashMap<String, (f64, f64, usize)> = std::collections::HashMap::new();
-    let mut time_stats_map: std::collections::HashMap<String, (f64, f64, usize)> = std::collections::HashMap::new();
+/// Runs in each window of the welcome screen's trailing average.
+const RECENT_RUNS: usize = 10;
 
-    for result in &app.history {
-        let (map, key) = if result.mode.starts_with("Words") {
-            (&mut word_stats_map, result.mode.clone())
-        } else {
-            (&mut time_stats_map, result.mode.clone())
-        };
-        
-        // Normalize key to just the number for display if possible, or keep full mode string
-        // The mode string is like "Words: 10" or "Time: 15s"
-        
-        let entry = map.entry(key).or_insert((0.0, 0.0, 0));
-        entry.0 += result.wpm;
-        entry.1 += result.accuracy;
-        entry.2 += 1;
+/// The average WPM of the latest runs, with an arrow showing which way it moved from the runs
+/// before, then how consistent the latest runs of the selected mode have been. Blank when there
+/// is no history.
+fn recent_average_line(app: &App) -> Line<'static> {
+    let Some((recent, previous)) = history::recent_wpm_averages(&app.history, RECENT_RUNS) else {
+        return Line::from("");
+    };
+    let runs = app.history.len().min(RECENT_RUNS);
+    let mut spans = vec![Span::styled(
+        format!("Last {} avg: {:.1} WPM", runs, recent),
+        Style::default().fg(Color::DarkGray),
+    )];
+    if let Some(previous) = previous {
+        let change = recent - previous;
+        let (arrow, color) = if change >= 0.0 { ("▲", Color::Green) } else { ("▼", Color::Red) };
+        spans.push(Span::styled(format!(" {} {:+.1}", arrow, change), Style::default().fg(color)));
     }
-
-    // Helper to render stats list
-    fn render_stats_column(f: &mut Frame, map: std::collections::HashMap<String, (f64, f64, usize)>, title: &str, area: Rect) {
-        let mut lines = Vec::new();
-        let mut modes: Vec<&String> = map.keys().collect();
-        modes.sort();
-
-        for mode in modes {
-            if let Some((total_wpm, total_acc, count)) = map.get(mode) {
-                 let avg_wpm = total_wpm / *count as f64;
-                 let avg_acc = total_acc / *count as f64;
-                 lines.push(Line::from(vec![
-                     Span::styled(format!("{:<15}", mode), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-                     Span::raw(" | "),
-                     Span::styled(format!("WPM: {:<5.1}", avg_wpm), Style::default().fg(Color::Yellow)),
-                     Span::raw(" | "),
-                     Span::styled(format!("Acc: {:.1}%", avg_acc), Style::default().fg(Color::Green)),
-                     Span::raw(format!(" ({})", count)),
-                 ]));
-            }
+    if let Some((recent, previous)) = history::history_consistency(&app.history, &app.mode_label(), RECENT_RUNS) {
+        spans.push(Span::styled(format!(" | Consistency: {:.0}%", recent), Style::default().fg(Color::DarkGray)));
+        if let Some(previous) = previous {
+            let change = recent - previous;
+            let (arrow, color) = if change >= 0.0 { ("▲", Color::Green) } else { ("▼", Color::Red) };
+            spans.push(Span::styled(format!(" {} {:+.0}", arrow, change), Style::default().fg(color)));
         }
-        
-        if lines.is_empty() {
-            lines.push(Line::from("No data."));
-        }
-
-        let block = Block::default().borders(Borders::ALL).title(title);
-        let widget = Paragraph::new(lines).block(block);
-        f.render_widget(widget, area);
     }
+    Line::from(spans)
+}
 
-    render_stats_column(f, word_stats_map, "Word Tests", stats_chunks[0]);
-    render_stats_column(f, time_stats_map, "Time Tests", stats_chunks[1]);
+/// Best WPM against the mode's goal: "Goal: best 72 / 80 WPM (90%)", or a cheer once it's met.
+fn goal_span(best: f64, goal: u32) -> Span<'static> {
+    if best >= goal as f64 {
+        Span::styled(
+            format!("Goal met! Best {:.0} / {} WPM", best, goal),
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )
+    } else {
+        Span::styled(
+            format!("Goal: best {:.0} / {} WPM ({:.0}%)", best, goal, best / goal as f64 * 100.0),
+            Style::default().fg(Color::Yellow),
+        )
+    }
+}
 
+/// What the selected mode will ask for, e.g. "25 words · ~146 characters".
+fn test_length_summary(app: &App) -> String {
+    let word_length = app.expected_word_length();
+    match app.test_mode {
+        TestMode::Words(n) => {
+            let chars = word_length * n as f64 + n.saturating_sub(1) as f64;
+            format!("{} words · ~{:.0} characters", n, chars)
+        }
+        TestMode::Time(_) => format!("~{:.1} characters per word", word_length),
+    }
+}
 
-    // History List
-    // We render Newest First (Reverse Order)
-    let header_cells = ["Date", "Mode", "WPM", "Accuracy"]
-        .iter()
-        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
-    let header = Row::new(header_cells).height(1).bottom_margin(1);
-    
-    let rows = app.history.iter().rev().map(|result| {
-        let cells = vec![
-            Cell::from(result.timestamp.format("%Y-%m-%d %H:%M").to_string()),
-            Cell::from(result.mode.clone()),
-            Cell::from(format!("{:.1}", result.wpm)),
-            Cell::from(format!("{:.1}%", result.accuracy)),
-        ];
-        Row::new(cells)
-    });
-    
-    let t = Table::new(
-        rows,
-        [
-            Constraint::Percentage(30),
-            Constraint::Percentage(20),
-            Constraint::Percentage(20),
-            Constraint::Percentage(30),
-        ]
-    )
-    .header(header)
-    .block(Block::default().borders(Borders::ALL).title("Test History"))
-    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-    .highlight_symbol(">> ");
-    
-    f.render_stateful_widget(t, chunks[1], &mut app.history_state);
+/// First of `total` settings shown when `rows` fit, keeping `selection` in the middle where it
+/// can. There are more options than fit on a typical 24-line terminal, so the list scrolls with
+/// the selection instead of cutting off the ones at the bottom.
+fn settings_scroll(selection: usize, total: usize, rows: usize) -> usize {
+    selection.saturating_sub(rows / 2).min(total.saturating_sub(rows))
 }
 
-fn render_performance_view(
-    f: &mut Frame, 
-    area: Rect, 
-    wpm: f64, 
-    acc: f64, 
-    wpm_history: &[(f64, f64)], 
-    error_points: &[(f64, f64)],
-    is_new_result: bool
-) {
-    let title = if is_new_result { "Test Complete!" } else { "Test Details" };
-    
-    let results_text = vec![
-        Line::from(Span::styled(title, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+/// Lists every option with its value, the selected one highlighted.
+fn render_settings(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines = vec![
+        Line::from(Span::styled("Settings", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
         Line::from(""),
-        Line::from(vec![
-            Span::raw("WPM: "),
-            Span::styled(format!("{:.1}", wpm), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-        ]),
-        Line::from(vec![
-            Span::raw("Accuracy: "),
-            Span::styled(format!("{:.1}%", acc), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-        ]),
     ];
-    
-     let p = Paragraph::new(results_text)
-        .alignment(Alignment::Center)
-        .wrap(Wrap { trim: true });
-     
-     let v_center = Layout::default()
+    let rows = (area.height as usize).saturating_sub(lines.len()).max(1);
+    let total = SettingOption::ALL.len();
+    let first = settings_scroll(app.settings_selection, total, rows);
+    for (i, &option) in SettingOption::ALL.iter().enumerate().skip(first).take(rows) {
+        let (label, value, style) = setting_display(app, option);
+        let mut label_style = Style::default();
+        let mut value_style = style;
+        if app.settings_selection == i {
+            label_style = label_style.add_modifier(Modifier::REVERSED);
+            value_style = value_style.add_modifier(Modifier::REVERSED);
+        }
+        if app.flashing_option() == Some(option) {
+            label_style = label_style.fg(Color::Black).bg(Color::Yellow);
+            value_style = value_style.fg(Color::Black).bg(Color::Yellow);
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<16}", label), label_style),
+            Span::styled(format!("{:<24}", value), value_style),
+        ]));
+    }
+
+    let height = lines.len() as u16;
+    let top = if total > rows { Constraint::Length(0) } else { Constraint::Percentage(20) };
+    let v_center = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(2), // Top padding
-            Constraint::Length(6), // Results Text
-            Constraint::Length(2), // Gap
-            Constraint::Min(10),   // Graph area
-        ])
+        .constraints([top, Constraint::Length(height), Constraint::Min(0)])
         .split(area);
-    
-    f.render_widget(p, v_center[1]);
-
-    // Render Graph
-    use ratatui::{
-        symbols,
-        widgets::{Axis, Chart, Dataset, GraphType},
-    };
+    let h_center = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(40), Constraint::Min(0)])
+        .split(v_center[1]);
+    f.render_widget(Paragraph::new(lines), h_center[1]);
+}
 
-    let raw_wpm_data: Vec<(f64, f64)> = wpm_history.to_vec();
-    
-    // Interpolate WPM data for smooth curve
-    fn interpolate_data(data: &[(f64, f64)], resolution: usize) -> Vec<(f64, f64)> {
-        if data.len() < 2 {
-            return data.to_vec();
-        }
-
-        let mut smooth_data = Vec::new();
-        
-        for i in 0..data.len() - 1 {
-            let p0 = if i == 0 { data[0] } else { data[i - 1] };
-            let p1 = data[i];
-            let p2 = data[i + 1];
-            let p3 = if i + 2 < data.len() { data[i + 2] } else { p2 };
-
-            for t_step in 0..resolution {
-                let t = t_step as f64 / resolution as f64;
-                let t2 = t * t;
-                let t3 = t2 * t;
-
-                let x = 0.5 * (
-                    (2.0 * p1.0) +
-                    (-p0.0 + p2.0) * t +
-                    (2.0 * p0.0 - 5.0 * p1.0 + 4.0 * p2.0 - p3.0) * t2 +
-                    (-p0.0 + 3.0 * p1.0 - 3.0 * p2.0 + p3.0) * t3
-                );
-                
-                let y = 0.5 * (
-                    (2.0 * p1.1) +
-                    (-p0.1 + p2.1) * t +
-                    (2.0 * p0.1 - 5.0 * p1.1 + 4.0 * p2.1 - p3.1) * t2 +
-                    (-p0.1 + 3.0 * p1.1 - 3.0 * p2.1 + p3.1) * t3
-                );
-                
-                smooth_data.push((x, y));
-            }
-        }
-        if let Some(last) = data.last() {
-            smooth_data.push(*last);
+/// The fixed touch-typing exercises, each with the start of its text.
+fn render_drills(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines = vec![
+        Line::from(Span::styled("Drills", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+    ];
+    for (i, preset) in DrillPreset::ALL.iter().enumerate() {
+        let mut style = Style::default();
+        if app.drill_selection == i {
+            style = style.add_modifier(Modifier::REVERSED);
         }
-        
-        smooth_data
+        lines.push(Line::from(vec![
+            Span::styled(format!("{:<12}", preset.name()), style),
+            Span::styled(
+                format!("{:<28}", preset.text(app.settings.keyboard_layout, 3)),
+                style.fg(Color::DarkGray),
+            ),
+        ]));
     }
 
-    let wpm_data = interpolate_data(&raw_wpm_data, 20); // 20 points between each sample
-    
-    let min_time = raw_wpm_data.first().map(|(t, _)| *t).unwrap_or(0.0);
-    let max_time = raw_wpm_data.last().map(|(t, _)| *t).unwrap_or(60.0).max(1.0);
-    
-    // Process Error Data
-    let bin_size = 1.5;
-    let num_bins = (max_time / bin_size).ceil() as usize + 1;
-    let mut error_bins = vec![0; num_bins];
+    let height = lines.len() as u16;
+    let v_center = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(20), Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+    let h_center = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(40), Constraint::Min(0)])
+        .split(v_center[1]);
+    f.render_widget(Paragraph::new(lines), h_center[1]);
+}
 
-    for (t, _) in error_points {
-        let bin_index = (t / bin_size).floor() as usize;
-        if bin_index < num_bins {
-            error_bins[bin_index] += 1;
+/// Label, current value and value style of an option.
+fn setting_display(app: &App, option: SettingOption) -> (&'static str, String, Style) {
+    fn on_off(on: bool) -> (String, Style) {
+        if on {
+            ("ON".to_string(), Style::default().fg(Color::Green))
+        } else {
+            ("OFF".to_string(), Style::default().fg(Color::Red))
         }
     }
 
-    let max_error_count = *error_bins.iter().max().unwrap_or(&0) as f64;
-    let max_wpm = wpm_data.iter().map(|(_, w)| *w).fold(0.0, f64::max).max(10.0);
-
-    let error_data: Vec<(f64, f64)> = error_bins.iter().enumerate()
-        .filter(|(_, &count)| count > 0)
-        .map(|(i, &count)| {
-            let time = i as f64 * bin_size;
-            let normalized_y = if max_error_count > 0.0 {
-                (count as f64 / max_error_count) * max_wpm
+    let accent = Style::default().fg(Color::Yellow);
+    match option {
+        SettingOption::Mode => {
+            let mode = match app.test_mode {
+                TestMode::Words(_) => "Words",
+                TestMode::Time(_) => "Time",
+            };
+            ("Mode", mode.to_string(), accent)
+        }
+        SettingOption::Length => {
+            let length = match app.test_mode {
+                TestMode::Words(n) => format!("{} words", n),
+                TestMode::Time(s) => format!("{}s", s),
+            };
+            ("Length", length, accent)
+        }
+        SettingOption::Source => ("Text", app.settings.text_source.to_string(), accent),
+        SettingOption::Goal => match app.settings.wpm_goals.get(&app.mode_label()) {
+            Some(goal) => ("WPM Goal", format!("{} ({})", goal, app.mode_label()), accent),
+            None => ("WPM Goal", "OFF".to_string(), Style::default().fg(Color::Red)),
+        },
+        SettingOption::Punctuation => {
+            let (value, style) = on_off(app.include_punctuation);
+            let value = if app.include_punctuation {
+                format!("{} ({})", value, app.settings.punctuation_level)
             } else {
-                0.0
+                value
             };
-            (time, normalized_y)
-        })
-        .collect();
-
-    let graph_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Min(10),
-            Constraint::Length(6), 
-        ])
-        .split(v_center[3]);
-
-    let datasets = vec![
-        Dataset::default()
-            .name("WPM")
+            ("Punctuation", value, style)
+        }
+        SettingOption::PunctuationLevel => ("Punct. Level", format!("{}", app.settings.punctuation_level), accent),
+        SettingOption::Numbers => {
+            let (value, style) = on_off(app.include_numbers);
+            let value = if app.include_numbers {
+                let numbers = app.settings.numbers;
+                format!("{} ({}-{}, {:.0}%)", value, numbers.min, numbers.max, numbers.density * 100.0)
+            } else {
+                value
+            };
+            ("Numbers", value, style)
+        }
+        SettingOption::Cursor => ("Cursor", format!("{}", app.settings.cursor_style), accent),
+        SettingOption::Warmup => match app.settings.warmup_words {
+            0 => ("Warmup", "OFF".to_string(), Style::default().fg(Color::Red)),
+            1 => ("Warmup", "1 word".to_string(), accent),
+            n => ("Warmup", format!("{} words", n), accent),
+        },
+        SettingOption::TimerStart => ("Timer Starts", app.settings.timer_start.to_string(), accent),
+        SettingOption::FixedCursor => {
+            let (value, style) = on_off(app.settings.fixed_cursor);
+            ("Fixed Cursor", value, style)
+        }
+        SettingOption::Lookahead => match app.settings.lookahead_chars {
+            0 => ("Look-ahead", "OFF".to_string(), Style::default().fg(Color::Red)),
+            n => ("Look-ahead", format!("{} chars", n), accent),
+        },
+        SettingOption::FadeTyped => match app.settings.fade_typed_chars {
+            0 => ("Fade Typed", "OFF".to_string(), Style::default().fg(Color::Red)),
+            n => ("Fade Typed", format!("after {} chars", n), accent),
+        },
+        SettingOption::Metronome => match app.settings.metronome_bpm {
+            0 => ("Metronome", "OFF".to_string(), Style::default().fg(Color::Red)),
+            bpm => ("Metronome", format!("{} BPM", bpm), accent),
+        },
+        SettingOption::FinishWord => {
+            let (value, style) = on_off(app.settings.finish_word_on_timeout);
+            ("Finish Word", value, style)
+        }
+        SettingOption::CompleteWords => {
+            let (value, style) = on_off(app.settings.complete_words_wpm);
+            ("Whole-Word WPM", value, style)
+        }
+        SettingOption::EnterAsSpace => {
+            let (value, style) = on_off(app.settings.enter_as_space);
+            ("Enter as Space", value, style)
+        }
+        SettingOption::FinishOnSpace => {
+            let (value, style) = on_off(app.settings.finish_on_trailing_space);
+            ("Finish on Space", value, style)
+        }
+        SettingOption::FinishCue => {
+            let cue = app.settings.completion_cue;
+            let style = if cue == CompletionCue::Off { Style::default().fg(Color::Red) } else { accent };
+            ("Finish Cue", cue.to_string(), style)
+        }
+        SettingOption::HideResults => {
+            let (value, style) = on_off(app.settings.hide_results);
+            ("Hide Results", value, style)
+        }
+        SettingOption::FixErrors => {
+            let (value, style) = on_off(app.settings.require_correct_words);
+            ("Fix All Errors", value, style)
+        }
+        SettingOption::SuddenDeath => {
+            let (value, style) = on_off(app.settings.sudden_death);
+            ("Sudden Death", value, style)
+        }
+        SettingOption::RollingAverage => {
+            let (value, style) = on_off(app.settings.show_rolling_average);
+            ("Avg WPM Line", value, style)
+        }
+        SettingOption::Smoothing => ("WPM Smoothing", app.settings.chart_smoothing.to_string(), accent),
+        SettingOption::FocusMode => {
+            let (value, style) = on_off(app.settings.focus_mode);
+            ("Focus Mode", value, style)
+        }
+        SettingOption::Beginner => {
+            let (value, style) = on_off(app.settings.beginner);
+            ("Beginner", value, style)
+        }
+        SettingOption::Paste => ("Paste", app.settings.paste.to_string(), accent),
+        SettingOption::AutoSave => {
+            let interval = app.settings.snapshot_interval;
+            let style = if interval.enabled() { accent } else { Style::default().fg(Color::Red) };
+            ("Auto-save", interval.to_string(), style)
+        }
+        SettingOption::PracticeLoop => {
+            let (value, style) = on_off(app.settings.practice_loop);
+            ("Practice Loop", value, style)
+        }
+        SettingOption::Combos => match app.settings.drill_combos.as_slice() {
+            [] => ("Combo Drill", "All".to_string(), accent),
+            combos => ("Combo Drill", combos.join(", "), accent),
+        },
+        SettingOption::KeyboardLayout => ("Keyboard", app.settings.keyboard_layout.to_string(), accent),
+        SettingOption::Theme => ("Theme", app.theme.name.to_string(), accent),
+    }
+}
+
+/// Drawn in place of a space in the text that was typed as something else.
+const MISSED_SPACE: &str = "·";
+
+fn render_typing(f: &mut Frame, app: &App, area: Rect) {
+    // Work in graphemes so an accented letter or emoji made of several code points is one cell
+    let target_chars = text::graphemes(&app.target_text);
+    let input_chars = text::graphemes(&app.input);
+    let theme = &app.theme;
+
+    // Each word owns the space after it; a word is done once that space has been typed
+    let mut word_ranges = Vec::new();
+    let mut word_start = 0;
+    for (i, &c) in target_chars.iter().enumerate() {
+        if c == " " || i + 1 == target_chars.len() {
+            word_ranges.push(word_start..i + 1);
+            word_start = i + 1;
+        }
+    }
+    // Fading typed text dims by distance from the cursor instead of a word at a time
+    let fade = app.settings.fade_typed_chars;
+    let mut word_done_style: Vec<Option<Style>> = vec![None; target_chars.len()];
+    for range in word_ranges {
+        if input_chars.len() < range.end {
+            continue;
+        }
+        let has_error = range.clone().any(|i| input_chars[i] != target_chars[i]);
+        let style = if has_error {
+            Style::default().add_modifier(Modifier::UNDERLINED).underline_color(theme.incorrect)
+        } else if fade > 0 {
+            continue;
+        } else {
+            Style::default().fg(theme.correct).add_modifier(Modifier::DIM)
+        };
+        for i in range {
+            word_done_style[i] = Some(style);
+        }
+    }
+
+    // Spans for each target character, in order
+    let mut char_spans: Vec<Vec<Span>> = Vec::with_capacity(target_chars.len());
+    for (i, &target_char) in target_chars.iter().enumerate() {
+        let mut spans = Vec::new();
+        if i < input_chars.len() {
+            let input_char = input_chars[i];
+            // The character just typed may still be waiting for its combining mark
+            let partial = i + 1 == input_chars.len() && target_char.starts_with(input_char);
+            let style = if input_char == target_char || partial {
+                let style = Style::default().fg(theme.correct).add_modifier(theme.correct_modifier);
+                // Errors are left out, so they stay as loud as ever
+                if fade > 0 && input_chars.len() - i > fade {
+                    style.remove_modifier(theme.correct_modifier).add_modifier(Modifier::DIM)
+                } else {
+                    style
+                }
+            } else {
+                Style::default().fg(theme.incorrect).bg(theme.incorrect_bg).add_modifier(theme.incorrect_modifier)
+            };
+            // Finished words fade out when correct and stay underlined when they have errors
+            let style = match word_done_style[i] {
+                Some(done) => style.patch(done),
+                None => style,
+            };
+            // A mistyped space would otherwise be a blank cell, invisible on themes without an
+            // error background
+            let shown = if target_char == " " && input_char != " " { MISSED_SPACE } else { target_char };
+            spans.push(Span::styled(shown, style));
+        } else if i == input_chars.len() {
+            // Cursor position - highlight the character we need to type
+            match app.settings.cursor_style {
+                CursorStyle::Block => {
+                    spans.push(Span::styled(target_char.to_string(), Style::default().fg(theme.cursor_text).bg(theme.cursor)));
+                }
+                CursorStyle::Underline => {
+                    spans.push(Span::styled(
+                        target_char.to_string(),
+                        Style::default().fg(theme.cursor).add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+                    ));
+                }
+                // The terminal's own cursor draws the bar, placed on this cell below
+                CursorStyle::Bar => {
+                    spans.push(Span::styled(target_char.to_string(), Style::default().fg(theme.cursor)));
+                }
+            }
+        } else {
+            // The next few characters stand out a little from the rest of the text
+            let color = if i - input_chars.len() <= app.settings.lookahead_chars {
+                theme.lookahead
+            } else {
+                theme.pending
+            };
+            spans.push(Span::styled(target_char.to_string(), Style::default().fg(color)));
+        }
+        char_spans.push(spans);
+    }
+
+    // The unscored warmup words are set in italics
+    let warmup_end = if app.warming_up {
+        let words = app.settings.warmup_words;
+        target_chars.iter().enumerate().filter(|(_, &c)| c == " ").nth(words.saturating_sub(1)).map_or(0, |(i, _)| i + 1)
+    } else {
+        app.measured_from
+    };
+    for spans in char_spans.iter_mut().take(warmup_end) {
+        for span in spans.iter_mut() {
+            span.style = span.style.add_modifier(Modifier::ITALIC);
+        }
+    }
+
+    let width = (area.width as usize).max(1);
+    let height = area.height as usize;
+    let cursor = input_chars.len().min(target_chars.len().saturating_sub(1));
+    let widths: Vec<usize> = target_chars.iter().map(|c| c.width()).collect();
+    let bar_cursor = app.settings.cursor_style == CursorStyle::Bar && input_chars.len() < target_chars.len();
+
+    if app.settings.fixed_cursor {
+        // Teleprompter: one line through the middle of the area, scrolled so the cursor stays put
+        let (start, end, padding) = fixed_cursor_window(&widths, cursor, width);
+        let mut spans = vec![Span::raw(" ".repeat(padding))];
+        spans.extend(char_spans[start..end].concat());
+        let row = Rect { y: area.y + area.height / 2, height: area.height.min(1), ..area };
+        f.render_widget(Paragraph::new(Line::from(spans)), row);
+        if bar_cursor && area.height > 0 {
+            let x = padding + widths[start..cursor].iter().sum::<usize>();
+            f.set_cursor_position((area.x + x as u16, row.y));
+        }
+        return;
+    }
+
+    // Keep the line with the cursor in the middle of the text area
+    let line_starts = wrap_lines(&target_chars, width);
+    let cursor_line = line_starts.partition_point(|&start| start <= cursor).saturating_sub(1);
+    let scroll = scroll_offset(cursor_line, line_starts.len(), height);
+
+    let lines: Vec<Line> = line_starts
+        .iter()
+        .enumerate()
+        .skip(scroll)
+        .take(height)
+        .map(|(line, &start)| {
+            let end = line_starts.get(line + 1).copied().unwrap_or(target_chars.len());
+            Line::from(char_spans[start..end].concat())
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines), area);
+    if bar_cursor && (scroll..scroll + height).contains(&cursor_line) {
+        let x = widths[line_starts[cursor_line]..cursor].iter().sum::<usize>();
+        f.set_cursor_position((area.x + x as u16, area.y + (cursor_line - scroll) as u16));
+    }
+}
+
+/// How a character of the finished text compares with what was typed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReviewMark {
+    Correct,
+    Wrong,
+    Missing, // Never typed, in the word the test ended on
+    Extra,   // Typed past the end of the text
+}
+
+/// Marks each character of the finished run for review, as `(char to show, mark)`.
+///
+/// The text is shown up to the end of the last word that was started, so a timed test doesn't
+/// list all the words it never reached. Extra characters typed past the end follow the text.
+fn review_marks<'a>(target: &[&'a str], input: &[&'a str]) -> Vec<(&'a str, ReviewMark)> {
+    let typed = input.len().min(target.len());
+    let shown = if typed == 0 || typed == target.len() {
+        typed
+    } else {
+        // Finish the word the cursor was in, unless it stopped right after a space
+        match target[typed - 1] {
+            " " => typed,
+            _ => target[typed..].iter().position(|&c| c == " ").map_or(target.len(), |i| typed + i),
+        }
+    };
+
+    let mut marks: Vec<(&str, ReviewMark)> = target[..shown]
+        .iter()
+        .enumerate()
+        .map(|(i, &t)| match input.get(i) {
+            Some(&c) if c == t => (t, ReviewMark::Correct),
+            Some(_) => (t, ReviewMark::Wrong),
+            None => (t, ReviewMark::Missing),
+        })
+        .collect();
+    marks.extend(input.iter().skip(target.len()).map(|&c| (c, ReviewMark::Extra)));
+    marks
+}
+
+/// The finished text with every mistake marked in place, scrolled by `review_scroll`.
+/// The error of a past run being stepped through, shown in the text it was typed against.
+fn render_error_review(f: &mut Frame, app: &App, result: &TestResult, index: usize, area: Rect) {
+    let (time, _) = result.error_points[index];
+    let title = format!(" Error {}/{} at {:.1}s ", index + 1, result.error_points.len(), time);
+    let block = Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(app.theme.incorrect));
+    let width = block.inner(area).width as usize;
+    let line = error_context_line(&result.target_text, result.error_positions[index], width, &app.theme);
+    f.render_widget(Paragraph::new(line).alignment(Alignment::Center).block(block), area);
+}
+
+/// Up to `width` characters of `text` around the error at `position`, with the word it landed in
+/// picked out and the character itself underlined. Typing past the end marks a blank after it.
+fn error_context_line(text: &str, position: usize, width: usize, theme: &Theme) -> Line<'static> {
+    let mut chars = text::graphemes(text);
+    if position >= chars.len() {
+        chars.resize(position + 1, " ");
+    }
+    // A mistyped space belongs to the word before it
+    let word_start = chars[..position].iter().rposition(|&c| c == " ").map_or(0, |i| i + 1);
+    let word_end = chars[position..].iter().position(|&c| c == " ").map_or(chars.len(), |i| position + i);
+
+    let end = (position.saturating_sub(width / 2) + width).min(chars.len());
+    let start = end.saturating_sub(width);
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_style = None;
+    for (i, &c) in chars.iter().enumerate().take(end).skip(start) {
+        let (shown, style) = if i == position {
+            let shown = if c == " " { MISSED_SPACE } else { c };
+            (shown, Style::default().fg(theme.incorrect).add_modifier(Modifier::BOLD | Modifier::UNDERLINED))
+        } else if (word_start..word_end).contains(&i) {
+            (c, Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+        } else {
+            (c, Style::default().fg(theme.pending))
+        };
+        if run_style.is_some_and(|current| current != style) {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style.unwrap_or_default()));
+        }
+        run.push_str(shown);
+        run_style = Some(style);
+    }
+    spans.push(Span::styled(run, run_style.unwrap_or_default()));
+    Line::from(spans)
+}
+
+fn render_review(f: &mut Frame, app: &mut App, area: Rect) {
+    let target = text::graphemes(&app.target_text);
+    let input = text::graphemes(&app.input);
+    let marks = review_marks(&target, &input);
+    let theme = app.theme;
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(1)])
+        .split(area);
+    let errors = marks.iter().filter(|(_, mark)| *mark != ReviewMark::Correct).count();
+    let legend = Line::from(vec![
+        Span::styled("Review", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::raw(format!("  {} to look at  ", errors)),
+        Span::styled("wrong", Style::default().fg(theme.incorrect).add_modifier(Modifier::UNDERLINED)),
+        Span::raw(" "),
+        Span::styled("missing", Style::default().fg(theme.pending).add_modifier(Modifier::UNDERLINED)),
+        Span::raw(" "),
+        Span::styled("extra", Style::default().fg(theme.incorrect).add_modifier(Modifier::CROSSED_OUT)),
+    ]);
+    f.render_widget(Paragraph::new(legend), layout[0]);
+
+    let spans: Vec<Span> = marks
+        .iter()
+        .map(|&(c, mark)| match mark {
+            ReviewMark::Correct => Span::styled(c, Style::default().fg(theme.correct)),
+            ReviewMark::Wrong => {
+                let shown = if c == " " { MISSED_SPACE } else { c };
+                Span::styled(
+                    shown,
+                    Style::default().fg(theme.incorrect).bg(theme.incorrect_bg).add_modifier(Modifier::UNDERLINED),
+                )
+            }
+            ReviewMark::Missing => {
+                Span::styled(c, Style::default().fg(theme.pending).add_modifier(Modifier::UNDERLINED))
+            }
+            ReviewMark::Extra => {
+                Span::styled(c, Style::default().fg(theme.incorrect).add_modifier(Modifier::CROSSED_OUT))
+            }
+        })
+        .collect();
+
+    let chars: Vec<&str> = marks.iter().map(|&(c, _)| c).collect();
+    let width = (layout[1].width as usize).max(1);
+    let height = layout[1].height as usize;
+    let line_starts = wrap_lines(&chars, width);
+    // Stop scrolling once the last line is at the bottom
+    app.review_scroll = app.review_scroll.min(line_starts.len().saturating_sub(height));
+
+    let lines: Vec<Line> = line_starts
+        .iter()
+        .enumerate()
+        .skip(app.review_scroll)
+        .take(height)
+        .map(|(line, &start)| {
+            let end = line_starts.get(line + 1).copied().unwrap_or(chars.len());
+            Line::from(spans[start..end].to_vec())
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), layout[1]);
+}
+
+/// Word-wraps the graphemes in `chars` to `width` columns, returning the index of the first one
+/// on each line.
+///
+/// Columns are counted by display width, so wide (e.g. CJK) characters take two. The space after
+/// a word stays on the word's line even if it hangs past the edge, and words longer than a whole
+/// line are split.
+fn wrap_lines(chars: &[&str], width: usize) -> Vec<usize> {
+    // Column at which each char starts, plus the end of the last one
+    let mut columns = Vec::with_capacity(chars.len() + 1);
+    let mut column = 0;
+    columns.push(0);
+    for &c in chars {
+        column += c.width();
+        columns.push(column);
+    }
+    let span = |from: usize, to: usize| columns[to] - columns[from];
+
+    let mut starts = vec![0];
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let mut word_end = i;
+        while word_end < chars.len() && chars[word_end] != " " {
+            word_end += 1;
+        }
+        if i > line_start && span(line_start, word_end) > width {
+            starts.push(i);
+            line_start = i;
+        }
+        while span(line_start, word_end) > width {
+            // Fit as many chars as possible, but always at least one
+            let mut split = line_start + 1;
+            while split < word_end && span(line_start, split + 1) <= width {
+                split += 1;
+            }
+            line_start = split;
+            starts.push(line_start);
+        }
+        // Step past the word and the space after it
+        i = word_end + 1;
+    }
+    starts
+}
+
+/// The slice of text shown on a `width`-column line with the character at `cursor` in the middle
+/// column, given each character's display width. Returns `(start, end, padding)`: the range of
+/// characters to draw after `padding` blank columns, which fill the left half near the start of
+/// the text.
+fn fixed_cursor_window(widths: &[usize], cursor: usize, width: usize) -> (usize, usize, usize) {
+    let center = width / 2;
+    let mut start = cursor.min(widths.len());
+    let mut left = 0;
+    while start > 0 && left + widths[start - 1] <= center {
+        start -= 1;
+        left += widths[start];
+    }
+    let mut end = cursor.min(widths.len());
+    let mut used = center;
+    while end < widths.len() && used + widths[end] <= width {
+        used += widths[end];
+        end += 1;
+    }
+    (start, end, center - left)
+}
+
+/// First line to show so `cursor_line` sits mid-area without scrolling past either end of the text.
+fn scroll_offset(cursor_line: usize, total_lines: usize, height: usize) -> usize {
+    if total_lines <= height {
+        return 0;
+    }
+    cursor_line.saturating_sub(height / 2).min(total_lines - height)
+}
+
+fn render_history_view(f: &mut Frame, app: &App, history_state: &mut TableState, area: Rect) {
+    if app.history_disabled {
+        let notice = Paragraph::new(vec![
+            Line::from(Span::styled("History disabled", Style::default().fg(Color::Yellow))),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Runs are not being saved (--no-history or the no_history setting).",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ])
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+        let v_center = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+        f.render_widget(notice, v_center[1]);
+        return;
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(10), // Stats container
+            Constraint::Length(10), // Trend chart
+            Constraint::Min(1),     // List
+        ])
+        .split(area);
+
+    // Stats Split
+    let stats_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ])
+        .split(chunks[0]);
+
+    // Stats per mode, split into Words and Time tests (mode names are like "Words: 10" or "Time: 15s")
+    let (word_stats, time_stats): (Vec<_>, Vec<_>) = history::mode_summaries(&app.history)
+        .into_iter()
+        .partition(|(mode, _)| mode.starts_with("Words"));
+
+    // Helper to render stats list
+    fn render_stats_column(f: &mut Frame, stats: &[(String, history::ModeSummary)], title: &str, area: Rect) {
+        let mut lines = Vec::new();
+        for (mode, summary) in stats {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<15}", mode), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+                Span::raw(" | "),
+                Span::styled(format!("WPM: {:<5.1}", summary.average_wpm), Style::default().fg(Color::Yellow)),
+                Span::raw(" | "),
+                Span::styled(format!("Acc: {:.1}%", summary.average_accuracy), Style::default().fg(Color::Green)),
+                Span::raw(format!(" ({})", summary.tests)),
+            ]));
+        }
+
+        if lines.is_empty() {
+            lines.push(Line::from("No data."));
+        }
+
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let widget = Paragraph::new(lines).block(block);
+        f.render_widget(widget, area);
+    }
+
+    render_stats_column(f, &word_stats, "Word Tests", stats_chunks[0]);
+    render_stats_column(f, &time_stats, "Time Tests", stats_chunks[1]);
+    render_history_trend(f, app, chunks[1]);
+
+    // History List
+    // We render Newest First (Reverse Order)
+    let header_cells = ["Date", "Mode", "WPM", "Accuracy"]
+        .iter()
+        .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    // Long mode labels (drills, custom texts) are cut to the Mode column here, the same way the
+    // table will lay it out, so they end in "…" instead of being clipped mid-character
+    let inner = Rect::new(0, 0, chunks[2].width.saturating_sub(2), 1);
+    let [_, columns] =
+        Layout::horizontal([Constraint::Length(HISTORY_HIGHLIGHT.width() as u16), Constraint::Fill(0)]).areas(inner);
+    let mode_width = Layout::horizontal(HISTORY_COLUMNS).spacing(1).split(columns)[1].width as usize;
+
+    let rows = app.history.iter().enumerate().rev().map(|(i, result)| {
+        let marked = app.compare_index == Some(i);
+        let date = result.timestamp.format("%Y-%m-%d %H:%M").to_string();
+        let cells = vec![
+            Cell::from(if marked { format!("* {}", date) } else { date }),
+            Cell::from(mode_with_badges(result, mode_width)),
+            Cell::from(format!("{:.1}", result.wpm)),
+            Cell::from(format!("{:.1}%", result.accuracy)),
+        ];
+        if marked {
+            Row::new(cells).style(Style::default().fg(Color::Magenta))
+        } else {
+            Row::new(cells)
+        }
+    });
+    
+    let t = Table::new(rows, HISTORY_COLUMNS)
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title("Test History"))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .highlight_symbol(HISTORY_HIGHLIGHT);
+    
+    history_state.select((!app.history.is_empty()).then_some(app.selected_history_index));
+    f.render_stateful_widget(t, chunks[2], history_state);
+}
+
+/// The results screen with everything covered up, so the WPM can be guessed before it's shown.
+fn render_hidden_results(f: &mut Frame, app: &App, area: Rect) {
+    let lines = vec![
+        Line::from(Span::styled("Test Complete!", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from("How fast do you think that was?"),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!("Press <{}> to reveal your results", app.keymap.label(Action::Reveal)),
+            Style::default().fg(app.theme.accent),
+        )),
+    ];
+    let height = lines.len() as u16;
+    let v_center = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(30), Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), v_center[1]);
+}
+
+/// Celebrates how far a sudden death run got before its first error.
+fn sudden_death_message(app: &App, chars: usize) -> String {
+    if app.total_incorrect_strokes == 0 {
+        return format!("Flawless! {} characters without a single error", chars);
+    }
+    match history::best_sudden_death(app.past_results()) {
+        Some(best) if chars > best => format!("New best streak! {} characters error-free (was {})", chars, best),
+        Some(best) => format!("{} characters error-free (best: {})", chars, best),
+        None => format!("{} characters error-free", chars),
+    }
+}
+
+/// Columns of the history table: date, mode, WPM and accuracy.
+const HISTORY_COLUMNS: [Constraint; 4] = [
+    Constraint::Percentage(30),
+    Constraint::Percentage(20),
+    Constraint::Percentage(20),
+    Constraint::Percentage(30),
+];
+/// Marks the selected row of the history table.
+const HISTORY_HIGHLIGHT: &str = ">> ";
+
+/// The run's mode followed by a small badge for each option that was on, cut to fit `width`
+/// cells.
+fn mode_with_badges(result: &TestResult, width: usize) -> Line<'static> {
+    let badge = Style::default().fg(Color::Black).bg(Color::Cyan);
+    let sudden_death = result.sudden_death.is_some();
+    // The mode always shows a little of itself; badges that don't fit beside that are left out
+    let mut room = width.saturating_sub(4);
+    let labels: Vec<&str> = [(result.punctuation, "P"), (result.numbers, "#"), (sudden_death, "SD")]
+        .into_iter()
+        .filter_map(|(on, label)| on.then_some(label))
+        .take_while(|label| {
+            let fits = label.len() < room;
+            room = room.saturating_sub(label.len() + 1);
+            fits
+        })
+        .collect();
+    let badges_width: usize = labels.iter().map(|label| label.len() + 1).sum();
+    let mut spans = vec![Span::raw(text::truncate(&result.mode, width - badges_width))];
+    for label in labels {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(label, badge));
+    }
+    Line::from(spans)
+}
+
+/// Plots WPM or accuracy of every run in history, oldest first.
+fn render_history_trend(f: &mut Frame, app: &App, area: Rect) {
+    use ratatui::{
+        symbols,
+        widgets::{Axis, Chart, Dataset, GraphType},
+    };
+
+    let (title, color, values): (&str, Color, Vec<f64>) = match app.history_trend {
+        HistoryTrend::Wpm => ("WPM Trend", Color::Yellow, app.history.iter().map(|r| r.wpm).collect()),
+        HistoryTrend::Accuracy => ("Accuracy Trend", Color::Green, app.history.iter().map(|r| r.accuracy).collect()),
+    };
+    let data: Vec<(f64, f64)> = values.iter().enumerate().map(|(i, &v)| ((i + 1) as f64, v)).collect();
+
+    let [min_y, max_y] = match app.history_trend {
+        HistoryTrend::Wpm => [0.0, values.iter().copied().fold(0.0, f64::max).max(10.0)],
+        HistoryTrend::Accuracy => accuracy_axis_bounds(&values),
+    };
+    let max_x = (data.len() as f64).max(2.0);
+
+    let datasets = vec![Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .style(Style::default().fg(color))
+        .graph_type(GraphType::Line)
+        .data(&data)];
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(format!("{} (t to switch)", title)).borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .title("Run")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([1.0, max_x])
+                .labels(vec![
+                    Span::styled("1", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", max_x), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([min_y, max_y])
+                .labels(vec![
+                    Span::styled(format!("{:.0}", min_y), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", max_y), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+/// Y range for an accuracy chart: 80-100% while every run is above 80%, so small changes are
+/// visible, otherwise widened down to the nearest 10% below the worst run.
+fn accuracy_axis_bounds(values: &[f64]) -> [f64; 2] {
+    let lowest = values.iter().copied().fold(100.0, f64::min);
+    let floor = if lowest >= 80.0 { 80.0 } else { (lowest / 10.0).floor() * 10.0 };
+    [floor.max(0.0), 100.0]
+}
+
+/// WPM samples (one a second) averaged for the rolling average line.
+const ROLLING_AVERAGE_WINDOW: usize = 5;
+
+/// Everything the results screen and history details need to draw a run.
+struct PerformanceView<'a> {
+    wpm: f64,
+    kpm: Option<f64>, // `None` for runs saved before it was tracked
+    accuracy: f64,
+    raw_accuracy: f64,
+    consistency: f64,
+    wpm_history: &'a [(f64, f64)],
+    error_points: &'a [(f64, f64)],
+    target_text: &'a str,
+    error_positions: &'a [usize], // Where in `target_text` each error was typed
+    keystroke_times: &'a [f64], // Empty for past runs, which don't keep keystroke timings
+    slowest_word: Option<(&'a str, f64)>, // Also only known for new results
+    rank: Option<String>,
+    note: Option<String>,
+    is_new_result: bool,
+    session_test: Option<usize>, // Which test of the session this is, for new results
+    complete_words: bool,  // WPM left out the word cut off by the timer
+    theme: Theme,
+    rolling_average: bool, // Overlay the moving average of the WPM samples
+    keyboard_layout: KeyboardLayout, // Which keys the heatmap shows where
+    smoothing: ChartSmoothing,
+    from_origin: bool,     // Start the WPM line at (0, 0)
+    goal: Option<(f64, u32)>, // Best WPM of the mode and its goal
+    chart_view: ChartView,
+}
+
+fn render_performance_view(f: &mut Frame, area: Rect, view: &PerformanceView) {
+    let PerformanceView { wpm, accuracy: acc, wpm_history, error_points, .. } = *view;
+    let title = if view.is_new_result { "Test Complete!" } else { "Test Details" };
+    
+    let session_line = match view.session_test {
+        Some(n) => Line::from(Span::styled(format!("Test #{} this session", n), Style::default().fg(Color::DarkGray))),
+        None => Line::from(""),
+    };
+    let mut wpm_line = vec![
+        Span::raw("WPM: "),
+        Span::styled(format!("{:.1}", wpm), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        Span::styled(
+            if view.complete_words { " (whole words)" } else { "" },
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if let Some(kpm) = view.kpm {
+        wpm_line.push(Span::raw(" | KPM: "));
+        wpm_line.push(Span::styled(format!("{:.0}", kpm), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+    }
+    let mut results_text = vec![
+        Line::from(Span::styled(title, Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))),
+        session_line,
+        Line::from(wpm_line),
+        Line::from(vec![
+            Span::raw("Accuracy: "),
+            Span::styled(format!("{:.1}%", acc), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::raw(" | Raw: "),
+            Span::styled(format!("{:.1}%", view.raw_accuracy), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![
+            Span::raw("Consistency: "),
+            Span::styled(format!("{:.0}%", view.consistency), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+        ]),
+    ];
+    // Past runs don't keep keystroke timings, so only a new result shows these
+    if let Some((fastest, slowest)) = stats::interval_extremes(&stats::keystroke_intervals(view.keystroke_times)) {
+        if let Some(line) = results_text.last_mut() {
+            line.spans.push(Span::raw(format!(
+                " | Keys: fastest {}, slowest {}",
+                format_interval(fastest),
+                format_interval(slowest)
+            )));
+        }
+    }
+    if let Some((word, word_wpm)) = view.slowest_word {
+        results_text.push(Line::from(vec![
+            Span::raw("Slowest word: "),
+            Span::styled(format!("'{}'", word), Style::default().fg(Color::Yellow)),
+            Span::raw(format!(" ({:.0} WPM)", word_wpm)),
+        ]));
+    }
+    let mut rank_line = Vec::new();
+    if let Some(rank) = &view.rank {
+        rank_line.push(Span::styled(rank.as_str(), Style::default().fg(Color::Magenta)));
+    }
+    if let Some((best, goal)) = view.goal {
+        if !rank_line.is_empty() {
+            rank_line.push(Span::raw(" | "));
+        }
+        rank_line.push(goal_span(best, goal));
+    }
+    if !rank_line.is_empty() {
+        results_text.push(Line::from(rank_line));
+    }
+    if let Some(note) = &view.note {
+        results_text.push(Line::from(Span::styled(note.as_str(), Style::default().fg(Color::DarkGray))));
+    }
+    
+     let p = Paragraph::new(results_text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+     
+     let v_center = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Top padding
+            Constraint::Length(8), // Results Text
+            Constraint::Length(2), // Gap
+            Constraint::Min(10),   // Graph area
+        ])
+        .split(area);
+    
+    f.render_widget(p, v_center[1]);
+
+    // Render Graph
+    use ratatui::{
+        symbols,
+        widgets::{Axis, Chart, Dataset, GraphType},
+    };
+
+    let raw_wpm_data = chart_points(wpm_history, view.from_origin);
+
+    let wpm_data = wpm_curve(wpm_history, view.from_origin, view.smoothing, v_center[3].width);
+    
+    let min_time = raw_wpm_data.first().map(|(t, _)| *t).unwrap_or(0.0);
+    let max_time = raw_wpm_data.last().map(|(t, _)| *t).unwrap_or(60.0).max(1.0);
+    
+    let (bin_size, error_bins) = error_bins(error_points, max_time);
+
+    let max_error_count = *error_bins.iter().max().unwrap_or(&0) as f64;
+    let max_wpm = wpm_data.iter().map(|(_, w)| *w).fold(0.0, f64::max).max(10.0);
+
+    let error_data: Vec<(f64, f64)> = error_bins.iter().enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(i, &count)| {
+            let time = i as f64 * bin_size;
+            let normalized_y = if max_error_count > 0.0 {
+                (count as f64 / max_error_count) * max_wpm
+            } else {
+                0.0
+            };
+            (time, normalized_y)
+        })
+        .collect();
+
+    // The error axis column is sized to its widest label and hidden entirely when there are no errors
+    let error_axis_width = if max_error_count > 0.0 {
+        let label_width = format!("{:.0}", max_error_count).len().max("Errs".len()) as u16;
+        label_width + 2 // Tick mark and spacing
+    } else {
+        0
+    };
+
+    if view.chart_view == ChartView::Keys {
+        render_key_heatmap(f, v_center[3], view);
+        return;
+    }
+
+    // The other views are drawn from keystroke timings, which only new results have
+    let has_timings = view.keystroke_times.len() > 2;
+    if view.chart_view == ChartView::Latency && has_timings {
+        render_latency_histogram(f, v_center[3], view.keystroke_times);
+        return;
+    }
+
+    // A run over within a second or two has a sample or none, not enough for a line; a chart
+    // of it would just be empty axes
+    if stats::dedup_samples(wpm_history).len() < 2 {
+        let notice = Paragraph::new("Not enough data for a chart: the test was too short")
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        let area = v_center[3];
+        f.render_widget(notice, Rect::new(area.x, area.y + area.height / 3, area.width, area.height - area.height / 3));
+        return;
+    }
+
+    // Keystroke latency histogram alongside the chart when timings were captured
+    let histogram_width = if has_timings { LATENCY_HISTOGRAM_WIDTH } else { 0 };
+
+    let graph_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(10),
+            Constraint::Length(error_axis_width),
+            Constraint::Length(histogram_width),
+        ])
+        .split(v_center[3]);
+
+    if histogram_width > 0 {
+        render_latency_histogram(f, graph_layout[2], view.keystroke_times);
+    }
+    if view.chart_view == ChartView::Accuracy && has_timings {
+        render_accuracy_chart(f, graph_layout[0], view, [min_time, max_time]);
+        return;
+    }
+
+    let average_data = if view.rolling_average {
+        stats::moving_average(&stats::dedup_samples(wpm_history), ROLLING_AVERAGE_WINDOW)
+    } else {
+        Vec::new()
+    };
+
+    let mut datasets = vec![
+        Dataset::default()
+            .name("WPM")
             .marker(symbols::Marker::Braille)
-            .style(Style::default().fg(Color::Cyan))
+            .style(Style::default().fg(view.theme.chart_wpm))
             .graph_type(GraphType::Line)
             .data(&wpm_data),
         Dataset::default()
             .name("Errors")
             .marker(symbols::Marker::Dot)
-            .style(Style::default().fg(Color::Red))
+            .style(Style::default().fg(view.theme.chart_errors))
             .graph_type(GraphType::Scatter)
             .data(&error_data),
     ];
+    if !average_data.is_empty() {
+        datasets.push(
+            Dataset::default()
+                .name(format!("{}s avg", ROLLING_AVERAGE_WINDOW))
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(Color::Magenta))
+                .graph_type(GraphType::Line)
+                .data(&average_data),
+        );
+    }
 
     let chart = Chart::new(datasets)
         .block(Block::default().title("Performance").borders(Borders::ALL))
@@ -483,34 +1839,804 @@ fn render_performance_view(
     f.render_widget(chart, graph_layout[0]);
 
     if max_error_count > 0.0 {
-        let axis_area = graph_layout[1];
-        let axis_split = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Min(0),
-                Constraint::Length(1),
-                Constraint::Length(1),
-            ])
-            .split(axis_area);
-
-        f.render_widget(
-            Paragraph::new("Errs")
-                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)), 
-            axis_split[0]
-        );
-        
-        f.render_widget(
-            Paragraph::new(format!("{:.0}", max_error_count))
-                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)), 
-            axis_split[1]
+        render_error_axis(f, graph_layout[1], max_error_count as usize, view.theme.chart_errors);
+    }
+}
+
+/// Accuracy so far at each WPM sample, for the results screen's accuracy view.
+fn render_accuracy_chart(f: &mut Frame, area: Rect, view: &PerformanceView, time_bounds: [f64; 2]) {
+    use ratatui::{
+        symbols,
+        widgets::{Axis, Chart, Dataset, GraphType},
+    };
+
+    let times: Vec<f64> = view.wpm_history.iter().map(|&(t, _)| t).collect();
+    let error_times: Vec<f64> = view.error_points.iter().map(|&(t, _)| t).collect();
+    let data = stats::accuracy_over_time(view.keystroke_times, &error_times, &times);
+    let values: Vec<f64> = data.iter().map(|&(_, accuracy)| accuracy).collect();
+    let [low, high] = accuracy_axis_bounds(&values);
+
+    let datasets = vec![Dataset::default()
+        .name("Accuracy")
+        .marker(symbols::Marker::Braille)
+        .style(Style::default().fg(view.theme.chart_wpm))
+        .graph_type(GraphType::Line)
+        .data(&data)];
+    let chart = Chart::new(datasets)
+        .block(Block::default().title("Accuracy").borders(Borders::ALL))
+        .x_axis(
+            Axis::default()
+                .title("Time (s)")
+                .style(Style::default().fg(Color::Gray))
+                .bounds(time_bounds)
+                .labels(vec![
+                    Span::styled(format!("{:.0}", time_bounds[0]), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", time_bounds[1]), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("%")
+                .style(Style::default().fg(Color::Cyan))
+                .bounds([low, high])
+                .labels(vec![
+                    Span::styled(format!("{:.0}", low), Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", high), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
         );
+    f.render_widget(chart, area);
+}
+
+/// The WPM samples to plot, led by a (0, 0) point at the first keystroke when `from_origin` is set.
+///
+/// Only the chart gets the anchor; consistency and the rolling average use the samples as they are.
+fn chart_points(samples: &[(f64, f64)], from_origin: bool) -> Vec<(f64, f64)> {
+    let mut points = Vec::with_capacity(samples.len() + 1);
+    if from_origin && samples.first().is_some_and(|&(t, _)| t > 0.0) {
+        points.push((0.0, 0.0));
+    }
+    points.extend_from_slice(samples);
+    points
+}
+
+/// A keystroke interval as "45ms" or, from a second up, "1.2s".
+fn format_interval(ms: f64) -> String {
+    if ms < 1000.0 {
+        format!("{:.0}ms", ms)
+    } else {
+        format!("{:.1}s", ms / 1000.0)
+    }
+}
+
+/// Upper bound on interpolated points per segment, for runs with very few samples.
+const MAX_INTERPOLATION_RESOLUTION: usize = 40;
+
+/// Samples averaged into each point of the moving-average WPM line.
+const SMOOTHING_WINDOW: usize = 3;
+
+/// The WPM line for a chart `width` cells wide, drawn the way the `chart_smoothing` setting asks.
+/// The average is taken before the origin anchor goes in, so it doesn't drag the start down.
+fn wpm_curve(samples: &[(f64, f64)], from_origin: bool, smoothing: ChartSmoothing, width: u16) -> Vec<(f64, f64)> {
+    let samples = &stats::dedup_samples(samples);
+    match smoothing {
+        ChartSmoothing::Raw => chart_points(samples, from_origin),
+        ChartSmoothing::MovingAverage => chart_points(&stats::moving_average(samples, SMOOTHING_WINDOW), from_origin),
+        ChartSmoothing::Spline => {
+            let points = chart_points(samples, from_origin);
+            interpolate_data(&points, interpolation_resolution(width, points.len()))
+        }
+    }
+}
+
+/// Errors over a run are counted in about this many bins, however long it was.
+const ERROR_BINS: f64 = 25.0;
+/// Narrowest error bin in seconds, so a short run doesn't get a bar per keystroke.
+const MIN_ERROR_BIN: f64 = 0.5;
+
+/// Counts `error_points` into equal bins across a run of `max_time` seconds, returning the bin
+/// width with the counts. The width follows the run's length, so a 15 second test and a two
+/// minute one both get a readable number of bars.
+fn error_bins(error_points: &[(f64, f64)], max_time: f64) -> (f64, Vec<usize>) {
+    let bin_size = (max_time / ERROR_BINS).max(MIN_ERROR_BIN);
+    let num_bins = (max_time / bin_size).ceil() as usize + 1;
+    let mut bins = vec![0; num_bins];
+    for (t, _) in error_points {
+        let bin_index = (t / bin_size).floor() as usize;
+        if bin_index < num_bins {
+            bins[bin_index] += 1;
+        }
+    }
+    (bin_size, bins)
+}
+
+/// Points per segment for [`interpolate_data`] so a chart `width` cells wide gets roughly one
+/// point per braille dot column (two per cell) across its `samples` samples.
+fn interpolation_resolution(width: u16, samples: usize) -> usize {
+    let segments = samples.saturating_sub(1).max(1);
+    let dots = width as usize * 2;
+    dots.div_ceil(segments).clamp(1, MAX_INTERPOLATION_RESOLUTION)
+}
+
+/// Catmull-Rom interpolation of WPM samples for a smooth curve, `resolution` points per segment.
+fn interpolate_data(data: &[(f64, f64)], resolution: usize) -> Vec<(f64, f64)> {
+    if data.len() < 2 {
+        return data.to_vec();
+    }
+
+    let mut smooth_data = Vec::new();
+
+    for i in 0..data.len() - 1 {
+        let p0 = if i == 0 { data[0] } else { data[i - 1] };
+        let p1 = data[i];
+        let p2 = data[i + 1];
+        let p3 = if i + 2 < data.len() { data[i + 2] } else { p2 };
+
+        for t_step in 0..resolution {
+            let t = t_step as f64 / resolution as f64;
+            let t2 = t * t;
+            let t3 = t2 * t;
+
+            let x = 0.5 * (
+                (2.0 * p1.0) +
+                (-p0.0 + p2.0) * t +
+                (2.0 * p0.0 - 5.0 * p1.0 + 4.0 * p2.0 - p3.0) * t2 +
+                (-p0.0 + 3.0 * p1.0 - 3.0 * p2.0 + p3.0) * t3
+            );
+
+            let y = 0.5 * (
+                (2.0 * p1.1) +
+                (-p0.1 + p2.1) * t +
+                (2.0 * p0.1 - 5.0 * p1.1 + 4.0 * p2.1 - p3.1) * t2 +
+                (-p0.1 + 3.0 * p1.1 - 3.0 * p2.1 + p3.1) * t3
+            );
+
+            // A steep climb (e.g. up from the origin anchor) can overshoot below zero
+            smooth_data.push((x, y.max(0.0)));
+        }
+    }
+    if let Some(last) = data.last() {
+        smooth_data.push(*last);
+    }
+
+    smooth_data
+}
+
+/// Overlays the WPM curves of two history runs on one chart, each labelled with its date.
+fn render_comparison_view(
+    f: &mut Frame,
+    area: Rect,
+    first: &TestResult,
+    second: &TestResult,
+    from_origin: bool,
+    smoothing: ChartSmoothing,
+) {
+    use ratatui::{
+        symbols,
+        widgets::{Axis, Chart, Dataset, GraphType, LegendPosition},
+    };
+
+    let first_data = wpm_curve(&first.wpm_history, from_origin, smoothing, area.width);
+    let second_data = wpm_curve(&second.wpm_history, from_origin, smoothing, area.width);
+    let first_label = format!("{} ({:.0} WPM)", first.timestamp.format("%Y-%m-%d %H:%M"), first.wpm);
+    let second_label = format!("{} ({:.0} WPM)", second.timestamp.format("%Y-%m-%d %H:%M"), second.wpm);
+
+    let max_time = first.wpm_history.iter().chain(&second.wpm_history)
+        .map(|(t, _)| *t)
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let max_wpm = first_data.iter().chain(&second_data)
+        .map(|(_, w)| *w)
+        .fold(0.0, f64::max)
+        .max(10.0);
 
-        f.render_widget(
-            Paragraph::new("0")
-                .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)), 
-            axis_split[3]
+    let datasets = vec![
+        Dataset::default()
+            .name(first_label)
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Magenta))
+            .graph_type(GraphType::Line)
+            .data(&first_data),
+        Dataset::default()
+            .name(second_label)
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Cyan))
+            .graph_type(GraphType::Line)
+            .data(&second_data),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(format!("Compare: {} vs {}", first.mode, second.mode)).borders(Borders::ALL))
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Constraint::Percentage(60), Constraint::Percentage(50)))
+        .x_axis(
+            Axis::default()
+                .title("Time (s)")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_time])
+                .labels(vec![
+                    Span::styled("0", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", max_time), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .title("WPM")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, max_wpm])
+                .labels(vec![
+                    Span::styled("0", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{:.0}", max_wpm), Style::default().add_modifier(Modifier::BOLD)),
+                ]),
         );
+
+    f.render_widget(chart, area);
+}
+
+const LATENCY_BUCKET_MS: f64 = 100.0;
+const LATENCY_BUCKETS: usize = 6;
+/// Bars of width 4 with a gap of 1, plus the block's borders.
+const LATENCY_HISTOGRAM_WIDTH: u16 = (LATENCY_BUCKETS as u16 + 1) * 5 + 1;
+
+/// Bar chart of the gaps between keystrokes, with long pauses gathered in a final "slow" bar.
+fn render_latency_histogram(f: &mut Frame, area: Rect, keystroke_times: &[f64]) {
+    let intervals = stats::keystroke_intervals(keystroke_times);
+    let counts = stats::latency_histogram(&intervals, LATENCY_BUCKET_MS, LATENCY_BUCKETS);
+
+    let bars: Vec<Bar> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let label = if i < LATENCY_BUCKETS {
+                format!("{}", (i + 1) * LATENCY_BUCKET_MS as usize)
+            } else {
+                "slow".to_string()
+            };
+            let color = if i < LATENCY_BUCKETS { Color::Cyan } else { Color::Red };
+            Bar::default()
+                .value(count)
+                .label(Line::from(label))
+                .style(Style::default().fg(color))
+        })
+        .collect();
+
+    // Bars widen to fill the whole chart area in the results screen's latency view
+    let slots = LATENCY_BUCKETS as u16 + 1;
+    let bar_width = ((area.width.saturating_sub(2) + 1) / slots).saturating_sub(1).max(4);
+    let chart = BarChart::default()
+        .block(Block::default().title("Key gaps (ms)").borders(Borders::ALL))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan));
+
+    f.render_widget(chart, area);
+}
+
+/// Cells each key of the heatmap takes, including the gap after it.
+const HEATMAP_KEY_WIDTH: usize = 4;
+/// How far each letter row sits to the right of the one above, in cells, as on a real keyboard.
+const HEATMAP_ROW_OFFSETS: [usize; 3] = [0, 1, 3];
+
+/// The letter keys laid out as on the chosen keyboard layout, each shaded by how many errors
+/// landed on it.
+fn render_key_heatmap(f: &mut Frame, area: Rect, view: &PerformanceView) {
+    let counts = stats::key_errors(view.target_text, view.error_positions);
+    let rows = view.keyboard_layout.rows();
+    let max = rows.iter().flat_map(|row| row.chars()).filter_map(|key| counts.get(&key)).max().copied().unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (row, offset) in rows.iter().zip(HEATMAP_ROW_OFFSETS) {
+        let mut spans = vec![Span::raw(" ".repeat(offset))];
+        for key in row.chars() {
+            let count = counts.get(&key).copied().unwrap_or(0);
+            // Thirds of the worst key's count, from a few misses to the most
+            let style = match count {
+                0 => Style::default().fg(Color::DarkGray),
+                _ if count * 3 <= max => Style::default().fg(Color::Black).bg(Color::Yellow),
+                _ if count * 3 <= max * 2 => Style::default().fg(Color::Black).bg(Color::LightRed),
+                _ => Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+            };
+            spans.push(Span::styled(format!(" {} ", key), style));
+            spans.push(Span::raw(" ".repeat(HEATMAP_KEY_WIDTH - 3)));
+        }
+        lines.push(Line::from(spans));
+        lines.push(Line::from(""));
+    }
+
+    let mut missed: Vec<(char, usize)> =
+        rows.iter().flat_map(|row| row.chars()).filter_map(|key| counts.get(&key).map(|&count| (key, count))).collect();
+    // Most missed first, then in keyboard order
+    missed.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    let summary = if missed.is_empty() {
+        "No letter keys missed".to_string()
+    } else {
+        let keys: Vec<String> = missed.iter().take(5).map(|(key, count)| format!("{} ×{}", key, count)).collect();
+        format!("Most missed: {}", keys.join(", "))
+    };
+    lines.push(Line::from(Span::styled(summary, Style::default().fg(Color::Gray))));
+
+    let title = format!("Mistyped keys ({})", view.keyboard_layout);
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    // Centered as a whole, so the rows keep their stagger
+    let keyboard_width = (HEATMAP_ROW_OFFSETS[2] + rows[0].chars().count() * HEATMAP_KEY_WIDTH) as u16;
+    let x = inner.x + inner.width.saturating_sub(keyboard_width) / 2;
+    let keyboard = Rect::new(x, inner.y + inner.height.saturating_sub(lines.len() as u16) / 2, inner.right() - x, inner.height);
+    f.render_widget(Paragraph::new(lines), keyboard.intersection(inner));
+}
+
+/// Draws the secondary y-axis for the error scatter, aligned with the chart's plotting area.
+///
+/// The chart is rendered with a bordered block and x-axis labels, so the plotted rows start one
+/// row below the top border and end above the x-axis line and its labels.
+fn render_error_axis(f: &mut Frame, area: Rect, max_errors: usize, color: Color) {
+    // Border (1) on top, border (1) + x-axis labels (1) + x-axis line (1) at the bottom
+    let graph_height = area.height.saturating_sub(4) as usize;
+    if graph_height < 2 || max_errors == 0 {
+        return;
+    }
+
+    let style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+    let mut rows: Vec<Option<usize>> = vec![None; graph_height];
+
+    // Choose a whole-number step so labels never collide on adjacent rows
+    let max_ticks = (graph_height / 2).clamp(1, 4);
+    let step = max_errors.div_ceil(max_ticks).max(1);
+    let mut value = 0;
+    while value <= max_errors {
+        let offset = ((value as f64 / max_errors as f64) * (graph_height - 1) as f64).round() as usize;
+        rows[graph_height - 1 - offset] = Some(value);
+        value += step;
+    }
+    // Always label the top of the scale, which is where the tallest error bin is plotted
+    if rows[0].is_none() {
+        if graph_height > 1 {
+            rows[1] = None;
+        }
+        rows[0] = Some(max_errors);
+    }
+
+    let mut lines = vec![Line::from(Span::styled("Errs", style))];
+    for row in rows {
+        lines.push(match row {
+            Some(value) => Line::from(vec![
+                Span::styled("├", Style::default().fg(color)),
+                Span::styled(format!(" {}", value), style),
+            ]),
+            None => Line::from(Span::styled("│", Style::default().fg(color))),
+        });
+    }
+
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{backend::TestBackend, layout::Position, Terminal};
+    use typestorm::keymap::{KeyList, Keymap};
+
+    #[test]
+    fn test_mode_badge() {
+        let mut app = App { test_mode: TestMode::Words(25), include_punctuation: true, include_numbers: true, ..Default::default() };
+        assert_eq!(mode_badge(&app), "Words:25 • punct • num");
+        app.test_mode = TestMode::Time(30);
+        app.include_punctuation = false;
+        assert_eq!(mode_badge(&app), "Time:30s • num");
+
+        app.drill_label = Some("Drill: missed words".to_string());
+        assert_eq!(mode_badge(&app), "Time:30s • num");
+        app.mode = AppMode::Typing;
+        assert_eq!(mode_badge(&app), "Drill: missed words");
+
+        // Shown on the right of the header, while there's room next to the title
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|f| render_header(f, &app, f.area())).unwrap();
+        let row: String = (0..80).map(|x| terminal.backend().buffer()[(x, 1)].symbol().to_string()).collect();
+        assert!(row.trim_end_matches('│').trim_end().ends_with("Drill: missed words"));
+        let mut terminal = Terminal::new(TestBackend::new(40, 24)).unwrap();
+        terminal.draw(|f| render_header(f, &app, f.area())).unwrap();
+        let row: String = (0..40).map(|x| terminal.backend().buffer()[(x, 1)].symbol().to_string()).collect();
+        assert!(!row.contains("Drill"));
+    }
+
+    #[test]
+    fn test_footer_hints_follow_keymap() {
+        let mut app = App { mode: AppMode::Results, ..Default::default() };
+        let hints = footer_hints(&app);
+        assert_eq!(hints[0], "<Enter> restart");
+        // Nothing to drill after a clean run
+        assert!(!hints.iter().any(|hint| hint.contains("drill")));
+        app.missed_words.insert(0);
+        assert!(footer_hints(&app).contains(&"<m> drill missed words".to_string()));
+
+        let overrides = [("restart".to_string(), KeyList::One("space".to_string())), ("quit".to_string(), KeyList::Many(vec![]))];
+        app.keymap = Keymap::from_config(&overrides.into_iter().collect()).0;
+        let hints = footer_hints(&app);
+        assert_eq!(hints[0], "<Space> restart");
+        assert!(!hints.iter().any(|hint| hint.contains("quit")));
+
+        app.mode = AppMode::History;
+        assert_eq!(footer_hints(&app)[0], "<Up/Down> navigate");
+    }
+
+    #[test]
+    fn test_mode_with_badges_fits_width() {
+        let mut result: TestResult = serde_json::from_value(serde_json::json!({
+            "timestamp": "2026-03-14T09:26:53+00:00", "mode": "Drill: combos th, ér, ing", "wpm": 50.0,
+            "accuracy": 97.0, "raw_accuracy": 95.0, "consistency": 80.0, "wpm_history": [], "error_points": [],
+            "punctuation": true, "numbers": false
+        }))
+        .unwrap();
+        let text = |result: &TestResult, width| mode_with_badges(result, width).to_string();
+        assert_eq!(text(&result, 40), "Drill: combos th, ér, ing P");
+        assert_eq!(text(&result, 20), "Drill: combos th,… P");
+        // A narrow column still shows a little of the mode, and leaves out the badges that don't fit
+        assert_eq!(text(&result, 6), "Dri… P");
+        assert_eq!(text(&result, 5), "Dril…");
+        result.mode = "Words: 25".to_string();
+        assert_eq!(text(&result, 11), "Words: 25 P");
+        result.numbers = true;
+        assert_eq!(text(&result, 8), "Wor… P #");
+        assert_eq!(text(&result, 7), "Word… P");
+        for width in 0..16 {
+            assert!(mode_with_badges(&result, width).width() <= width);
+        }
+    }
+
+    #[test]
+    fn test_accuracy_gauge() {
+        let thresholds = AccuracyThresholds::default();
+        let color = |accuracy| accuracy_gauge(accuracy, thresholds)[3].style.fg;
+        assert_eq!(color(100.0), Some(Color::Green));
+        assert_eq!(color(95.0), Some(Color::Green));
+        assert_eq!(color(90.0), Some(Color::Yellow));
+        assert_eq!(color(85.0), Some(Color::Yellow));
+        assert_eq!(color(60.0), Some(Color::Red));
+
+        let text = |accuracy| accuracy_gauge(accuracy, thresholds).iter().map(|span| span.content.to_string()).collect::<String>();
+        assert_eq!(text(100.0), "Acc ▰▰▰▰▰ 100%");
+        assert_eq!(text(86.0), "Acc ▰▰▰▰▱ 86%");
+        assert_eq!(text(0.0), "Acc ▱▱▱▱▱ 0%");
+
+        let custom = AccuracyThresholds { green: 80.0, yellow: 50.0 };
+        assert_eq!(accuracy_gauge(85.0, custom)[3].style.fg, Some(Color::Green));
+        assert_eq!(accuracy_gauge(49.0, custom)[3].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_fit_hints() {
+        let hints: Vec<String> = ["<a> one", "<b> two", "<c> three"].iter().map(|h| h.to_string()).collect();
+        assert_eq!(fit_hints(&hints, 40, 2), vec!["<a> one | <b> two | <c> three"]);
+        assert_eq!(fit_hints(&hints, 18, 2), vec!["<a> one | <b> two", "<c> three"]);
+        // Whole hints are dropped rather than cut mid-word
+        assert_eq!(fit_hints(&hints, 18, 1), vec!["<a> one …"]);
+        assert_eq!(fit_hints(&hints, 4, 1), vec!["…"]);
+        assert!(fit_hints(&hints, 40, 0).is_empty());
+    }
+
+    #[test]
+    fn test_error_context_line() {
+        let theme = Theme::default();
+        let text = |line: Line| line.spans.iter().map(|span| span.content.to_string()).collect::<Vec<_>>();
+        // The word the error landed in stands apart from the rest
+        assert_eq!(text(error_context_line("one two three", 5, 40, &theme)), vec!["one ", "t", "w", "o", " three"]);
+        assert_eq!(text(error_context_line("one two", 3, 40, &theme)), vec!["one", "·", "two"]);
+        // Past the end of the text
+        assert_eq!(text(error_context_line("one", 3, 40, &theme)), vec!["one", "·"]);
+        // Trimmed around the error on narrow screens
+        assert_eq!(text(error_context_line("one two three four", 8, 6, &theme)), vec!["wo ", "t", "hr"]);
+    }
+
+    #[test]
+    fn test_small_terminal_shows_notice() {
+        let mut app = App { mode: AppMode::History, ..Default::default() };
+        let mut terminal = Terminal::new(TestBackend::new(30, 6)).unwrap();
+        terminal.draw(|f| draw(f, &mut app, &mut TableState::default())).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Terminal too small"));
+    }
+
+    #[test]
+    fn test_mistyped_space_is_visible() {
+        let mut app = App { mode: AppMode::Typing, ..Default::default() };
+        app.target_text = "ab cd ef".to_string();
+        for c in "abxcd ".chars() {
+            app.type_char(c);
+        }
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        terminal.draw(|f| render_typing(f, &app, f.area())).unwrap();
+
+        let text: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        // Only the space typed as "x" is marked; the correctly typed one stays blank
+        assert!(text.starts_with("ab·cd ef"), "{:?}", text);
+    }
+
+    #[test]
+    fn test_bar_cursor_keeps_text_in_place() {
+        let mut app = App { mode: AppMode::Typing, ..Default::default() };
+        app.settings.cursor_style = CursorStyle::Bar;
+        app.target_text = "ab cd ef".to_string();
+        for c in "ab c".chars() {
+            app.type_char(c);
+        }
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        terminal.draw(|f| render_typing(f, &app, f.area())).unwrap();
+
+        let text: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.starts_with("ab cd ef "), "{:?}", text);
+        // The terminal's cursor sits on the "d" to type next
+        assert_eq!(terminal.get_cursor_position().unwrap(), Position::new(4, 0));
+
+        app.settings.fixed_cursor = true;
+        terminal.draw(|f| render_typing(f, &app, f.area())).unwrap();
+        assert_eq!(terminal.get_cursor_position().unwrap(), Position::new(10, 1));
+        assert_eq!(terminal.backend().buffer()[(10, 1)].symbol(), "d");
+    }
+
+    #[test]
+    fn test_fade_typed() {
+        let mut app = App { mode: AppMode::Typing, ..Default::default() };
+        app.settings.fade_typed_chars = 3;
+        app.target_text = "ab cd ef".to_string();
+        for c in "ax cd e".chars() {
+            app.type_char(c);
+        }
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        terminal.draw(|f| render_typing(f, &app, f.area())).unwrap();
+
+        let dim: Vec<bool> = (0..7)
+            .map(|x| terminal.backend().buffer()[(x, 0)].modifier.contains(Modifier::DIM))
+            .collect();
+        // Correct characters more than three back fade, even inside the finished word; the
+        // error doesn't, and neither does the trail behind the cursor
+        assert_eq!(dim, vec![true, false, true, true, false, false, false]);
+        assert_eq!(terminal.backend().buffer()[(1, 0)].fg, app.theme.incorrect);
+    }
+
+    #[test]
+    fn test_focus_mode_shows_metronome() {
+        let mut app = App { mode: AppMode::Typing, ..Default::default() };
+        app.settings.focus_mode = true;
+        app.target_text = "ab cd".to_string();
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| draw(f, &mut app, &mut TableState::default())).unwrap();
+        let top: String = (0..60).map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string()).collect();
+        assert!(!top.contains("BPM"), "{:?}", top);
+
+        app.settings.metronome_bpm = 120;
+        terminal.draw(|f| draw(f, &mut app, &mut TableState::default())).unwrap();
+        let top: String = (0..60).map(|x| terminal.backend().buffer()[(x, 0)].symbol().to_string()).collect();
+        assert_eq!(top.trim(), "♩ 120 BPM");
+    }
+
+    #[test]
+    fn test_key_heatmap_follows_layout() {
+        let mut app = App { mode: AppMode::Typing, history_disabled: true, ..Default::default() };
+        app.target_text = "jab".to_string();
+        for c in "kab".chars() {
+            app.type_char(c);
+        }
+        app.result_animation_start = None;
+        app.chart_view = ChartView::Keys;
+        let missed_key = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+            terminal.draw(|f| draw(f, app, &mut TableState::default())).unwrap();
+            let buffer = terminal.backend().buffer();
+            let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+            assert!(text.contains("Most missed: j ×1"), "{}", text);
+            let cells: Vec<(u16, u16)> = (0..40)
+                .flat_map(|y| (0..100).map(move |x| (x, y)))
+                .filter(|&(x, y)| buffer[(x, y)].bg == Color::Red)
+                .collect();
+            assert_eq!(cells.len(), 3, "one key, three cells wide");
+            assert_eq!(buffer[(cells[1].0, cells[1].1)].symbol(), "j");
+            cells[1]
+        };
+        let (qwerty_x, qwerty_y) = missed_key(&mut app);
+        // On Dvorak the J key is the third one along the bottom row
+        app.settings.keyboard_layout = KeyboardLayout::Dvorak;
+        let (dvorak_x, dvorak_y) = missed_key(&mut app);
+        assert_eq!(dvorak_y, qwerty_y + 2);
+        assert_eq!(dvorak_x + (6 - 2) * HEATMAP_KEY_WIDTH as u16, qwerty_x + 2);
+    }
+
+    #[test]
+    fn test_instant_test_has_no_chart() {
+        let mut app = App { mode: AppMode::Typing, history_disabled: true, ..Default::default() };
+        app.target_text = "a".to_string();
+        app.type_char('a');
+        assert_eq!(app.mode, AppMode::Results);
+        app.result_animation_start = None;
+        // The one key both starts and ends the test
+        app.end_time = app.start_time;
+        assert!(app.wpm_history.is_empty());
+        assert_eq!(app.calculate_wpm(), 0.0);
+        assert_eq!(app.calculate_accuracy(), 100.0);
+        assert_eq!(app.calculate_consistency(), 100.0);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| draw(f, &mut app, &mut TableState::default())).unwrap();
+        let text: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Not enough data for a chart"), "{}", text);
+        assert!(text.contains("WPM"));
+    }
+
+    #[test]
+    fn test_settings_scroll() {
+        // Everything fits: no scrolling
+        assert_eq!(settings_scroll(20, 31, 40), 0);
+        // Otherwise the selection stays in view, centered away from the ends
+        assert_eq!(settings_scroll(3, 31, 20), 0);
+        assert_eq!(settings_scroll(15, 31, 20), 5);
+        assert_eq!(settings_scroll(30, 31, 20), 11);
+    }
+
+    #[test]
+    fn test_error_bins_follow_duration() {
+        let errors = [(0.2, 0.0), (0.4, 0.0), (14.9, 0.0)];
+        let (size, bins) = error_bins(&errors, 15.0);
+        assert_eq!(size, 0.6);
+        assert_eq!(bins.len(), 26);
+        assert_eq!(bins[0], 2);
+        assert_eq!(bins[24], 1);
+
+        // A two minute run gets wider bins, not four times as many
+        let (size, bins) = error_bins(&errors, 120.0);
+        assert_eq!(size, 4.8);
+        assert_eq!(bins.len(), 26);
+        assert_eq!(bins[0], 2);
+        assert_eq!(bins[3], 1);
+
+        // Very short runs stop at the narrowest bin
+        let (size, bins) = error_bins(&[], 2.0);
+        assert_eq!(size, MIN_ERROR_BIN);
+        assert_eq!(bins, vec![0; 5]);
+    }
+
+    #[test]
+    fn test_review_marks() {
+        use ReviewMark::*;
+        let marks = |target: &str, input: &str| {
+            let target = text::graphemes(target);
+            let input = text::graphemes(input);
+            review_marks(&target, &input).into_iter().map(|(_, mark)| mark).collect::<Vec<_>>()
+        };
+        // Cut off mid-word: the rest of that word is missing, later words aren't shown
+        assert_eq!(marks("ab cd ef", "ax c"), vec![Correct, Wrong, Correct, Correct, Missing]);
+        // Stopped right after a space
+        assert_eq!(marks("ab cd", "ab "), vec![Correct, Correct, Correct]);
+        // Typed past the end
+        assert_eq!(marks("ab", "abc"), vec![Correct, Correct, Extra]);
+        assert!(marks("ab", "").is_empty());
+        // A letter with a combining accent is one mark
+        assert_eq!(marks("e\u{301}a", "ea"), vec![Wrong, Correct]);
+    }
+
+    #[test]
+    fn test_wrap_lines_wide_chars() {
+        // Each of these takes two columns, so only two fit on a 5 column line
+        let chars = text::graphemes("日本語 漢字");
+        assert_eq!(wrap_lines(&chars, 5), vec![0, 2, 4]);
+        assert_eq!(wrap_lines(&chars, 6), vec![0, 4]);
+        // A combining accent takes no column of its own
+        let chars = text::graphemes("cafe\u{301} ok");
+        assert_eq!(wrap_lines(&chars, 5), vec![0, 5]);
+    }
+
+    #[test]
+    fn test_interpolation_resolution() {
+        // 60 samples on an 80 cell chart: 160 dots over 59 segments
+        assert_eq!(interpolation_resolution(80, 60), 3);
+        // Narrow charts with many samples don't interpolate at all
+        assert_eq!(interpolation_resolution(20, 120), 1);
+        // Few samples on a wide chart are capped
+        assert_eq!(interpolation_resolution(200, 2), MAX_INTERPOLATION_RESOLUTION);
+        assert_eq!(interpolation_resolution(80, 0), MAX_INTERPOLATION_RESOLUTION);
+    }
+
+    #[test]
+    fn test_chart_points_origin_anchor() {
+        let samples = [(1.0, 90.0), (2.0, 40.0), (3.0, 45.0)];
+        assert_eq!(chart_points(&samples, false), samples.to_vec());
+        let points = chart_points(&samples, true);
+        assert_eq!(points[0], (0.0, 0.0));
+        assert_eq!(&points[1..], &samples);
+        assert!(chart_points(&[], true).is_empty());
+
+        // The sharp rise and fall must not dip the curve below zero or break time order
+        let smooth = interpolate_data(&points, 10);
+        assert!(smooth.iter().all(|&(_, w)| w >= 0.0));
+        assert!(smooth.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        assert_eq!(smooth.last(), Some(&(3.0, 45.0)));
+    }
+
+    #[test]
+    fn test_wpm_curve_smoothing() {
+        let samples = [(1.0, 90.0), (2.0, 30.0), (3.0, 60.0)];
+        // Raw keeps the samples, only anchored at the origin
+        assert_eq!(wpm_curve(&samples, true, ChartSmoothing::Raw, 80), vec![(0.0, 0.0), (1.0, 90.0), (2.0, 30.0), (3.0, 60.0)]);
+        // The average leaves the anchor out of its windows
+        assert_eq!(wpm_curve(&samples, true, ChartSmoothing::MovingAverage, 80), vec![(0.0, 0.0), (1.0, 90.0), (2.0, 60.0), (3.0, 60.0)]);
+        let spline = wpm_curve(&samples, false, ChartSmoothing::Spline, 80);
+        assert!(spline.len() > samples.len());
+        assert_eq!(spline.last(), Some(&(3.0, 60.0)));
+    }
+
+    #[test]
+    fn test_format_interval() {
+        assert_eq!(format_interval(45.2), "45ms");
+        assert_eq!(format_interval(1200.0), "1.2s");
+    }
+
+    #[test]
+    fn test_accuracy_axis_bounds() {
+        assert_eq!(accuracy_axis_bounds(&[]), [80.0, 100.0]);
+        assert_eq!(accuracy_axis_bounds(&[95.0, 88.5]), [80.0, 100.0]);
+        assert_eq!(accuracy_axis_bounds(&[95.0, 72.0]), [70.0, 100.0]);
+    }
+
+    #[test]
+    fn test_fixed_cursor_window() {
+        let widths = vec![1; 30];
+        // At the start the left half is blank
+        assert_eq!(fixed_cursor_window(&widths, 0, 10), (0, 5, 5));
+        // Further on the text before the cursor fills it
+        assert_eq!(fixed_cursor_window(&widths, 12, 10), (7, 17, 0));
+        // Near the end the line just runs out
+        assert_eq!(fixed_cursor_window(&widths, 29, 10), (24, 30, 0));
+        // Wide characters that don't fit the half are left off
+        assert_eq!(fixed_cursor_window(&[2, 2, 2, 1], 3, 5), (2, 4, 0));
+    }
+
+    #[test]
+    fn test_fixed_cursor_keeps_column() {
+        let mut app = App { mode: AppMode::Typing, ..Default::default() };
+        app.settings.fixed_cursor = true;
+        app.target_text = "the quick brown fox jumps over the lazy dog".to_string();
+        let column_of_cursor = |app: &App| {
+            let mut terminal = Terminal::new(TestBackend::new(21, 5)).unwrap();
+            terminal.draw(|f| render_typing(f, app, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..21).find(|&x| buffer[(x, 2)].bg == app.theme.cursor)
+        };
+        assert_eq!(column_of_cursor(&app), Some(10));
+        for c in "the quick brown".chars() {
+            app.type_char(c);
+        }
+        assert_eq!(column_of_cursor(&app), Some(10));
+    }
+
+    #[test]
+    fn test_wrap_lines() {
+        let chars = text::graphemes("aaa bbb cccc dd");
+        // "aaa bbb " | "cccc dd"
+        assert_eq!(wrap_lines(&chars, 7), vec![0, 8]);
+        // A word longer than the line is split
+        let chars = text::graphemes("abcdefghij k");
+        assert_eq!(wrap_lines(&chars, 4), vec![0, 4, 8]);
+    }
+
+    #[test]
+    fn test_scroll_keeps_cursor_centered() {
+        // 100 words of 4 chars wrap to 50 lines of width 10 ("word word ")
+        let text = vec!["word"; 100].join(" ");
+        let chars = text::graphemes(&text);
+        let starts = wrap_lines(&chars, 10);
+        assert_eq!(starts.len(), 50);
+
+        let height = 5;
+        // Near the start we don't scroll
+        assert_eq!(scroll_offset(0, starts.len(), height), 0);
+        assert_eq!(scroll_offset(2, starts.len(), height), 0);
+        // In the middle the cursor line sits in the middle row
+        assert_eq!(scroll_offset(20, starts.len(), height), 18);
+        // Near the end we stop at the last full screen
+        assert_eq!(scroll_offset(49, starts.len(), height), 45);
+        // Short texts never scroll
+        assert_eq!(scroll_offset(3, 4, height), 0);
     }
 }
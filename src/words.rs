@@ -1,5 +1,7 @@
+use crate::settings::KeyboardLayout;
 use rand::seq::SliceRandom;
 use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 
 pub const WORDS: &[&str] = &[
     "the", "be", "of", "and", "a", "to", "in", "he", "have", "it", "that", "for", "they", "i",
@@ -22,30 +24,126 @@ pub const WORDS: &[&str] = &[
     "play", "stand", "increase", "early", "course", "change", "help", "line",
 ];
 
-pub fn get_random_words(count: usize, punctuation: bool, numbers: bool) -> Vec<String> {
+/// How much punctuation is mixed in when punctuation is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PunctuationLevel {
+    /// The odd period or comma.
+    Light,
+    #[default]
+    Medium,
+    /// Frequent marks, including dashes and quoted words.
+    Heavy,
+}
+
+impl PunctuationLevel {
+    /// Chance that any given word gets a mark.
+    pub fn chance(self) -> f64 {
+        match self {
+            PunctuationLevel::Light => 0.1,
+            PunctuationLevel::Medium => 0.2,
+            PunctuationLevel::Heavy => 0.35,
+        }
+    }
+
+    /// Marks to pick from. A `"` wraps the word in quotes instead of following it.
+    fn marks(self) -> &'static [&'static str] {
+        match self {
+            PunctuationLevel::Light => &[".", ","],
+            PunctuationLevel::Medium => &[".", ",", "!", "?", ";", ":"],
+            PunctuationLevel::Heavy => &[".", ",", "!", "?", ";", ":", "-", "\""],
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PunctuationLevel::Light => PunctuationLevel::Medium,
+            PunctuationLevel::Medium => PunctuationLevel::Heavy,
+            PunctuationLevel::Heavy => PunctuationLevel::Light,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            PunctuationLevel::Light => PunctuationLevel::Heavy,
+            PunctuationLevel::Medium => PunctuationLevel::Light,
+            PunctuationLevel::Heavy => PunctuationLevel::Medium,
+        }
+    }
+}
+
+impl std::fmt::Display for PunctuationLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PunctuationLevel::Light => write!(f, "Light"),
+            PunctuationLevel::Medium => write!(f, "Medium"),
+            PunctuationLevel::Heavy => write!(f, "Heavy"),
+        }
+    }
+}
+
+/// How often numbers replace words, and which numbers can appear.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NumberOptions {
+    /// Chance (0.0 - 1.0) that any given word is replaced by a number.
+    pub density: f64,
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Default for NumberOptions {
+    fn default() -> Self {
+        Self {
+            density: 0.1,
+            min: 0,
+            max: 999,
+        }
+    }
+}
+
+/// How many of the most common [`WORDS`] beginner mode draws from.
+pub const BEGINNER_WORDS: usize = 40;
+
+/// The words a test draws from: all of [`WORDS`], or only the most common ones for beginners.
+pub fn word_pool(beginner: bool) -> &'static [&'static str] {
+    if beginner {
+        &WORDS[..BEGINNER_WORDS]
+    } else {
+        WORDS
+    }
+}
+
+pub fn get_random_words(
+    pool: &[&str],
+    count: usize,
+    punctuation: Option<PunctuationLevel>,
+    numbers: Option<NumberOptions>,
+) -> Vec<String> {
     let mut rng = thread_rng();
-    let mut words: Vec<String> = WORDS
-        .choose_multiple(&mut rng, count)
-        .map(|&s| s.to_string())
-        .collect();
+    // Small pools are gone through again once used up, reshuffled each time
+    let mut words: Vec<String> = Vec::with_capacity(count);
+    while words.len() < count && !pool.is_empty() {
+        let needed = count - words.len();
+        words.extend(pool.choose_multiple(&mut rng, needed).map(|&s| s.to_string()));
+    }
 
-    if numbers {
+    if let Some(options) = numbers {
+        let density = options.density.clamp(0.0, 1.0);
+        let (min, max) = (options.min.min(options.max), options.min.max(options.max));
         for word in words.iter_mut() {
-            if rng.gen_bool(0.1) { // 10% chance to be a number
-                *word = rng.gen_range(0..1000).to_string();
+            if rng.gen_bool(density) {
+                *word = rng.gen_range(min..=max).to_string();
             }
         }
     }
 
-    if punctuation {
-        let puncts = [".", ",", "!", "?", ";", ":"];
+    if let Some(level) = punctuation {
         for word in words.iter_mut() {
-            if rng.gen_bool(0.2) { // 20% chance to have punctuation
-                let p = puncts.choose(&mut rng).unwrap();
-                word.push_str(p);
-                // Capitalize next word if it's a sentence ender (simplified: just capitalize this one if needed or next? 
-                // For simplicity in a type test, we usually just append punctuation. 
-                // Real sentence structure is harder. Let's just append for now.)
+            if rng.gen_bool(level.chance()) {
+                match *level.marks().choose(&mut rng).unwrap() {
+                    "\"" => *word = format!("\"{}\"", word),
+                    mark => word.push_str(mark),
+                }
             }
         }
         // Capitalize first word
@@ -61,3 +159,331 @@ pub fn get_random_words(count: usize, punctuation: bool, numbers: bool) -> Vec<S
     
     words
 }
+
+/// Expected characters per generated word (not counting spaces) for the given options.
+///
+/// Words are drawn evenly from `pool`, so this is exact up to the randomness of which words,
+/// numbers and punctuation actually come up.
+pub fn average_word_length(pool: &[&str], punctuation: Option<PunctuationLevel>, numbers: Option<NumberOptions>) -> f64 {
+    let word_chars: usize = pool.iter().map(|w| w.len()).sum();
+    let mut average = word_chars as f64 / pool.len().max(1) as f64;
+
+    if let Some(options) = numbers {
+        let density = options.density.clamp(0.0, 1.0);
+        let (min, max) = (options.min.min(options.max), options.min.max(options.max));
+        average = average * (1.0 - density) + average_digits(min, max) * density;
+    }
+    if let Some(level) = punctuation {
+        let marks = level.marks();
+        let mark_chars: usize = marks.iter().map(|m| if *m == "\"" { 2 } else { m.len() }).sum();
+        average += level.chance() * mark_chars as f64 / marks.len() as f64;
+    }
+    average
+}
+
+/// Mean number of digits over the numbers in `min..=max`.
+fn average_digits(min: u32, max: u32) -> f64 {
+    let (min, max) = (min as u64, max as u64);
+    let mut total_digits = 0;
+    let mut low = 0;
+    let mut high = 9;
+    let mut digits = 1;
+    while low <= max {
+        let from = low.max(min);
+        let to = high.min(max);
+        if from <= to {
+            total_digits += (to - from + 1) * digits;
+        }
+        low = high + 1;
+        high = high * 10 + 9;
+        digits += 1;
+    }
+    total_digits as f64 / (max - min + 1) as f64
+}
+
+/// Whole sentences for the sentences text source, with their own capitals and punctuation.
+pub const SENTENCES: &[&str] = &[
+    "The early train was late again, so we walked to the office instead.",
+    "She opened the window and let the cold morning air fill the room.",
+    "If you want to learn something well, try to teach it to someone else.",
+    "The old bridge over the river was closed for repairs all summer.",
+    "He wrote the address on the back of an envelope and lost it by noon.",
+    "Most of the village was still asleep when the bakery opened its doors.",
+    "We planted tomatoes, beans and a row of sunflowers along the fence.",
+    "Do you remember where we left the spare key to the shed?",
+    "A good map is useful, but it is no substitute for asking the locals.",
+    "The meeting ran long, and nobody could agree on what to do next.",
+    "Rain fell on the tin roof all night, steady and soft.",
+    "Her grandfather kept every letter he ever received in a wooden box.",
+    "The library is quiet in the afternoon, which makes it a good place to think.",
+    "After the storm passed, the children ran outside to look for rainbows.",
+    "It takes practice to type quickly without looking at the keyboard.",
+    "The recipe calls for two eggs, a cup of flour and a pinch of salt.",
+    "Why does the cat always sit on the one chair we want to use?",
+    "They painted the kitchen yellow, and it has felt warmer ever since.",
+    "The museum was free on Sundays, so it was always crowded.",
+    "He checked the weather twice before deciding to leave his umbrella at home.",
+    "Small changes, made every day, add up to large results over time.",
+    "The ferry crossed the bay in twenty minutes on a calm day.",
+    "We laughed so hard at dinner that the waiter came to see what was wrong.",
+    "Please turn off the lights and lock the door when you leave.",
+    "The mountain looked close, but the walk to its foot took all morning.",
+    "Nobody knew who had left the flowers on the doorstep.",
+    "The band played until midnight, and the crowd asked for one more song.",
+    "Reading before bed helps some people sleep, while others stay up for hours.",
+    "The garden was overgrown, but the roses still bloomed every June.",
+    "She keeps a notebook in her pocket to write down ideas as they come.",
+    "The shop on the corner sells fresh bread, newspapers and cheap umbrellas.",
+    "Have you ever tried to fold a map back the way it came?",
+    "The clock in the hall has been five minutes fast for as long as I can remember.",
+    "A thick fog rolled in from the sea and hid the harbor from view.",
+    "He fixed the old bicycle with a few spare parts and a lot of patience.",
+    "The test was hard, but she had studied well and finished early.",
+    "We stopped at a small cafe by the road and ordered soup and tea.",
+    "The dog waited by the door every evening for its owner to come home.",
+    "Learning a new language is easier when you use it every single day.",
+    "The stars were bright that night, far from the lights of the city.",
+];
+
+/// Whole sentences from [`SENTENCES`] in random order, split into words, stopping at the end of
+/// the sentence that reaches `count` words; the corpus is gone through again once used up.
+pub fn get_sentences(count: usize) -> Vec<String> {
+    let mut rng = thread_rng();
+    let mut words: Vec<String> = Vec::with_capacity(count + 20);
+    while words.len() < count {
+        let mut sentences = SENTENCES.to_vec();
+        sentences.shuffle(&mut rng);
+        for sentence in sentences {
+            if words.len() >= count {
+                break;
+            }
+            words.extend(sentence.split(' ').map(str::to_string));
+        }
+    }
+    words
+}
+
+/// Average characters per word in [`SENTENCES`], punctuation included.
+pub fn sentence_word_length() -> f64 {
+    let (chars, words) = SENTENCES.iter().flat_map(|s| s.split(' ')).fold((0, 0), |(c, w), word| (c + word.len(), w + 1));
+    chars as f64 / words as f64
+}
+
+/// Repeats each word `repeats` times and shuffles the result, for drilling specific words.
+pub fn drill_words(words: &[String], repeats: usize) -> Vec<String> {
+    let mut rng = thread_rng();
+    let mut drill: Vec<String> = words
+        .iter()
+        .flat_map(|w| std::iter::repeat_n(w.clone(), repeats))
+        .collect();
+    drill.shuffle(&mut rng);
+    drill
+}
+
+/// Letter combinations the combo drill can practice: common bigrams, plus the trigrams and
+/// endings that tend to trip fingers up.
+pub const COMBOS: &[&str] = &[
+    "th", "he", "in", "er", "an", "re", "ou", "st", "ch", "sh", "wh", "qu", "ck", "ing", "tion", "ght",
+];
+
+/// `count` words that each contain one of `combos` (all of [`COMBOS`] when empty), for drilling
+/// letter combinations rather than whole words.
+///
+/// About half are real words from [`WORDS`] containing the combination; the rest are made-up
+/// words built around it, so rare combinations like "qu" still come up as often as common ones.
+pub fn combo_words(combos: &[String], count: usize) -> Vec<String> {
+    let combos: Vec<&str> = if combos.is_empty() {
+        COMBOS.to_vec()
+    } else {
+        combos.iter().map(String::as_str).filter(|c| !c.is_empty()).collect()
+    };
+    let mut rng = thread_rng();
+    let mut words = Vec::with_capacity(count);
+    for _ in 0..count {
+        let Some(&combo) = combos.choose(&mut rng) else {
+            break;
+        };
+        let real: Vec<&str> = WORDS.iter().copied().filter(|w| w.contains(combo)).collect();
+        match real.choose(&mut rng) {
+            Some(word) if rng.gen_bool(0.5) => words.push(word.to_string()),
+            _ => words.push(pseudo_word(combo, &mut rng)),
+        }
+    }
+    words
+}
+
+const ONSETS: &[&str] = &["b", "d", "f", "g", "l", "m", "p", "r", "s", "t", "w"];
+const VOWELS: &[&str] = &["a", "e", "i", "o", "u"];
+
+/// A consonant followed by a vowel.
+fn syllable(rng: &mut impl Rng) -> String {
+    format!("{}{}", ONSETS.choose(rng).unwrap(), VOWELS.choose(rng).unwrap())
+}
+
+/// A pronounceable made-up word around `combo`, e.g. "quat" or "mation".
+fn pseudo_word(combo: &str, rng: &mut impl Rng) -> String {
+    const CODAS: &[&str] = &["", "d", "l", "m", "n", "s", "t"];
+    // Endings like "ing" and "ck" only come at the end of a word
+    if combo.len() >= 3 || combo == "ck" {
+        return format!("{}{}", syllable(rng), combo);
+    }
+    let prefix = if rng.gen_bool(0.5) { syllable(rng) } else { String::new() };
+    format!("{}{}{}{}", prefix, combo, VOWELS.choose(rng).unwrap(), CODAS.choose(rng).unwrap())
+}
+
+/// Classic touch-typing exercises with a fixed text rather than random words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrillPreset {
+    Alphabet,
+    HomeRow,
+    TopRow,
+    BottomRow,
+    NumberRow,
+}
+
+impl DrillPreset {
+    pub const ALL: [DrillPreset; 5] = [
+        DrillPreset::Alphabet,
+        DrillPreset::HomeRow,
+        DrillPreset::TopRow,
+        DrillPreset::BottomRow,
+        DrillPreset::NumberRow,
+    ];
+
+    /// Name shown in the drills menu, and (lowercased) in the mode the run is saved under.
+    pub fn name(self) -> &'static str {
+        match self {
+            DrillPreset::Alphabet => "Alphabet",
+            DrillPreset::HomeRow => "Home row",
+            DrillPreset::TopRow => "Top row",
+            DrillPreset::BottomRow => "Bottom row",
+            DrillPreset::NumberRow => "Number row",
+        }
+    }
+
+    /// `count` groups of the exercise, e.g. "asdf jkl; fdsa ;lkj asdfjkl;" for the QWERTY home
+    /// row. Rows follow the keyboard `layout`; the alphabet runs are rounded so the text is about
+    /// as long as `count` words.
+    pub fn text(self, layout: KeyboardLayout, count: usize) -> String {
+        let count = count.max(1);
+        let row = match self {
+            DrillPreset::Alphabet => {
+                let alphabet = "abcdefghijklmnopqrstuvwxyz";
+                return vec![alphabet; count.div_ceil(5)].join(" ");
+            }
+            DrillPreset::HomeRow => layout.rows()[1],
+            DrillPreset::TopRow => layout.rows()[0],
+            DrillPreset::BottomRow => layout.rows()[2],
+            DrillPreset::NumberRow => "1234567890",
+        };
+        // Each hand's four fingers out and back, then the two together
+        let left: String = row.chars().take(4).collect();
+        let right: String = row.chars().skip(6).collect();
+        let reversed = |keys: &str| keys.chars().rev().collect::<String>();
+        let groups = [left.clone(), right.clone(), reversed(&left), reversed(&right), format!("{}{}", left, right)];
+        groups.iter().cycle().take(count).cloned().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drill_presets() {
+        assert_eq!(DrillPreset::HomeRow.text(KeyboardLayout::Qwerty, 5), "asdf jkl; fdsa ;lkj asdfjkl;");
+        assert_eq!(DrillPreset::HomeRow.text(KeyboardLayout::Dvorak, 2), "aoeu htns");
+        assert_eq!(DrillPreset::NumberRow.text(KeyboardLayout::Qwerty, 6), "1234 7890 4321 0987 12347890 1234");
+        assert_eq!(DrillPreset::Alphabet.text(KeyboardLayout::Qwerty, 10), "abcdefghijklmnopqrstuvwxyz abcdefghijklmnopqrstuvwxyz");
+        // Fixed, not random
+        assert_eq!(DrillPreset::TopRow.text(KeyboardLayout::Colemak, 25), DrillPreset::TopRow.text(KeyboardLayout::Colemak, 25));
+    }
+
+    #[test]
+    fn test_number_options() {
+        let options = NumberOptions {
+            density: 1.0,
+            min: 10_000,
+            max: 99_999,
+        };
+        let words = get_random_words(WORDS, 20, None, Some(options));
+        assert_eq!(words.len(), 20);
+        for word in &words {
+            let n: u32 = word.parse().expect("every word should be a number");
+            assert!((10_000..=99_999).contains(&n));
+        }
+
+        let none = NumberOptions { density: 0.0, ..Default::default() };
+        let words = get_random_words(WORDS, 20, None, Some(none));
+        assert!(words.iter().all(|w| w.parse::<u32>().is_err()));
+    }
+
+    #[test]
+    fn test_beginner_pool() {
+        let pool = word_pool(true);
+        assert_eq!(pool.len(), BEGINNER_WORDS);
+        // More words than the pool holds still get generated, all from the pool
+        let words = get_random_words(pool, 100, None, None);
+        assert_eq!(words.len(), 100);
+        assert!(words.iter().all(|w| pool.contains(&w.as_str())));
+        assert!(average_word_length(pool, None, None) < average_word_length(WORDS, None, None));
+    }
+
+    #[test]
+    fn test_punctuation_level_density() {
+        let share = |level| {
+            let words: Vec<String> = (0..20)
+                .flat_map(|_| get_random_words(WORDS, WORDS.len(), Some(level), None))
+                .collect();
+            let marked = words.iter().filter(|w| w.chars().any(|c| !c.is_alphanumeric())).count();
+            marked as f64 / words.len() as f64
+        };
+        let light = share(PunctuationLevel::Light);
+        let medium = share(PunctuationLevel::Medium);
+        let heavy = share(PunctuationLevel::Heavy);
+        assert!(light < medium && medium < heavy, "{} {} {}", light, medium, heavy);
+        assert!((heavy - PunctuationLevel::Heavy.chance()).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_average_word_length() {
+        let plain = average_word_length(WORDS, None, None);
+        assert!(plain > 3.0 && plain < 6.0);
+        let medium = average_word_length(WORDS, Some(PunctuationLevel::Medium), None);
+        assert!((medium - plain - PunctuationLevel::Medium.chance()).abs() < 1e-9);
+
+        let all_numbers = NumberOptions { density: 1.0, min: 0, max: 99 };
+        assert!((average_word_length(WORDS, None, Some(all_numbers)) - 1.9).abs() < 1e-9);
+        assert_eq!(average_digits(100, 999), 3.0);
+    }
+
+    #[test]
+    fn test_get_sentences() {
+        // Whole sentences only, so the text ends on a full stop or question mark
+        for count in [1, 10, 25, 100] {
+            let words = get_sentences(count);
+            assert!(words.len() >= count);
+            assert!(words.last().unwrap().ends_with(['.', '?']));
+            let text = words.join(" ");
+            assert!(SENTENCES.iter().any(|s| text.starts_with(s)));
+            // Stops at the first sentence that reaches the count
+            let longest = SENTENCES.iter().map(|s| s.split(' ').count()).max().unwrap();
+            assert!(words.len() < count + longest);
+        }
+        assert!(get_sentences(0).is_empty());
+        assert!(sentence_word_length() > 3.0);
+    }
+
+    #[test]
+    fn test_combo_words() {
+        let combos = vec!["qu".to_string(), "tion".to_string()];
+        let words = combo_words(&combos, 50);
+        assert_eq!(words.len(), 50);
+        assert!(words.iter().all(|w| w.contains("qu") || w.contains("tion")));
+        assert!(words.iter().all(|w| !w.starts_with("tion")));
+
+        // No selection drills every combination
+        let words = combo_words(&[], 50);
+        assert!(words.iter().all(|w| COMBOS.iter().any(|c| w.contains(c))));
+    }
+}